@@ -3,6 +3,12 @@ use std::fs::File;
 
 fn main() {
     println!("cargo:rerun-if-changed=build.rs");
+
+    // The OpenGL bindings are only used by the windowed frontend.
+    if std::env::var_os("CARGO_FEATURE_WINDOW").is_none() {
+        return;
+    }
+
     let mut file = File::create("./src/render/bindings.rs").unwrap();
 
     let registry = Registry::new(
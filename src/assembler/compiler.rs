@@ -0,0 +1,697 @@
+use crate::assembler::{tokenize, SourceMap, Token};
+use std::collections::{HashMap, VecDeque};
+use thiserror::Error;
+
+/// The address programs are assembled to run at.
+const PROGRAM_START: u16 = 0x200;
+
+/// The number of macro expansions after which a macro is assumed to be
+/// expanding itself forever.
+const MAX_MACRO_EXPANSIONS: usize = 10_000;
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+#[error("{line}:{column}: {message}")]
+pub struct AssembleError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl AssembleError {
+    fn new(token: &Token, message: impl Into<String>) -> Self {
+        Self {
+            line: token.line,
+            column: token.column,
+            message: message.into(),
+        }
+    }
+}
+
+type Result<T> = std::result::Result<T, AssembleError>;
+
+/// An assembled program, ready to be given to `Chip::load_rom`.
+#[derive(Clone, Debug)]
+pub struct Program {
+    /// The bytes of the program, starting at 0x200.
+    pub rom: Vec<u8>,
+    pub labels: HashMap<String, u16>,
+    /// The addresses of `:breakpoint` directives, and their names.
+    pub breakpoints: Vec<(u16, String)>,
+    pub source_map: SourceMap,
+}
+
+/// Assembles Octo source code into a program.
+pub fn assemble(source: &str) -> Result<Program> {
+    Compiler::new(source).compile()
+}
+
+struct Macro {
+    parameters: Vec<String>,
+    body: Vec<Token>,
+}
+
+/// Where a forward reference to a label is written once the label is known.
+struct Fixup {
+    address: u16,
+    label: Token,
+    long: bool,
+}
+
+struct Loop {
+    start: u16,
+    token: Token,
+    /// The jumps out of the loop emitted by `while`, to be pointed past the
+    /// loop's `again`.
+    exits: Vec<u16>,
+}
+
+/// A condition that is tested by skipping the next instruction.
+struct Condition {
+    /// Skips the next instruction if the condition is true.
+    skip_if_true: u16,
+    /// Skips the next instruction if the condition is false.
+    skip_if_false: u16,
+}
+
+struct Compiler {
+    tokens: VecDeque<Token>,
+    rom: Vec<u8>,
+    here: u16,
+    labels: HashMap<String, u16>,
+    constants: HashMap<String, i32>,
+    aliases: HashMap<String, u8>,
+    macros: HashMap<String, Macro>,
+    fixups: Vec<Fixup>,
+    loops: Vec<Loop>,
+    /// The jumps emitted by `begin` and `else`, to be pointed at the matching
+    /// `else` or `end`.
+    branches: Vec<(u16, Token)>,
+    breakpoints: Vec<(u16, String)>,
+    source_map: SourceMap,
+    /// The line of the statement being compiled.
+    line: usize,
+    expansions: usize,
+}
+
+impl Compiler {
+    fn new(source: &str) -> Self {
+        Self {
+            tokens: tokenize(source).into(),
+            rom: Vec::new(),
+            here: PROGRAM_START,
+            labels: HashMap::new(),
+            constants: HashMap::new(),
+            aliases: HashMap::new(),
+            macros: HashMap::new(),
+            fixups: Vec::new(),
+            loops: Vec::new(),
+            branches: Vec::new(),
+            breakpoints: Vec::new(),
+            source_map: SourceMap::new(),
+            line: 1,
+            expansions: 0,
+        }
+    }
+
+    fn compile(mut self) -> Result<Program> {
+        // Execution starts at 0x200, so unless the program starts with main,
+        // a jump to main is placed there, like Octo does.
+        let starts_with_main = self.tokens.front().map(|t| t.text.as_str()) == Some(":")
+            && self.tokens.get(1).map(|t| t.text.as_str()) == Some("main");
+        if !starts_with_main {
+            let main = Token { text: "main".to_string(), line: 1, column: 1 };
+            self.fixups.push(Fixup { address: self.here, label: main, long: false });
+            self.emit_word(0x1000);
+        }
+
+        while let Some(token) = self.tokens.pop_front() {
+            self.line = token.line;
+            self.statement(token)?;
+        }
+
+        if let Some(unclosed) = self.loops.last() {
+            return Err(AssembleError::new(&unclosed.token, "loop is missing its again"));
+        }
+        if let Some((_, token)) = self.branches.last() {
+            return Err(AssembleError::new(token, "begin is missing its end"));
+        }
+
+        for fixup in std::mem::take(&mut self.fixups) {
+            let target = match self.labels.get(&fixup.label.text) {
+                Some(target) => *target,
+                None if fixup.label.text == "main" => {
+                    return Err(AssembleError::new(&fixup.label, "this program is missing a main label"))
+                }
+                None => {
+                    return Err(AssembleError::new(
+                        &fixup.label,
+                        format!("undefined label '{}'", fixup.label.text),
+                    ))
+                }
+            };
+
+            if fixup.long {
+                self.write_word(fixup.address, target);
+            } else {
+                if target > 0xFFF {
+                    return Err(AssembleError::new(
+                        &fixup.label,
+                        format!("'{}' is past 0xFFF, use i := long to reach it", fixup.label.text),
+                    ));
+                }
+                let opcode = self.read_word(fixup.address) & 0xF000;
+                self.write_word(fixup.address, opcode | target);
+            }
+        }
+
+        Ok(Program {
+            rom: self.rom,
+            labels: self.labels,
+            breakpoints: self.breakpoints,
+            source_map: self.source_map,
+        })
+    }
+
+    fn statement(&mut self, token: Token) -> Result<()> {
+        match token.text.as_str() {
+            ":" => {
+                let name = self.next(&token)?;
+                if self.labels.insert(name.text.clone(), self.here).is_some() {
+                    return Err(AssembleError::new(&name, format!("the label '{}' is already defined", name.text)));
+                }
+            }
+            ":alias" => {
+                let name = self.next(&token)?;
+                let register = self.next(&token)?;
+                let register = self.register(&register)?;
+                self.aliases.insert(name.text, register);
+            }
+            ":const" => {
+                let name = self.next(&token)?;
+                let value = self.next(&token)?;
+                let value = self
+                    .literal(&value)
+                    .ok_or_else(|| AssembleError::new(&value, format!("'{}' is not a number", value.text)))?;
+                self.constants.insert(name.text, value);
+            }
+            ":macro" => self.define_macro(&token)?,
+            ":org" => {
+                let address = self.next(&token)?;
+                let value = self.value(&address)?;
+                if value < PROGRAM_START {
+                    return Err(AssembleError::new(&address, "programs can't be placed below 0x200"));
+                }
+                self.here = value;
+            }
+            ":byte" => {
+                let value = self.next(&token)?;
+                let value = self.byte(&value)?;
+                self.emit_byte(value);
+            }
+            ":breakpoint" => {
+                let name = self.next(&token)?;
+                self.breakpoints.push((self.here, name.text));
+            }
+            ":call" | ":calls" | ":unpack" | ":next" | ":stringmode" => {
+                return Err(AssembleError::new(&token, format!("the Octo directive '{}' is not supported", token.text)))
+            }
+            "clear" => self.emit_word(0x00E0),
+            "return" | ";" => self.emit_word(0x00EE),
+            "scroll-right" => self.emit_word(0x00FB),
+            "scroll-left" => self.emit_word(0x00FC),
+            "exit" => self.emit_word(0x00FD),
+            "lores" => self.emit_word(0x00FE),
+            "hires" => self.emit_word(0x00FF),
+            "audio" => self.emit_word(0xF002),
+            "scroll-down" | "scroll-up" | "plane" => {
+                let n = self.next(&token)?;
+                let n = self.nibble(&n)?;
+                let opcode = match token.text.as_str() {
+                    "scroll-down" => 0x00C0 | n,
+                    "scroll-up" => 0x00D0 | n,
+                    _ => 0xF001 | n << 8,
+                };
+                self.emit_word(opcode);
+            }
+            "jump" => self.address_instruction(&token, 0x1000)?,
+            "jump0" => self.address_instruction(&token, 0xB000)?,
+            "native" => self.address_instruction(&token, 0x0000)?,
+            "bcd" => self.x_instruction(&token, 0xF033)?,
+            "saveflags" => self.x_instruction(&token, 0xF075)?,
+            "loadflags" => self.x_instruction(&token, 0xF085)?,
+            "save" | "load" => {
+                let x = self.next(&token)?;
+                let x = self.register(&x)? as u16;
+                if self.peek_is("-") {
+                    self.tokens.pop_front();
+                    let y = self.next(&token)?;
+                    let y = self.register(&y)? as u16;
+                    let suffix = if token.text == "save" { 0x2 } else { 0x3 };
+                    self.emit_word(0x5000 | x << 8 | y << 4 | suffix);
+                } else {
+                    let suffix = if token.text == "save" { 0x55 } else { 0x65 };
+                    self.emit_word(0xF000 | x << 8 | suffix);
+                }
+            }
+            "sprite" => {
+                let x = self.next(&token)?;
+                let x = self.register(&x)? as u16;
+                let y = self.next(&token)?;
+                let y = self.register(&y)? as u16;
+                let n = self.next(&token)?;
+                let n = self.nibble(&n)?;
+                self.emit_word(0xD000 | x << 8 | y << 4 | n);
+            }
+            "delay" | "buzzer" | "pitch" => {
+                self.expect(&token, ":=")?;
+                let suffix = match token.text.as_str() {
+                    "delay" => 0x15,
+                    "buzzer" => 0x18,
+                    _ => 0x3A,
+                };
+                self.x_instruction(&token, 0xF000 | suffix)?;
+            }
+            "i" => self.i_assignment(&token)?,
+            "if" => self.if_statement(&token)?,
+            "else" => {
+                let (jump, _) = self
+                    .branches
+                    .pop()
+                    .ok_or_else(|| AssembleError::new(&token, "else without a matching begin"))?;
+                self.branches.push((self.here, token.clone()));
+                self.emit_word(0x1000);
+                self.patch_jump(jump, self.here, &token)?;
+            }
+            "end" => {
+                let (jump, _) = self
+                    .branches
+                    .pop()
+                    .ok_or_else(|| AssembleError::new(&token, "end without a matching begin"))?;
+                self.patch_jump(jump, self.here, &token)?;
+            }
+            "loop" => self.loops.push(Loop { start: self.here, token, exits: Vec::new() }),
+            "while" => {
+                if self.loops.is_empty() {
+                    return Err(AssembleError::new(&token, "while outside of a loop"));
+                }
+                let condition = self.condition(&token)?;
+                self.emit_word(condition.skip_if_true);
+                let exit = self.here;
+                self.emit_word(0x1000);
+                self.loops.last_mut().unwrap().exits.push(exit);
+            }
+            "again" => {
+                let frame = self
+                    .loops
+                    .pop()
+                    .ok_or_else(|| AssembleError::new(&token, "again without a matching loop"))?;
+                let jump = Self::jump(frame.start, &frame.token)?;
+                self.emit_word(jump);
+                for exit in frame.exits {
+                    self.patch_jump(exit, self.here, &token)?;
+                }
+            }
+            text if self.macros.contains_key(text) => self.expand_macro(&token)?,
+            _ if self.try_register(&token).is_some() => self.register_assignment(&token)?,
+            _ if self.literal(&token).is_some() => {
+                let value = self.byte(&token)?;
+                self.emit_byte(value);
+            }
+            text if text.starts_with(':') => {
+                return Err(AssembleError::new(&token, format!("unsupported directive '{}'", text)))
+            }
+            // Anything else is the name of a subroutine to call.
+            _ => {
+                self.fixups.push(Fixup { address: self.here, label: token, long: false });
+                self.emit_word(0x2000);
+            }
+        }
+        Ok(())
+    }
+
+    fn define_macro(&mut self, directive: &Token) -> Result<()> {
+        let name = self.next(directive)?;
+
+        let mut parameters = Vec::new();
+        loop {
+            let token = self.next(directive)?;
+            if token.text == "{" {
+                break;
+            }
+            parameters.push(token.text);
+        }
+
+        let mut body = Vec::new();
+        let mut depth = 1;
+        loop {
+            let token = self
+                .tokens
+                .pop_front()
+                .ok_or_else(|| AssembleError::new(&name, "macro is missing its closing }"))?;
+            match token.text.as_str() {
+                "{" => depth += 1,
+                "}" => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                _ => {}
+            }
+            body.push(token);
+        }
+
+        self.macros.insert(name.text, Macro { parameters, body });
+        Ok(())
+    }
+
+    /// Replaces a macro invocation with the macro's body. The expanded tokens
+    /// take the position of the invocation, so errors and the source map point
+    /// at the line the macro was used on.
+    fn expand_macro(&mut self, invocation: &Token) -> Result<()> {
+        self.expansions += 1;
+        if self.expansions > MAX_MACRO_EXPANSIONS {
+            return Err(AssembleError::new(invocation, "too many macro expansions, is a macro expanding itself?"));
+        }
+
+        let (parameters, body) = match self.macros.get(&invocation.text) {
+            Some(definition) => (definition.parameters.clone(), definition.body.clone()),
+            None => return Ok(()),
+        };
+
+        let mut arguments = HashMap::new();
+        for parameter in parameters {
+            let argument = self.next(invocation)?;
+            arguments.insert(parameter, argument.text);
+        }
+
+        for token in body.into_iter().rev() {
+            let text = arguments.get(&token.text).cloned().unwrap_or(token.text);
+            self.tokens.push_front(Token { text, ..invocation.clone() });
+        }
+        Ok(())
+    }
+
+    fn i_assignment(&mut self, i: &Token) -> Result<()> {
+        let operator = self.next(i)?;
+        match operator.text.as_str() {
+            "+=" => self.x_instruction(&operator, 0xF01E),
+            ":=" => {
+                if self.peek_is("hex") || self.peek_is("bighex") {
+                    let font = self.next(&operator)?;
+                    let opcode = if font.text == "hex" { 0xF029 } else { 0xF030 };
+                    self.x_instruction(&font, opcode)
+                } else if self.peek_is("long") {
+                    let long = self.next(&operator)?;
+                    let target = self.next(&long)?;
+                    self.emit_word(0xF000);
+                    match self.literal(&target) {
+                        Some(_) => {
+                            let address = self.value(&target)?;
+                            self.write_word(self.here, address);
+                        }
+                        None => self.fixups.push(Fixup { address: self.here, label: target, long: true }),
+                    }
+                    self.here = self.here.wrapping_add(2);
+                    Ok(())
+                } else {
+                    self.address_instruction(&operator, 0xA000)
+                }
+            }
+            _ => Err(AssembleError::new(&operator, format!("expected := or += after i, found '{}'", operator.text))),
+        }
+    }
+
+    fn register_assignment(&mut self, register: &Token) -> Result<()> {
+        let x = self.register(register)? as u16;
+        let operator = self.next(register)?;
+        let source = self.next(&operator)?;
+
+        let with_register = |suffix: u16, compiler: &Self| -> Result<u16> {
+            let y = compiler.register(&source)? as u16;
+            Ok(0x8000 | x << 8 | y << 4 | suffix)
+        };
+
+        let opcode = match operator.text.as_str() {
+            ":=" => match source.text.as_str() {
+                "random" => {
+                    let mask = self.next(&source)?;
+                    0xC000 | x << 8 | self.byte(&mask)? as u16
+                }
+                "delay" => 0xF007 | x << 8,
+                "key" => 0xF00A | x << 8,
+                _ if self.try_register(&source).is_some() => with_register(0x0, self)?,
+                _ => 0x6000 | x << 8 | self.byte(&source)? as u16,
+            },
+            "+=" if self.try_register(&source).is_some() => with_register(0x4, self)?,
+            "+=" => 0x7000 | x << 8 | self.byte(&source)? as u16,
+            "-=" if self.try_register(&source).is_some() => with_register(0x5, self)?,
+            "-=" => 0x7000 | x << 8 | (self.byte(&source)? as u16).wrapping_neg() & 0xFF,
+            "=-" => with_register(0x7, self)?,
+            "|=" => with_register(0x1, self)?,
+            "&=" => with_register(0x2, self)?,
+            "^=" => with_register(0x3, self)?,
+            ">>=" => with_register(0x6, self)?,
+            "<<=" => with_register(0xE, self)?,
+            _ => {
+                return Err(AssembleError::new(
+                    &operator,
+                    format!("'{}' is not an operator that can follow a register", operator.text),
+                ))
+            }
+        };
+        self.emit_word(opcode);
+        Ok(())
+    }
+
+    fn if_statement(&mut self, token: &Token) -> Result<()> {
+        let condition = self.condition(token)?;
+        let body = self.next(token)?;
+        match body.text.as_str() {
+            // The next statement is skipped when the condition is false.
+            "then" => self.emit_word(condition.skip_if_false),
+            // The jump to else or end is skipped when the condition is true.
+            "begin" => {
+                self.emit_word(condition.skip_if_true);
+                self.branches.push((self.here, body));
+                self.emit_word(0x1000);
+            }
+            _ => return Err(AssembleError::new(&body, format!("expected then or begin, found '{}'", body.text))),
+        }
+        Ok(())
+    }
+
+    /// Parses a condition. Comparisons that Chip-8 can't test directly are
+    /// computed into vf first, and become a test of vf.
+    fn condition(&mut self, token: &Token) -> Result<Condition> {
+        let left = self.next(token)?;
+        let x = self.register(&left)? as u16;
+        let operator = self.next(&left)?;
+
+        if operator.text == "key" || operator.text == "-key" {
+            let pressed = Condition { skip_if_true: 0xE09E | x << 8, skip_if_false: 0xE0A1 | x << 8 };
+            return Ok(if operator.text == "key" { pressed } else { pressed.negate() });
+        }
+
+        let right = self.next(&operator)?;
+        let y = self.try_register(&right);
+        let equal = match y {
+            Some(y) => Condition {
+                skip_if_true: 0x5000 | x << 8 | (y as u16) << 4,
+                skip_if_false: 0x9000 | x << 8 | (y as u16) << 4,
+            },
+            None => {
+                let byte = self.byte(&right)? as u16;
+                Condition { skip_if_true: 0x3000 | x << 8 | byte, skip_if_false: 0x4000 | x << 8 | byte }
+            }
+        };
+        let vf_is_zero = Condition { skip_if_true: 0x3F00, skip_if_false: 0x4F00 };
+
+        let (subtract, zero) = match operator.text.as_str() {
+            "==" => return Ok(equal),
+            "!=" => return Ok(equal.negate()),
+            // vf := right - x, which borrows when x > right.
+            ">" => (0x8F05, true),
+            "<=" => (0x8F05, false),
+            // vf := x - right, which borrows when x < right.
+            "<" => (0x8F07, true),
+            ">=" => (0x8F07, false),
+            _ => return Err(AssembleError::new(&operator, format!("'{}' is not a comparison", operator.text))),
+        };
+
+        match y {
+            Some(y) => self.emit_word(0x8F00 | (y as u16) << 4),
+            None => {
+                let byte = self.byte(&right)? as u16;
+                self.emit_word(0x6F00 | byte);
+            }
+        }
+        self.emit_word(subtract | x << 4);
+        Ok(if zero { vf_is_zero } else { vf_is_zero.negate() })
+    }
+
+    /// Emits an instruction that takes a 12-bit address, which may be a label
+    /// that is defined later.
+    fn address_instruction(&mut self, after: &Token, opcode: u16) -> Result<()> {
+        let target = self.next(after)?;
+        if self.literal(&target).is_some() {
+            let address = self.value(&target)?;
+            if address > 0xFFF {
+                return Err(AssembleError::new(&target, format!("{:#x} is past 0xFFF", address)));
+            }
+            self.emit_word(opcode | address);
+        } else {
+            self.fixups.push(Fixup { address: self.here, label: target, long: false });
+            self.emit_word(opcode);
+        }
+        Ok(())
+    }
+
+    /// Emits an instruction that takes a register in its second nibble.
+    fn x_instruction(&mut self, after: &Token, opcode: u16) -> Result<()> {
+        let register = self.next(after)?;
+        let x = self.register(&register)? as u16;
+        self.emit_word(opcode | x << 8);
+        Ok(())
+    }
+
+    fn next(&mut self, after: &Token) -> Result<Token> {
+        self.tokens
+            .pop_front()
+            .ok_or_else(|| AssembleError::new(after, format!("expected more after '{}'", after.text)))
+    }
+
+    fn expect(&mut self, after: &Token, text: &str) -> Result<()> {
+        let token = self.next(after)?;
+        if token.text == text {
+            Ok(())
+        } else {
+            Err(AssembleError::new(&token, format!("expected '{}', found '{}'", text, token.text)))
+        }
+    }
+
+    fn peek_is(&self, text: &str) -> bool {
+        self.tokens.front().map(|token| token.text == text).unwrap_or(false)
+    }
+
+    fn try_register(&self, token: &Token) -> Option<u8> {
+        if let Some(register) = self.aliases.get(&token.text) {
+            return Some(*register);
+        }
+
+        let mut characters = token.text.chars();
+        match (characters.next(), characters.next(), characters.next()) {
+            (Some('v'), Some(digit), None) | (Some('V'), Some(digit), None) => {
+                digit.to_digit(16).map(|digit| digit as u8)
+            }
+            _ => None,
+        }
+    }
+
+    fn register(&self, token: &Token) -> Result<u8> {
+        self.try_register(token)
+            .ok_or_else(|| AssembleError::new(token, format!("'{}' is not a register", token.text)))
+    }
+
+    /// The value of a number or constant.
+    fn literal(&self, token: &Token) -> Option<i32> {
+        Self::number(&token.text).or_else(|| self.constants.get(&token.text).copied())
+    }
+
+    fn ranged(&self, token: &Token, range: std::ops::RangeInclusive<i32>) -> Result<i32> {
+        let value = self
+            .literal(token)
+            .ok_or_else(|| AssembleError::new(token, format!("'{}' is not a number", token.text)))?;
+        if range.contains(&value) {
+            Ok(value)
+        } else {
+            Err(AssembleError::new(token, format!("{} doesn't fit in {:?}", value, range)))
+        }
+    }
+
+    /// A byte, where negative numbers are two's complement.
+    fn byte(&self, token: &Token) -> Result<u8> {
+        self.ranged(token, -128..=255).map(|value| value as u8)
+    }
+
+    fn nibble(&self, token: &Token) -> Result<u16> {
+        self.ranged(token, 0..=15).map(|value| value as u16)
+    }
+
+    fn value(&self, token: &Token) -> Result<u16> {
+        self.ranged(token, 0..=0xFFFF).map(|value| value as u16)
+    }
+
+    /// Parses decimal, 0x hexadecimal and 0b binary numbers.
+    fn number(text: &str) -> Option<i32> {
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, text),
+        };
+
+        let value = if let Some(hex) = digits.strip_prefix("0x") {
+            i32::from_str_radix(hex, 16).ok()?
+        } else if let Some(binary) = digits.strip_prefix("0b") {
+            i32::from_str_radix(binary, 2).ok()?
+        } else if digits.starts_with(|c: char| c.is_ascii_digit()) {
+            digits.parse().ok()?
+        } else {
+            return None;
+        };
+
+        Some(if negative { -value } else { value })
+    }
+
+    fn emit_byte(&mut self, value: u8) {
+        self.source_map.insert(self.here, self.line);
+        self.write_byte(self.here, value);
+        self.here = self.here.wrapping_add(1);
+    }
+
+    fn emit_word(&mut self, value: u16) {
+        self.source_map.insert(self.here, self.line);
+        self.write_word(self.here, value);
+        self.here = self.here.wrapping_add(2);
+    }
+
+    fn write_byte(&mut self, address: u16, value: u8) {
+        let index = address.wrapping_sub(PROGRAM_START) as usize;
+        if index >= self.rom.len() {
+            self.rom.resize(index + 1, 0);
+        }
+        self.rom[index] = value;
+    }
+
+    fn write_word(&mut self, address: u16, value: u16) {
+        self.write_byte(address, (value >> 8) as u8);
+        self.write_byte(address.wrapping_add(1), value as u8);
+    }
+
+    fn read_word(&self, address: u16) -> u16 {
+        let index = address.wrapping_sub(PROGRAM_START) as usize;
+        (self.rom[index] as u16) << 8 | self.rom[index + 1] as u16
+    }
+
+    /// Points the jump at the address to the target, which is where `token`
+    /// is.
+    fn patch_jump(&mut self, address: u16, target: u16, token: &Token) -> Result<()> {
+        let jump = Self::jump(target, token)?;
+        self.write_word(address, jump);
+        Ok(())
+    }
+
+    /// A jump to the target, which is where `token` is. Jumps only take 12
+    /// bits, so targets past 0xFFF can't be reached.
+    fn jump(target: u16, token: &Token) -> Result<u16> {
+        if target > 0xFFF {
+            return Err(AssembleError::new(token, format!("{:#x} is past 0xFFF, where jumps can't reach", target)));
+        }
+        Ok(0x1000 | target)
+    }
+}
+
+impl Condition {
+    fn negate(self) -> Self {
+        Self { skip_if_true: self.skip_if_false, skip_if_false: self.skip_if_true }
+    }
+}
@@ -0,0 +1,11 @@
+//! An assembler for the syntax of Octo (https://github.com/JohnEarnest/Octo),
+//! the most widely used Chip-8 assembly language.
+
+mod compiler;
+pub use compiler::*;
+
+mod source_map;
+pub use source_map::*;
+
+mod token;
+pub use token::*;
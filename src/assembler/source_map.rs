@@ -0,0 +1,38 @@
+use std::collections::BTreeMap;
+
+/// Maps the addresses of an assembled program back to the lines of source code
+/// they were assembled from.
+#[derive(Clone, Debug, Default)]
+pub struct SourceMap {
+    lines: BTreeMap<u16, usize>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self { lines: BTreeMap::new() }
+    }
+
+    pub fn insert(&mut self, address: u16, line: usize) {
+        self.lines.insert(address, line);
+    }
+
+    /// The line the byte at the address was assembled from. Addresses inside
+    /// of an instruction map to the instruction's line.
+    pub fn line(&self, address: u16) -> Option<usize> {
+        self.lines.range(..=address).next_back().map(|(_, line)| *line)
+    }
+
+    /// The address of the first byte assembled from the line.
+    pub fn address(&self, line: usize) -> Option<u16> {
+        self.lines
+            .iter()
+            .filter(|(_, l)| **l == line)
+            .map(|(address, _)| *address)
+            .min()
+    }
+
+    /// Every address that starts an instruction or data, and its line.
+    pub fn iter(&self) -> impl Iterator<Item = (u16, usize)> + '_ {
+        self.lines.iter().map(|(address, line)| (*address, *line))
+    }
+}
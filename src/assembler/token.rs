@@ -0,0 +1,46 @@
+/// A whitespace separated word of source code, and where it was found.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Token {
+    pub text: String,
+    /// The line the token starts on, counting from 1.
+    pub line: usize,
+    /// The column the token starts at, counting characters from 1.
+    pub column: usize,
+}
+
+/// Splits source code into tokens. Octo tokens are separated by whitespace,
+/// and a `#` starts a comment that runs to the end of the line.
+pub fn tokenize(source: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+
+    for (line_index, line) in source.lines().enumerate() {
+        let mut current: Option<Token> = None;
+
+        for (column_index, character) in line.chars().enumerate() {
+            if character == '#' && current.is_none() {
+                break;
+            }
+
+            if character.is_whitespace() {
+                if let Some(token) = current.take() {
+                    tokens.push(token);
+                }
+            } else {
+                current
+                    .get_or_insert_with(|| Token {
+                        text: String::new(),
+                        line: line_index + 1,
+                        column: column_index + 1,
+                    })
+                    .text
+                    .push(character);
+            }
+        }
+
+        if let Some(token) = current.take() {
+            tokens.push(token);
+        }
+    }
+
+    tokens
+}
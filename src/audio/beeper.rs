@@ -0,0 +1,50 @@
+use crate::audio::{AudioSink, Pattern, Synth, Tone};
+use crate::scheduler::TIMER_HZ;
+
+/// Sounds a tone while a chip's sound timer is active, producing a frame's
+/// worth of samples after every frame the chip runs. The samples follow the
+/// chip's frames rather than the wall clock, so a second of emulation is a
+/// second of audio whatever the speed it ran at.
+pub struct Beeper<S: AudioSink> {
+    synth: Synth,
+    sink: S,
+    /// The fraction of a sample owed from previous frames, in sixtieths.
+    owed: u32,
+    buffer: Vec<f32>,
+}
+
+impl<S: AudioSink> Beeper<S> {
+    pub fn new(tone: Tone, sink: S) -> Self {
+        Self { synth: Synth::new(tone, sink.sample_rate()), sink, owed: 0, buffer: Vec::new() }
+    }
+
+    pub fn sink(&self) -> &S {
+        &self.sink
+    }
+
+    pub fn sink_mut(&mut self) -> &mut S {
+        &mut self.sink
+    }
+
+    pub fn into_sink(self) -> S {
+        self.sink
+    }
+
+    /// Plays a pattern in place of the tone, usually `Pattern::of` the chip,
+    /// from the next frame on.
+    pub fn set_pattern(&mut self, pattern: Option<Pattern>) {
+        self.synth.set_pattern(pattern);
+    }
+
+    /// Writes a frame of samples, with the tone sounding if `active`, which
+    /// is usually `Chip::sound_active`.
+    pub fn frame(&mut self, active: bool) -> std::io::Result<()> {
+        self.owed += self.synth.sample_rate();
+        let samples = (self.owed / TIMER_HZ) as usize;
+        self.owed %= TIMER_HZ;
+
+        self.buffer.resize(samples, 0.0);
+        self.synth.fill(&mut self.buffer, active);
+        self.sink.write(&self.buffer)
+    }
+}
@@ -0,0 +1,16 @@
+//! The beeper's audio. A `Synth` generates the tone procedurally, or plays an
+//! XO-CHIP pattern, and a `Beeper` turns a chip's sound timer into a frame's
+//! worth of samples at a time, written to an `AudioSink`: a sound device in
+//! the frontend, or a WAV file or nothing at all when running headless.
+
+mod beeper;
+pub use beeper::*;
+
+mod sink;
+pub use sink::*;
+
+mod synth;
+pub use synth::*;
+
+mod wav;
+pub use wav::*;
@@ -0,0 +1,36 @@
+/// Where the beeper's samples go. Samples are mono, from -1 to 1.
+pub trait AudioSink {
+    /// The number of samples per second the sink expects.
+    fn sample_rate(&self) -> u32;
+
+    fn write(&mut self, samples: &[f32]) -> std::io::Result<()>;
+}
+
+/// Discards samples, counting them.
+#[derive(Clone, Debug)]
+pub struct NullSink {
+    sample_rate: u32,
+    samples: u64,
+}
+
+impl NullSink {
+    pub fn new(sample_rate: u32) -> Self {
+        Self { sample_rate, samples: 0 }
+    }
+
+    /// The number of samples written.
+    pub fn samples(&self) -> u64 {
+        self.samples
+    }
+}
+
+impl AudioSink for NullSink {
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn write(&mut self, samples: &[f32]) -> std::io::Result<()> {
+        self.samples += samples.len() as u64;
+        Ok(())
+    }
+}
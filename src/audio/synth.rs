@@ -0,0 +1,169 @@
+use crate::emulator::Chip;
+use std::f32::consts::TAU;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("unknown waveform '{0}', expected square, sine or triangle")]
+pub struct UnknownWaveform(pub String);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Waveform {
+    Square,
+    Sine,
+    Triangle,
+}
+
+/// What the beeper sounds like.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tone {
+    /// The pitch, in Hz.
+    pub frequency: f32,
+    pub waveform: Waveform,
+    /// The loudness, from 0 to 1.
+    pub volume: f32,
+    /// How long the tone takes to fade in when it starts, in seconds.
+    pub attack: f32,
+    /// How long the tone takes to fade out when it stops, in seconds.
+    pub release: f32,
+}
+
+/// An XO-CHIP audio pattern: 128 bits played one after the other, most
+/// significant first, each as a high or a low sample.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pattern {
+    pub bits: [u8; 16],
+    /// How many bits are played per second.
+    pub rate: f32,
+}
+
+/// Generates a tone one sample at a time. Starting and stopping fade the tone
+/// in and out over its attack and release, as cutting a wave off mid-cycle
+/// clicks.
+#[derive(Clone, Debug)]
+pub struct Synth {
+    tone: Tone,
+    sample_rate: u32,
+    /// How far through a cycle of the wave the next sample is, from 0 to 1.
+    phase: f32,
+    /// The current volume of the envelope, from 0 to 1.
+    level: f32,
+    /// The pattern played instead of the tone, if any.
+    pattern: Option<Pattern>,
+    /// How far through the pattern the next sample is, in bits.
+    position: f32,
+}
+
+impl Waveform {
+    /// The value of the wave, from -1 to 1, at a point of its cycle, from 0
+    /// to 1. Every wave starts its cycle rising from 0 and peaks a quarter of
+    /// the way through.
+    pub fn sample(self, phase: f32) -> f32 {
+        match self {
+            Waveform::Square if phase < 0.5 => 1.0,
+            Waveform::Square => -1.0,
+            Waveform::Sine => (phase * TAU).sin(),
+            Waveform::Triangle => 1.0 - 4.0 * ((phase + 0.25).fract() - 0.5).abs(),
+        }
+    }
+}
+
+impl FromStr for Waveform {
+    type Err = UnknownWaveform;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().as_str() {
+            "square" => Ok(Waveform::Square),
+            "sine" => Ok(Waveform::Sine),
+            "triangle" => Ok(Waveform::Triangle),
+            _ => Err(UnknownWaveform(name.to_string())),
+        }
+    }
+}
+
+impl Pattern {
+    /// The pattern a chip plays, once its program has loaded one.
+    pub fn of(chip: &Chip) -> Option<Self> {
+        chip.audio_pattern().map(|bits| Self { bits, rate: chip.playback_rate() as f32 })
+    }
+
+    /// The value of the pattern, -1 or 1, at a position in bits, wrapping
+    /// around after the last one.
+    pub fn sample(&self, position: f32) -> f32 {
+        let bit = position as usize % 128;
+        if self.bits[bit / 8] & (0x80 >> (bit % 8)) != 0 {
+            1.0
+        } else {
+            -1.0
+        }
+    }
+}
+
+/// A quiet 440 Hz square wave, fading in and out over 5 ms.
+impl Default for Tone {
+    fn default() -> Self {
+        Self { frequency: 440.0, waveform: Waveform::Square, volume: 0.25, attack: 0.005, release: 0.005 }
+    }
+}
+
+impl Synth {
+    pub fn new(tone: Tone, sample_rate: u32) -> Self {
+        Self { tone, sample_rate: sample_rate.max(1), phase: 0.0, level: 0.0, pattern: None, position: 0.0 }
+    }
+
+    pub fn tone(&self) -> Tone {
+        self.tone
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Plays a pattern in place of the tone's wave, which keeps its volume
+    /// and envelope, or the tone again with `None`.
+    pub fn set_pattern(&mut self, pattern: Option<Pattern>) {
+        self.pattern = pattern;
+    }
+
+    /// The next sample, from -1 to 1, with the tone sounding or fading out
+    /// depending on `active`. The wave, or the pattern, keeps its phase while
+    /// silent.
+    pub fn next_sample(&mut self, active: bool) -> f32 {
+        let sample_rate = self.sample_rate as f32;
+        if active {
+            self.level = (self.level + step(self.tone.attack, sample_rate)).min(1.0);
+        } else {
+            self.level = (self.level - step(self.tone.release, sample_rate)).max(0.0);
+        }
+
+        let wave = match &self.pattern {
+            Some(pattern) => {
+                let wave = pattern.sample(self.position);
+                self.position = (self.position + pattern.rate / sample_rate) % 128.0;
+                wave
+            }
+            None => {
+                let wave = self.tone.waveform.sample(self.phase);
+                self.phase = (self.phase + self.tone.frequency / sample_rate).fract();
+                wave
+            }
+        };
+        wave * self.tone.volume * self.level
+    }
+
+    /// Fills the samples, with the tone sounding or fading out.
+    pub fn fill(&mut self, samples: &mut [f32], active: bool) {
+        for sample in samples {
+            *sample = self.next_sample(active);
+        }
+    }
+}
+
+/// How much the envelope moves per sample to fade over `seconds`.
+fn step(seconds: f32, sample_rate: f32) -> f32 {
+    if seconds <= 0.0 {
+        1.0
+    } else {
+        1.0 / (seconds * sample_rate)
+    }
+}
@@ -0,0 +1,44 @@
+//! Assembles Octo source code into a Chip-8 program.
+//!
+//! Usage: chirp8-asm <source> [output]
+//!
+//! The output defaults to the source path with a .ch8 extension.
+
+use chirp_8::assembler::assemble;
+use std::path::PathBuf;
+
+fn main() {
+    let mut args = std::env::args_os().skip(1);
+    let source_path = match args.next() {
+        Some(path) => PathBuf::from(path),
+        None => {
+            eprintln!("usage: chirp8-asm <source> [output]");
+            std::process::exit(2);
+        }
+    };
+    let output_path = args
+        .next()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| source_path.with_extension("ch8"));
+
+    let source = match std::fs::read_to_string(&source_path) {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!("failed to read {}: {}", source_path.display(), err);
+            std::process::exit(1);
+        }
+    };
+
+    let program = match assemble(&source) {
+        Ok(program) => program,
+        Err(err) => {
+            eprintln!("{}:{}:{}: error: {}", source_path.display(), err.line, err.column, err.message);
+            std::process::exit(1);
+        }
+    };
+
+    if let Err(err) = std::fs::write(&output_path, &program.rom) {
+        eprintln!("failed to write {}: {}", output_path.display(), err);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,32 @@
+//! Prints an annotated listing of a Chip-8 program.
+//!
+//! Usage: chirp8-disasm <rom>
+
+use chirp_8::disassembler::Disassembly;
+use std::io::Write;
+use std::path::PathBuf;
+
+fn main() {
+    let path = match std::env::args_os().nth(1) {
+        Some(path) => PathBuf::from(path),
+        None => {
+            eprintln!("usage: chirp8-disasm <rom>");
+            std::process::exit(2);
+        }
+    };
+
+    let rom = match std::fs::read(&path) {
+        Ok(rom) => rom,
+        Err(err) => {
+            eprintln!("failed to read {}: {}", path.display(), err);
+            std::process::exit(1);
+        }
+    };
+
+    // Writing fails when the output is piped into a program that exits early,
+    // such as head, which is not worth reporting.
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    let _ = writeln!(out, "; {} ({} bytes)", path.display(), rom.len())
+        .and_then(|_| write!(out, "{}", Disassembly::new(&rom)));
+}
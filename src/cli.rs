@@ -0,0 +1,266 @@
+use crate::keymap::KeyMap;
+use chirp_8::audio::Waveform;
+use chirp_8::emulator::Quirks;
+use chirp_8::scheduler::TIMER_HZ;
+use chirp_8::trace::Category;
+use clap::builder::{PossibleValuesParser, TypedValueParser};
+use clap::Parser;
+use std::path::PathBuf;
+
+/// An RGB color with components from 0 to 1.
+pub type Color = (f32, f32, f32);
+
+/// A CHIP-8, SUPER-CHIP and XO-CHIP emulator.
+///
+/// Options that are not given are taken from the configuration file, where
+/// games can have their own settings.
+///
+/// The keypad is mapped onto the 1-4, Q-R, A-F and Z-V keys unless another
+/// keymap is chosen. While a program runs, F1 to F8 load save slots and
+/// Shift+F1 to Shift+F8 save them, F9 pauses and resumes, F10 advances a paused
+/// program by a frame, F11 resets, F12 shows the keymap, Insert saves a
+/// screenshot, holding Tab fast-forwards, holding Backspace rewinds, = and -
+/// change the speed, and Escape quits.
+#[derive(Debug, Parser)]
+#[command(name = "chirp-8", version)]
+pub struct Args {
+    /// The ROM to run.
+    pub rom: PathBuf,
+
+    /// The configuration file, instead of config.toml in the chirp-8 folder of
+    /// the user's configuration directory.
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// How many instructions are executed per frame, at 60 frames per second.
+    /// [default: 10]
+    #[arg(
+        long,
+        visible_alias = "ipf",
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..=(u32::MAX / TIMER_HZ) as i64),
+    )]
+    pub instructions_per_frame: Option<u32>,
+
+    /// How many instructions are executed per second, instead of a number per
+    /// frame. [default: 600]
+    #[arg(
+        long,
+        value_name = "HZ",
+        value_parser = clap::value_parser!(u32).range(60..),
+        conflicts_with = "instructions_per_frame",
+    )]
+    pub cpu_hz: Option<u32>,
+
+    /// Charges every instruction the machine cycles it took on the COSMAC VIP,
+    /// leaving out those the display takes, instead of running a number of
+    /// instructions per frame.
+    #[arg(long, conflicts_with_all = ["instructions_per_frame", "cpu_hz"])]
+    pub vip_timing: bool,
+
+    /// The size of a CHIP-8 pixel in the window, in screen pixels.
+    /// [default: 10]
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub scale: Option<u32>,
+
+    /// The color of lit pixels, as a hexadecimal RGB color such as ffcc00.
+    /// [default: ffffff]
+    #[arg(long, value_name = "COLOR", value_parser = parse_color)]
+    pub foreground: Option<Color>,
+
+    /// The color of unlit pixels, as a hexadecimal RGB color.
+    /// [default: 000000]
+    #[arg(long, value_name = "COLOR", value_parser = parse_color)]
+    pub background: Option<Color>,
+
+    /// The interpreter whose behaviour is emulated. [default: vip]
+    #[arg(
+        long,
+        value_name = "PRESET",
+        value_parser = PossibleValuesParser::new(Quirks::PRESETS.iter().map(|(name, _)| *name))
+            .map(|name| name.parse::<Quirks>().unwrap()),
+    )]
+    pub quirks: Option<Quirks>,
+
+    /// The host keys the keypad is mapped onto. [default: qwerty]
+    #[arg(
+        long,
+        value_name = "PRESET",
+        value_parser = PossibleValuesParser::new(KeyMap::PRESETS).map(|name| KeyMap::preset(&name).unwrap()),
+    )]
+    pub keymap: Option<KeyMap>,
+
+    /// Seeds the random number generator, so runs with the same input play
+    /// out the same.
+    #[arg(long, value_name = "N")]
+    pub seed: Option<u64>,
+
+    /// Generates random numbers with the COSMAC VIP interpreter's algorithm.
+    #[arg(long)]
+    pub vip_random: bool,
+
+    /// Turns the sound off.
+    #[arg(long)]
+    pub mute: bool,
+
+    /// The pitch of the beeper, in Hz. [default: 440]
+    #[arg(long, value_name = "HZ", value_parser = parse_frequency)]
+    pub beep_frequency: Option<f32>,
+
+    /// The shape of the beeper's wave. [default: square]
+    #[arg(
+        long,
+        value_name = "WAVEFORM",
+        value_parser = PossibleValuesParser::new(["square", "sine", "triangle"])
+            .map(|name| name.parse::<Waveform>().unwrap()),
+    )]
+    pub beep_waveform: Option<Waveform>,
+
+    /// The loudness of the beeper, from 0 to 1. [default: 0.25]
+    #[arg(long, value_parser = parse_volume)]
+    pub volume: Option<f32>,
+
+    /// Writes the beeper's tone to a WAV file, following the emulated frames
+    /// rather than the wall clock. It works even when the sound is muted.
+    #[arg(long, value_name = "PATH")]
+    pub capture_audio: Option<PathBuf>,
+
+    /// Starts with the program paused, until F9 is pressed.
+    #[arg(long)]
+    pub paused: bool,
+
+    /// Prints executed instructions in the categories, a comma separated list
+    /// of flow, draw, memory, input, timers and alu, or all.
+    #[arg(long, value_name = "CATEGORIES")]
+    pub trace: Option<Category>,
+
+    /// Writes executed instructions to a JSON-lines file, in every category
+    /// unless --trace is given.
+    #[arg(long, value_name = "PATH")]
+    pub trace_json: Option<PathBuf>,
+
+    /// Keeps the last N executed instructions in memory, in every category
+    /// unless --trace is given, for the debugger's history command.
+    #[arg(long, value_name = "N")]
+    pub trace_buffer: Option<usize>,
+
+    /// Reads debugger commands from stdin.
+    #[arg(long)]
+    pub debug: bool,
+
+    /// The Octo source the ROM was assembled from. The debugger shows its
+    /// lines and stops at its :breakpoint directives.
+    #[arg(long, value_name = "PATH", requires = "debug")]
+    pub source: Option<PathBuf>,
+
+    /// Waits for a GDB client to connect on the port, 9001 if none is given.
+    #[arg(
+        long,
+        value_name = "PORT",
+        num_args = 0..=1,
+        default_missing_value = "9001",
+        conflicts_with = "debug",
+    )]
+    pub gdb: Option<u16>,
+
+    /// Records the input into a movie, which is written to the path on exit.
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["play", "debug", "gdb"])]
+    pub record: Option<PathBuf>,
+
+    /// Plays back a movie, ignoring the keyboard until it ends.
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["debug", "gdb"])]
+    pub play: Option<PathBuf>,
+}
+
+/// Parses a color written as six hexadecimal digits, optionally preceded by
+/// `#`.
+pub fn parse_color(color: &str) -> Result<Color, String> {
+    chirp_8::emulator::parse_color(color).ok_or_else(|| format!("'{}' is not a color like ffcc00", color))
+}
+
+pub fn parse_frequency(frequency: &str) -> Result<f32, String> {
+    match frequency.parse::<f32>() {
+        Ok(frequency) if frequency > 0.0 && frequency.is_finite() => Ok(frequency),
+        _ => Err(format!("'{}' is not a frequency in Hz", frequency)),
+    }
+}
+
+pub fn parse_volume(volume: &str) -> Result<f32, String> {
+    match volume.parse::<f32>() {
+        Ok(volume) if (0.0..=1.0).contains(&volume) => Ok(volume),
+        _ => Err(format!("'{}' is not a volume from 0 to 1", volume)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::error::ErrorKind;
+
+    fn parse(options: &[&str]) -> Result<Args, ErrorKind> {
+        Args::try_parse_from(["chirp-8", "rom.ch8"].iter().chain(options)).map_err(|err| err.kind())
+    }
+
+    #[test]
+    fn leaves_options_that_are_not_given_to_the_configuration() {
+        let args = parse(&[]).unwrap();
+        assert_eq!(args.rom, PathBuf::from("rom.ch8"));
+        assert_eq!(args.instructions_per_frame, None);
+        assert_eq!(args.cpu_hz, None);
+        assert_eq!(args.quirks, None);
+        assert_eq!(args.foreground, None);
+        assert!(!args.debug && !args.mute && !args.paused && !args.vip_timing);
+        assert_eq!(args.gdb, None);
+    }
+
+    #[test]
+    fn parses_values() {
+        let args = parse(&[
+            "--ipf", "20", "--quirks", "super-chip", "--foreground", "#ff8000", "--beep-waveform", "sine",
+            "--volume", "0.5", "--trace", "flow,draw", "--seed", "7",
+        ])
+        .unwrap();
+        assert_eq!(args.instructions_per_frame, Some(20));
+        assert_eq!(args.quirks, Some(Quirks::SUPER_CHIP));
+        assert_eq!(args.foreground, Some((1.0, 128.0 / 255.0, 0.0)));
+        assert_eq!(args.beep_waveform, Some(Waveform::Sine));
+        assert_eq!(args.volume, Some(0.5));
+        assert_eq!(args.trace, Some(Category::FLOW | Category::DRAW));
+        assert_eq!(args.seed, Some(7));
+
+        assert_eq!(parse(&["--cpu-hz", "1000"]).unwrap().cpu_hz, Some(1000));
+        assert_eq!(parse(&["--gdb"]).unwrap().gdb, Some(9001));
+        assert_eq!(parse(&["--gdb", "1234"]).unwrap().gdb, Some(1234));
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        assert_eq!(parse(&["--ipf", "0"]).err(), Some(ErrorKind::ValueValidation));
+        assert_eq!(parse(&["--cpu-hz", "59"]).err(), Some(ErrorKind::ValueValidation));
+        assert_eq!(parse(&["--scale", "0"]).err(), Some(ErrorKind::ValueValidation));
+        assert_eq!(parse(&["--foreground", "fff"]).err(), Some(ErrorKind::ValueValidation));
+        assert_eq!(parse(&["--beep-frequency", "0"]).err(), Some(ErrorKind::ValueValidation));
+        assert_eq!(parse(&["--volume", "2"]).err(), Some(ErrorKind::ValueValidation));
+        assert_eq!(parse(&["--quirks", "chip-9"]).err(), Some(ErrorKind::InvalidValue));
+        assert_eq!(parse(&["--keymap", "dvorak"]).err(), Some(ErrorKind::InvalidValue));
+        assert_eq!(parse(&["--trace", "sound"]).err(), Some(ErrorKind::ValueValidation));
+    }
+
+    #[test]
+    fn rejects_conflicting_options() {
+        assert_eq!(parse(&["--ipf", "20", "--cpu-hz", "1000"]).err(), Some(ErrorKind::ArgumentConflict));
+        assert_eq!(parse(&["--vip-timing", "--ipf", "20"]).err(), Some(ErrorKind::ArgumentConflict));
+        assert_eq!(parse(&["--debug", "--gdb"]).err(), Some(ErrorKind::ArgumentConflict));
+        assert_eq!(parse(&["--record", "a", "--play", "b"]).err(), Some(ErrorKind::ArgumentConflict));
+        assert_eq!(parse(&["--play", "a", "--debug"]).err(), Some(ErrorKind::ArgumentConflict));
+        assert_eq!(parse(&["--source", "game.8o"]).err(), Some(ErrorKind::MissingRequiredArgument));
+        assert!(parse(&["--source", "game.8o", "--debug"]).is_ok());
+    }
+
+    #[test]
+    fn parses_colors() {
+        assert_eq!(parse_color("000000"), Ok((0.0, 0.0, 0.0)));
+        assert_eq!(parse_color("#FFffFF"), Ok((1.0, 1.0, 1.0)));
+        assert!(parse_color("ggggg0").is_err());
+    }
+}
@@ -0,0 +1,426 @@
+use crate::cli::{parse_color, Args, Color};
+use crate::keymap::KeyMap;
+use chirp_8::audio::{Tone, Waveform};
+use chirp_8::database::Game;
+use chirp_8::emulator::{Quirks, DEFAULT_INSTRUCTIONS_PER_CYCLE};
+use chirp_8::scheduler::TIMER_HZ;
+use chirp_8::rom::RomHash;
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+const DEFAULT_SCALE: u32 = 10;
+const DEFAULT_FOREGROUND: Color = (1.0, 1.0, 1.0);
+const DEFAULT_BACKGROUND: Color = (0.0, 0.0, 0.0);
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("failed to read {0}: {1}")]
+    Io(PathBuf, std::io::Error),
+    #[error("invalid configuration in {0}: {1}")]
+    Parse(PathBuf, toml::de::Error),
+    #[error("invalid configuration in {0}: {1}")]
+    Invalid(PathBuf, String),
+}
+
+/// The settings the emulator runs a ROM with.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    pub quirks: Quirks,
+    /// The number of instructions executed per second.
+    pub cpu_hz: u32,
+    /// Whether instructions take as long as on the COSMAC VIP, instead of
+    /// running at `cpu_hz`.
+    pub vip_timing: bool,
+    pub scale: u32,
+    pub foreground: Color,
+    pub background: Color,
+    pub mute: bool,
+    pub tone: Tone,
+    pub keymap: KeyMap,
+}
+
+/// Settings that replace those of a lower layer where they are given.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Overrides {
+    /// Which game a section is for, for the reader's benefit.
+    pub name: Option<String>,
+    #[serde(deserialize_with = "quirks")]
+    pub quirks: Option<Quirks>,
+    pub instructions_per_frame: Option<u32>,
+    pub cpu_hz: Option<u32>,
+    pub vip_timing: Option<bool>,
+    pub scale: Option<u32>,
+    #[serde(deserialize_with = "color")]
+    pub foreground: Option<Color>,
+    #[serde(deserialize_with = "color")]
+    pub background: Option<Color>,
+    pub mute: Option<bool>,
+    pub beep_frequency: Option<f32>,
+    #[serde(deserialize_with = "waveform")]
+    pub beep_waveform: Option<Waveform>,
+    pub volume: Option<f32>,
+    /// How long the beep takes to fade in, in milliseconds.
+    pub beep_attack: Option<f32>,
+    /// How long the beep takes to fade out, in milliseconds.
+    pub beep_release: Option<f32>,
+    /// The keymap preset the `keys` table changes.
+    #[serde(deserialize_with = "keymap")]
+    pub keymap: Option<KeyMap>,
+    /// Host key names for keypad keys, by hexadecimal digit.
+    pub keys: Option<BTreeMap<String, Vec<String>>>,
+}
+
+/// The configuration file, `config.toml` in the `chirp-8` folder of the
+/// user's configuration directory. It has a `[defaults]` table, and a
+/// `[roms.<sha1>]` table per game whose settings differ, keyed by the SHA-1
+/// hash of the ROM:
+///
+/// ```toml
+/// [defaults]
+/// instructions-per-frame = 15
+/// foreground = "ffcc00"
+///
+/// keymap = "azerty"
+///
+/// [roms.607c4f7f4e4dce9f99d96b3182bfe7e88bb090ee]
+/// name = "Pong (1 player)"
+/// quirks = "chip-48"
+///
+/// [roms.607c4f7f4e4dce9f99d96b3182bfe7e88bb090ee.keys]
+/// 1 = ["W", "Up"]
+/// 4 = ["S", "Down"]
+/// ```
+///
+/// Command line options take precedence over a game's table, which takes
+/// precedence over what the game database knows of the game, which takes
+/// precedence over the defaults. The speed and `vip-timing` are decided
+/// together, by the first of them that sets either. Key bindings start from
+/// the keymap preset, with the arrow keys bound to the game's directions if the
+/// database knows them, then the defaults' `keys` and the game's `keys` rebind
+/// keypad keys.
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    pub defaults: Overrides,
+    pub roms: BTreeMap<RomHash, Overrides>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    defaults: Overrides,
+    roms: BTreeMap<String, Overrides>,
+}
+
+impl Config {
+    /// Where the configuration is read from when no path is given, if the
+    /// platform has a configuration directory.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("chirp-8").join("config.toml"))
+    }
+
+    /// Reads the configuration at the path, or at the default path if none is
+    /// given. Only a missing file at the default path is not an error, and
+    /// gives an empty configuration.
+    pub fn load(path: Option<&Path>) -> Result<Self, ConfigError> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match Self::default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Self::default()),
+            },
+        };
+
+        match std::fs::read_to_string(&path) {
+            Ok(source) => Self::parse(&source).map_err(|err| match err {
+                ParseError::Toml(err) => ConfigError::Parse(path, err),
+                ParseError::Invalid(message) => ConfigError::Invalid(path, message),
+            }),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound && !required => Ok(Self::default()),
+            Err(err) => Err(ConfigError::Io(path, err)),
+        }
+    }
+
+    fn parse(source: &str) -> Result<Self, ParseError> {
+        let file: ConfigFile = toml::from_str(source).map_err(ParseError::Toml)?;
+        file.defaults.validate()?;
+
+        let mut roms = BTreeMap::new();
+        for (hash, overrides) in file.roms {
+            let hash = hash
+                .parse()
+                .map_err(|_| ParseError::Invalid(format!("'{}' is not the SHA-1 hash of a ROM", hash)))?;
+            overrides.validate()?;
+            roms.insert(hash, overrides);
+        }
+
+        Ok(Self { defaults: file.defaults, roms })
+    }
+
+    /// The settings for a ROM, taken from the command line, the ROM's table,
+    /// the game database, the defaults and the built-in defaults, in that
+    /// order.
+    pub fn settings(&self, args: &Args, rom: RomHash, game: Option<&Game>) -> Settings {
+        let (cpu_hz, vip_timing) = self.speed(args, rom, game);
+        Settings {
+            quirks: self
+                .setting(args.quirks, rom, game.and_then(Game::quirks), |layer| layer.quirks)
+                .unwrap_or_default(),
+            cpu_hz,
+            vip_timing,
+            scale: self.setting(args.scale, rom, None, |layer| layer.scale).unwrap_or(DEFAULT_SCALE),
+            foreground: self
+                .setting(args.foreground, rom, game.and_then(|game| game.colors.get(1).copied()), |layer| {
+                    layer.foreground
+                })
+                .unwrap_or(DEFAULT_FOREGROUND),
+            background: self
+                .setting(args.background, rom, game.and_then(|game| game.colors.first().copied()), |layer| {
+                    layer.background
+                })
+                .unwrap_or(DEFAULT_BACKGROUND),
+            mute: args.mute || self.setting(None, rom, None, |layer| layer.mute).unwrap_or(false),
+            tone: self.tone(args, rom),
+            keymap: self.keymap(args, rom, game),
+        }
+    }
+
+    /// The CPU's rate, from the first layer that sets one, and whether VIP
+    /// timing is on, which the first layer that sets a rate or VIP timing
+    /// decides. A rate given for a game turns the defaults' VIP timing off, and
+    /// VIP timing given for a game overrides the database's rate.
+    fn speed(&self, args: &Args, rom: RomHash, game: Option<&Game>) -> (u32, bool) {
+        let command_line = (
+            args.cpu_hz.or(args.instructions_per_frame.and_then(|instructions| instructions.checked_mul(TIMER_HZ))),
+            if args.vip_timing { Some(true) } else { None },
+        );
+        let table = |layer: &Overrides| (layer.cpu_hz(), layer.vip_timing);
+        let layers = [
+            Some(command_line),
+            self.roms.get(&rom).map(table),
+            game.map(|game| (game.tickrate.and_then(|tickrate| tickrate.checked_mul(TIMER_HZ)), None)),
+            Some(table(&self.defaults)),
+        ];
+
+        let (mut cpu_hz, mut vip_timing) = (None, None);
+        for (layer_cpu_hz, layer_vip_timing) in layers.iter().flatten() {
+            vip_timing = vip_timing.or(*layer_vip_timing).or(layer_cpu_hz.map(|_| false));
+            cpu_hz = cpu_hz.or(*layer_cpu_hz);
+        }
+        (
+            cpu_hz.unwrap_or(DEFAULT_INSTRUCTIONS_PER_CYCLE as u32 * TIMER_HZ),
+            vip_timing.unwrap_or(false),
+        )
+    }
+
+    fn tone(&self, args: &Args, rom: RomHash) -> Tone {
+        let default = Tone::default();
+        let milliseconds =
+            |field: fn(&Overrides) -> Option<f32>| self.setting(None, rom, None, field).map(|ms| ms / 1000.0);
+        Tone {
+            frequency: self
+                .setting(args.beep_frequency, rom, None, |layer| layer.beep_frequency)
+                .unwrap_or(default.frequency),
+            waveform: self
+                .setting(args.beep_waveform, rom, None, |layer| layer.beep_waveform)
+                .unwrap_or(default.waveform),
+            volume: self.setting(args.volume, rom, None, |layer| layer.volume).unwrap_or(default.volume),
+            attack: milliseconds(|layer| layer.beep_attack).unwrap_or(default.attack),
+            release: milliseconds(|layer| layer.beep_release).unwrap_or(default.release),
+        }
+    }
+
+    fn keymap(&self, args: &Args, rom: RomHash, game: Option<&Game>) -> KeyMap {
+        let mut keymap = self
+            .setting(args.keymap.clone(), rom, None, |layer| layer.keymap.clone())
+            .unwrap_or_default();
+        if let Some(game) = game {
+            keymap.bind_directions(&game.keys);
+        }
+        // The tables were checked when the configuration was parsed.
+        let rom = self.roms.get(&rom);
+        for keys in self.defaults.keys.iter().chain(rom.and_then(|rom| rom.keys.as_ref())) {
+            keymap.rebind(keys).unwrap();
+        }
+        keymap
+    }
+
+    fn setting<T>(
+        &self,
+        command_line: Option<T>,
+        rom: RomHash,
+        game: Option<T>,
+        field: impl Fn(&Overrides) -> Option<T>,
+    ) -> Option<T> {
+        command_line
+            .or_else(|| self.roms.get(&rom).and_then(&field))
+            .or(game)
+            .or_else(|| field(&self.defaults))
+    }
+}
+
+#[derive(Debug)]
+enum ParseError {
+    Toml(toml::de::Error),
+    Invalid(String),
+}
+
+impl Overrides {
+    /// The CPU's rate from `cpu-hz`, or from `instructions-per-frame`. A table
+    /// can't have both.
+    fn cpu_hz(&self) -> Option<u32> {
+        self.cpu_hz.or(self.instructions_per_frame.and_then(|instructions| instructions.checked_mul(TIMER_HZ)))
+    }
+
+    fn validate(&self) -> Result<(), ParseError> {
+        if self.instructions_per_frame == Some(0) {
+            return Err(ParseError::Invalid("instructions-per-frame must be at least 1".to_string()));
+        }
+        if matches!(self.instructions_per_frame, Some(instructions) if instructions.checked_mul(TIMER_HZ).is_none()) {
+            return Err(ParseError::Invalid(format!(
+                "instructions-per-frame must be at most {}",
+                u32::MAX / TIMER_HZ
+            )));
+        }
+        if matches!(self.cpu_hz, Some(cpu_hz) if cpu_hz < TIMER_HZ) {
+            return Err(ParseError::Invalid(format!("cpu-hz must be at least {}", TIMER_HZ)));
+        }
+        if self.cpu_hz.is_some() && self.instructions_per_frame.is_some() {
+            return Err(ParseError::Invalid("only one of cpu-hz and instructions-per-frame can be given".to_string()));
+        }
+        if self.vip_timing == Some(true) && self.cpu_hz().is_some() {
+            return Err(ParseError::Invalid(
+                "vip-timing can't be given with cpu-hz or instructions-per-frame".to_string(),
+            ));
+        }
+        if self.scale == Some(0) {
+            return Err(ParseError::Invalid("scale must be at least 1".to_string()));
+        }
+        if matches!(self.beep_frequency, Some(frequency) if frequency <= 0.0) {
+            return Err(ParseError::Invalid("beep-frequency must be above 0".to_string()));
+        }
+        if matches!(self.volume, Some(volume) if !(0.0..=1.0).contains(&volume)) {
+            return Err(ParseError::Invalid("volume must be from 0 to 1".to_string()));
+        }
+        if [self.beep_attack, self.beep_release].iter().any(|ms| matches!(ms, Some(ms) if *ms < 0.0)) {
+            return Err(ParseError::Invalid("beep-attack and beep-release can't be negative".to_string()));
+        }
+        if let Some(keys) = &self.keys {
+            KeyMap::empty().rebind(keys).map_err(|err| ParseError::Invalid(err.to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+fn quirks<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Quirks>, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    name.parse().map(Some).map_err(serde::de::Error::custom)
+}
+
+fn color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Color>, D::Error> {
+    let color = String::deserialize(deserializer)?;
+    parse_color(&color).map(Some).map_err(serde::de::Error::custom)
+}
+
+fn keymap<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<KeyMap>, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    KeyMap::preset(&name).map(Some).map_err(serde::de::Error::custom)
+}
+
+fn waveform<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Waveform>, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    name.parse().map(Some).map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    const ROM: [u8; 2] = [0x12, 0x00];
+
+    fn args(options: &[&str]) -> Args {
+        Args::try_parse_from(["chirp-8", "rom.ch8"].iter().chain(options)).unwrap()
+    }
+
+    /// A configuration with the defaults' table and the ROM's table.
+    fn config(defaults: &str, rom: &str) -> Config {
+        let source = format!("[defaults]\n{}\n[roms.{}]\n{}\n", defaults, RomHash::of(&ROM), rom);
+        Config::parse(&source).unwrap()
+    }
+
+    fn game(tickrate: Option<u32>) -> Game {
+        Game {
+            title: "Test".to_string(),
+            file: None,
+            platforms: Vec::new(),
+            tickrate,
+            colors: Vec::new(),
+            keys: BTreeMap::new(),
+        }
+    }
+
+    /// The CPU rate and whether VIP timing is on.
+    fn speed(config: &Config, options: &[&str], tickrate: Option<u32>) -> (u32, bool) {
+        let settings = config.settings(&args(options), RomHash::of(&ROM), Some(&game(tickrate)));
+        (settings.cpu_hz, settings.vip_timing)
+    }
+
+    #[test]
+    fn layers_the_rate() {
+        let none = Config::default();
+        assert_eq!(speed(&none, &[], None), (600, false));
+        let defaults = config("instructions-per-frame = 15", "");
+        assert_eq!(speed(&defaults, &[], None), (900, false));
+        assert_eq!(speed(&defaults, &[], Some(20)), (1200, false));
+        let rom = config("instructions-per-frame = 15", "cpu-hz = 700");
+        assert_eq!(speed(&rom, &[], Some(20)), (700, false));
+        assert_eq!(speed(&rom, &["--ipf", "30"], Some(20)), (1800, false));
+        assert_eq!(speed(&rom, &["--cpu-hz", "61"], Some(20)), (61, false));
+    }
+
+    #[test]
+    fn decides_vip_timing_with_the_rate() {
+        // A rate from a higher layer turns off VIP timing from a lower one.
+        let defaults = config("vip-timing = true", "");
+        assert_eq!(speed(&defaults, &[], None), (600, true));
+        assert_eq!(speed(&defaults, &[], Some(20)), (1200, false));
+        assert_eq!(speed(&defaults, &["--ipf", "30"], None), (1800, false));
+        let rom = config("vip-timing = true", "cpu-hz = 700");
+        assert_eq!(speed(&rom, &[], None), (700, false));
+        let rom = config("vip-timing = true", "vip-timing = false");
+        assert_eq!(speed(&rom, &[], None), (600, false));
+
+        // VIP timing from a higher layer overrides a rate from a lower one.
+        let rom = config("cpu-hz = 700", "vip-timing = true");
+        assert_eq!(speed(&rom, &[], Some(20)), (1200, true));
+        let rom = config("", "cpu-hz = 700");
+        assert_eq!(speed(&rom, &["--vip-timing"], None), (700, true));
+    }
+
+    #[test]
+    fn rejects_rates_that_overflow() {
+        let max = (u32::MAX / TIMER_HZ).to_string();
+        assert_eq!(speed(&Config::default(), &["--ipf", &max], None), (u32::MAX / TIMER_HZ * TIMER_HZ, false));
+        assert!(Args::try_parse_from(["chirp-8", "rom.ch8", "--ipf", "100000000"]).is_err());
+
+        assert!(Config::parse(&format!("[defaults]\ninstructions-per-frame = {}", max)).is_ok());
+        assert!(matches!(
+            Config::parse("[defaults]\ninstructions-per-frame = 100000000"),
+            Err(ParseError::Invalid(message)) if message == format!("instructions-per-frame must be at most {}", max)
+        ));
+        assert_eq!(speed(&Config::default(), &[], Some(100_000_000)), (600, false));
+    }
+
+    #[test]
+    fn rejects_vip_timing_with_a_rate() {
+        assert!(matches!(
+            Config::parse("[defaults]\nvip-timing = true\ncpu-hz = 700"),
+            Err(ParseError::Invalid(_))
+        ));
+        assert!(Config::parse("[defaults]\nvip-timing = false\ncpu-hz = 700").is_ok());
+        assert!(Args::try_parse_from(["chirp-8", "rom.ch8", "--vip-timing", "--ipf", "5"]).is_err());
+    }
+}
@@ -0,0 +1,155 @@
+use crate::emulator::{parse_color, Chip, Quirks};
+use crate::rom::RomHash;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::OnceLock;
+use thiserror::Error;
+
+/// The games known to the emulator, in the format of the `programs.json` file
+/// of the community CHIP-8 database (https://github.com/chip-8/chip-8-database).
+/// It isn't a copy of that file: it lists the ROMs in `roms/`, by the hashes of
+/// those files, with the titles, authors and years their file names give.
+const PROGRAMS: &str = include_str!("../data/programs.json");
+
+#[derive(Debug, Error)]
+pub enum DatabaseError {
+    #[error("invalid game database: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid game database: '{0}' is not the SHA-1 hash of a ROM")]
+    InvalidHash(String),
+    #[error("invalid game database: '{0}' is not a color")]
+    InvalidColor(String),
+}
+
+/// What the database knows about a ROM.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Game {
+    pub title: String,
+    /// The name the ROM's file is commonly found under.
+    pub file: Option<String>,
+    /// The platforms the ROM runs on, the one it was written for first.
+    pub platforms: Vec<String>,
+    /// The number of instructions per frame the ROM is meant to run at.
+    pub tickrate: Option<u32>,
+    /// The colors of the background, the first plane, the second plane and
+    /// both planes, as far as the ROM sets them.
+    pub colors: Vec<(f32, f32, f32)>,
+    /// The keypad keys the ROM uses for inputs such as `up`, `down` and `a`.
+    pub keys: BTreeMap<String, u8>,
+}
+
+/// Games by the SHA-1 hash of their ROM.
+#[derive(Clone, Debug, Default)]
+pub struct Database {
+    games: HashMap<RomHash, Game>,
+}
+
+#[derive(Deserialize)]
+struct Program {
+    title: String,
+    roms: BTreeMap<String, Rom>,
+}
+
+#[derive(Deserialize)]
+struct Rom {
+    file: Option<String>,
+    #[serde(default)]
+    platforms: Vec<String>,
+    tickrate: Option<u32>,
+    #[serde(default)]
+    colors: Colors,
+    #[serde(default)]
+    keys: BTreeMap<String, u8>,
+}
+
+#[derive(Default, Deserialize)]
+struct Colors {
+    #[serde(default)]
+    pixels: Vec<String>,
+}
+
+impl Database {
+    /// The database embedded in the emulator.
+    pub fn builtin() -> &'static Database {
+        static BUILTIN: OnceLock<Database> = OnceLock::new();
+        BUILTIN.get_or_init(|| Database::parse(PROGRAMS).expect("the embedded game database is invalid"))
+    }
+
+    /// Reads a database in the format of the community database's
+    /// `programs.json`.
+    pub fn parse(json: &str) -> Result<Self, DatabaseError> {
+        let programs: Vec<Program> = serde_json::from_str(json)?;
+
+        let mut games = HashMap::new();
+        for program in programs {
+            for (hash, rom) in program.roms {
+                let hash = hash.parse().map_err(|_| DatabaseError::InvalidHash(hash))?;
+                let colors = rom
+                    .colors
+                    .pixels
+                    .iter()
+                    .map(|color| parse_color(color).ok_or_else(|| DatabaseError::InvalidColor(color.clone())))
+                    .collect::<Result<_, _>>()?;
+                let game = Game {
+                    title: program.title.clone(),
+                    file: rom.file,
+                    platforms: rom.platforms,
+                    tickrate: rom.tickrate,
+                    colors,
+                    keys: rom.keys,
+                };
+                games.insert(hash, game);
+            }
+        }
+        Ok(Self { games })
+    }
+
+    pub fn get(&self, rom: RomHash) -> Option<&Game> {
+        self.games.get(&rom)
+    }
+
+    pub fn len(&self) -> usize {
+        self.games.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.games.is_empty()
+    }
+}
+
+impl Game {
+    /// The quirks of the first of the game's platforms that the emulator
+    /// knows.
+    pub fn quirks(&self) -> Option<Quirks> {
+        self.platforms.iter().find_map(|platform| platform_quirks(platform))
+    }
+
+    /// Gives the chip the game's quirks, speed and colors.
+    pub fn apply(&self, chip: &mut Chip) {
+        if let Some(quirks) = self.quirks() {
+            chip.set_quirks(quirks);
+        }
+        if let Some(tickrate) = self.tickrate {
+            chip.set_instructions_per_cycle(tickrate as usize);
+        }
+
+        let display = chip.display_mut();
+        let mut palette = display.palette();
+        for (color, game_color) in palette.iter_mut().zip(&self.colors) {
+            *color = *game_color;
+        }
+        display.set_palette(palette);
+    }
+}
+
+/// The quirks of a platform, by its name in the community database.
+fn platform_quirks(platform: &str) -> Option<Quirks> {
+    match platform {
+        "originalChip8" | "hybridVIP" => Some(Quirks::COSMAC_VIP),
+        "modernChip8" => Some(Quirks::MODERN),
+        "chip48" => Some(Quirks::CHIP_48),
+        "superchip1" | "superchip" => Some(Quirks::SUPER_CHIP),
+        "xochip" => Some(Quirks::XO_CHIP),
+        _ => None,
+    }
+}
@@ -0,0 +1,190 @@
+use crate::debugger::{Breakpoint, Comparison, Condition};
+use crate::emulator::{Access, Register};
+use crate::trace::{Category, UnknownCategory};
+use thiserror::Error;
+
+/// A debugger command, as typed into the REPL.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+    /// `break <address> [if <register> <comparison> <value>]`
+    Break(Breakpoint),
+    /// `delete <address>`
+    Delete(u16),
+    /// `watch <address> [r|w|rw]`
+    Watch(u16, Access),
+    /// `unwatch <address>`
+    Unwatch(u16),
+    /// `step [count]`
+    Step(usize),
+    /// `next`, which steps over subroutine calls.
+    Next,
+    /// `finish`, which runs until the current subroutine returns.
+    Finish,
+    /// `rstep`, which goes back to the previous instruction.
+    ReverseStep,
+    Continue,
+    Pause,
+    Registers,
+    Timers,
+    Keys,
+    /// `memory <address> [length]`
+    Memory(u16, u16),
+    /// Lists the breakpoints and watchpoints.
+    List,
+    /// `trace <categories>`, which prints executed instructions in the
+    /// categories.
+    Trace(Category),
+    /// `history [count]`, which prints the last instructions kept by the
+    /// trace buffer.
+    History(Option<usize>),
+    Help,
+    Quit,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum CommandError {
+    #[error("unknown command '{0}', try help")]
+    Unknown(String),
+    #[error("expected {0}")]
+    Missing(&'static str),
+    #[error("'{0}' is not a number")]
+    InvalidNumber(String),
+    #[error("'{0}' is not a register, expected V0-VF or I")]
+    InvalidRegister(String),
+    #[error("'{0}' is not a comparison, expected ==, !=, <, <=, > or >=")]
+    InvalidComparison(String),
+    #[error("unexpected '{0}'")]
+    Unexpected(String),
+    #[error(transparent)]
+    UnknownCategory(#[from] UnknownCategory),
+}
+
+pub const HELP: &str = "\
+break <addr> [if <reg> <op> <value>]  stop at an address, e.g. break 0x2a0 if v3 == 5
+delete <addr>                        remove a breakpoint
+watch <addr> [r|w|rw]                stop when memory is read or written (default w)
+unwatch <addr>                       remove a watchpoint
+list                                 list breakpoints and watchpoints
+step [n]                             execute n instructions (default 1)
+next                                 step, stepping over subroutine calls
+finish                               run until the current subroutine returns
+rstep                                go back to the previous instruction
+continue                             resume execution
+pause                                stop execution
+registers                            show V0-VF, I, PC and the stack
+timers                               show the delay and sound timers
+keys                                 show the pressed keys
+memory <addr> [len]                  dump memory (default 16 bytes)
+trace <categories>|all|off           print executed instructions, e.g. trace flow,draw
+history [n]                          show the last n instructions kept by --trace-buffer
+quit                                 exit the emulator
+Numbers are decimal, or hexadecimal when prefixed with 0x.";
+
+impl Command {
+    pub fn parse(line: &str) -> Result<Command, CommandError> {
+        let mut words = line.split_whitespace();
+        let name = words.next().ok_or(CommandError::Missing("a command"))?;
+
+        let command = match name {
+            "break" | "b" => {
+                let address = address(words.next())?;
+                let condition = match words.next() {
+                    None => None,
+                    Some("if") => Some(Condition {
+                        register: register(words.next())?,
+                        comparison: comparison(words.next())?,
+                        value: number(words.next(), "a value")?,
+                    }),
+                    Some(word) => return Err(CommandError::Unexpected(word.to_string())),
+                };
+                Command::Break(Breakpoint { address, condition })
+            }
+            "delete" | "d" => Command::Delete(address(words.next())?),
+            "watch" | "w" => {
+                let address = address(words.next())?;
+                let access = match words.next() {
+                    None | Some("w") => Access::WRITE,
+                    Some("r") => Access::READ,
+                    Some("rw") => Access::READ | Access::WRITE,
+                    Some(word) => return Err(CommandError::Unexpected(word.to_string())),
+                };
+                Command::Watch(address, access)
+            }
+            "unwatch" => Command::Unwatch(address(words.next())?),
+            "step" | "s" => match words.next() {
+                None => Command::Step(1),
+                Some(count) => Command::Step(number(Some(count), "a count")? as usize),
+            },
+            "next" | "n" => Command::Next,
+            "finish" | "f" => Command::Finish,
+            "rstep" | "rs" => Command::ReverseStep,
+            "continue" | "c" => Command::Continue,
+            "pause" | "p" => Command::Pause,
+            "registers" | "regs" | "r" => Command::Registers,
+            "timers" | "t" => Command::Timers,
+            "keys" | "k" => Command::Keys,
+            "memory" | "mem" | "x" => {
+                let address = address(words.next())?;
+                let len = match words.next() {
+                    None => 16,
+                    Some(len) => number(Some(len), "a length")?,
+                };
+                Command::Memory(address, len)
+            }
+            "list" | "l" => Command::List,
+            "trace" => Command::Trace(words.next().ok_or(CommandError::Missing("categories"))?.parse()?),
+            "history" => match words.next() {
+                None => Command::History(None),
+                Some(count) => Command::History(Some(number(Some(count), "a count")? as usize)),
+            },
+            "help" | "h" | "?" => Command::Help,
+            "quit" | "q" => Command::Quit,
+            _ => return Err(CommandError::Unknown(name.to_string())),
+        };
+
+        match words.next() {
+            Some(word) => Err(CommandError::Unexpected(word.to_string())),
+            None => Ok(command),
+        }
+    }
+}
+
+fn address(word: Option<&str>) -> Result<u16, CommandError> {
+    number(word, "an address")
+}
+
+fn number(word: Option<&str>, expected: &'static str) -> Result<u16, CommandError> {
+    let word = word.ok_or(CommandError::Missing(expected))?;
+    let parsed = match word.strip_prefix("0x") {
+        Some(hex) => u16::from_str_radix(hex, 16),
+        None => word.parse(),
+    };
+    parsed.map_err(|_| CommandError::InvalidNumber(word.to_string()))
+}
+
+fn register(word: Option<&str>) -> Result<Register, CommandError> {
+    let word = word.ok_or(CommandError::Missing("a register"))?;
+    let lowercase = word.to_ascii_lowercase();
+    if lowercase == "i" {
+        return Ok(Register::I);
+    }
+
+    lowercase
+        .strip_prefix('v')
+        .filter(|digit| digit.len() == 1)
+        .and_then(|digit| u8::from_str_radix(digit, 16).ok())
+        .map(Register::V)
+        .ok_or_else(|| CommandError::InvalidRegister(word.to_string()))
+}
+
+fn comparison(word: Option<&str>) -> Result<Comparison, CommandError> {
+    match word.ok_or(CommandError::Missing("a comparison"))? {
+        "==" => Ok(Comparison::Equal),
+        "!=" => Ok(Comparison::NotEqual),
+        "<" => Ok(Comparison::Less),
+        "<=" => Ok(Comparison::LessOrEqual),
+        ">" => Ok(Comparison::Greater),
+        ">=" => Ok(Comparison::GreaterOrEqual),
+        word => Err(CommandError::InvalidComparison(word.to_string())),
+    }
+}
@@ -0,0 +1,501 @@
+use crate::debugger::{Breakpoint, Debugger, Stop};
+use crate::emulator::{Access, Chip};
+use std::io::{self, ErrorKind, Read, Write};
+use std::fmt;
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+
+/// The largest packet accepted from, and sent to, the client.
+const PACKET_SIZE: usize = 0x1000;
+
+/// The registers, in the order of their numbers in the protocol. They are sent
+/// in Chip-8's big-endian byte order.
+const TARGET_XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+  <feature name="org.chirp8.core">
+    <reg name="v0" bitsize="8" type="uint8" regnum="0"/>
+    <reg name="v1" bitsize="8" type="uint8"/>
+    <reg name="v2" bitsize="8" type="uint8"/>
+    <reg name="v3" bitsize="8" type="uint8"/>
+    <reg name="v4" bitsize="8" type="uint8"/>
+    <reg name="v5" bitsize="8" type="uint8"/>
+    <reg name="v6" bitsize="8" type="uint8"/>
+    <reg name="v7" bitsize="8" type="uint8"/>
+    <reg name="v8" bitsize="8" type="uint8"/>
+    <reg name="v9" bitsize="8" type="uint8"/>
+    <reg name="va" bitsize="8" type="uint8"/>
+    <reg name="vb" bitsize="8" type="uint8"/>
+    <reg name="vc" bitsize="8" type="uint8"/>
+    <reg name="vd" bitsize="8" type="uint8"/>
+    <reg name="ve" bitsize="8" type="uint8"/>
+    <reg name="vf" bitsize="8" type="uint8"/>
+    <reg name="i" bitsize="16" type="data_ptr"/>
+    <reg name="pc" bitsize="16" type="code_ptr"/>
+    <reg name="sp" bitsize="8" type="uint8"/>
+    <reg name="dt" bitsize="8" type="uint8"/>
+    <reg name="st" bitsize="8" type="uint8"/>
+  </feature>
+</target>
+"#;
+
+const REGISTER_COUNT: usize = 21;
+
+/// The size of the address space, which is the most a watchpoint can cover.
+const ADDRESS_SPACE: usize = 0x10000;
+
+/// A change to the stub's connection, for the frontend to report.
+#[derive(Debug)]
+pub enum GdbEvent {
+    Connected(SocketAddr),
+    Disconnected(io::Error),
+}
+
+impl fmt::Display for GdbEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GdbEvent::Connected(address) => write!(f, "GDB client connected from {}.", address),
+            GdbEvent::Disconnected(err) => write!(f, "GDB client disconnected: {}", err),
+        }
+    }
+}
+
+/// Exposes a chip over the GDB remote serial protocol on a TCP port, so it
+/// can be driven by gdb or any other client that speaks the protocol. One
+/// client is served at a time. Like `Repl`, the socket is polled by calling
+/// `run_cycle` instead of `Chip::cpu_cycle`.
+pub struct GdbStub {
+    debugger: Debugger,
+    listener: TcpListener,
+    client: Option<TcpStream>,
+    input: Vec<u8>,
+    /// Whether the client is waiting for a stop reply after continuing or
+    /// stepping.
+    running: bool,
+    quit: bool,
+}
+
+impl GdbStub {
+    /// Listens for a client on the address. The chip is paused until a client
+    /// connects and continues it.
+    pub fn listen(address: impl ToSocketAddrs, chip: &mut Chip) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+
+        let mut debugger = Debugger::new();
+        debugger.pause(chip);
+
+        Ok(Self {
+            debugger,
+            listener,
+            client: None,
+            input: Vec::new(),
+            running: false,
+            quit: false,
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<std::net::SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Whether the client asked to kill the program.
+    pub fn quit_requested(&self) -> bool {
+        self.quit
+    }
+
+    /// Handles the packets received since the last call, then runs the rest of
+    /// the chip's cycle. Returns the clients that connected and disconnected
+    /// meanwhile.
+    pub fn run_cycle(&mut self, chip: &mut Chip) -> Vec<GdbEvent> {
+        let mut events = Vec::new();
+        if self.client.is_none() {
+            events.extend(self.accept(chip));
+        }
+
+        if self.client.is_some() {
+            if let Err(err) = self.receive(chip) {
+                self.disconnect(chip);
+                events.push(GdbEvent::Disconnected(err));
+            }
+        }
+
+        if let Some(stop) = self.debugger.run_cycle(chip) {
+            if self.running {
+                self.running = false;
+                let reply = stop_reply(stop, chip);
+                if let Err(err) = self.send(&reply) {
+                    self.disconnect(chip);
+                    events.push(GdbEvent::Disconnected(err));
+                }
+            }
+        }
+        events
+    }
+
+    fn accept(&mut self, chip: &mut Chip) -> Option<GdbEvent> {
+        let (stream, address) = self.listener.accept().ok()?;
+        stream.set_nonblocking(true).ok()?;
+        let _ = stream.set_nodelay(true);
+
+        self.debugger.pause(chip);
+        self.client = Some(stream);
+        self.input.clear();
+        self.running = false;
+        Some(GdbEvent::Connected(address))
+    }
+
+    /// Removes everything the client set up, and lets the program run freely.
+    fn disconnect(&mut self, chip: &mut Chip) {
+        self.client = None;
+        self.running = false;
+
+        let breakpoints: Vec<u16> = self.debugger.breakpoints().iter().map(|b| b.address).collect();
+        for address in breakpoints {
+            self.debugger.remove_breakpoint(address);
+        }
+        let watchpoints: Vec<u16> = chip.memory().watchpoints().map(|(address, _)| address).collect();
+        for address in watchpoints {
+            chip.memory_mut().remove_watchpoint(address);
+        }
+
+        self.debugger.resume(chip);
+    }
+
+    fn receive(&mut self, chip: &mut Chip) -> io::Result<()> {
+        let mut buffer = [0; 1024];
+        loop {
+            let client = match &mut self.client {
+                Some(client) => client,
+                None => return Ok(()),
+            };
+            match client.read(&mut buffer) {
+                Ok(0) => return Err(io::Error::new(ErrorKind::UnexpectedEof, "connection closed")),
+                Ok(len) => self.input.extend_from_slice(&buffer[..len]),
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        while let Some(packet) = self.next_packet()? {
+            if let Some(reply) = self.handle(chip, &packet) {
+                self.send(&reply)?;
+            }
+            if self.client.is_none() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Takes the next complete packet from the input, acknowledging it. An
+    /// interrupt from the client is returned as a packet of its own.
+    fn next_packet(&mut self) -> io::Result<Option<String>> {
+        loop {
+            match self.input.first() {
+                None => return Ok(None),
+                Some(0x03) => {
+                    self.input.remove(0);
+                    return Ok(Some("\x03".to_string()));
+                }
+                Some(b'$') => break,
+                // Acknowledgements, and anything else outside of a packet.
+                Some(_) => {
+                    self.input.remove(0);
+                }
+            }
+        }
+
+        let end = match self.input.iter().position(|byte| *byte == b'#') {
+            Some(end) if end + 2 < self.input.len() => end,
+            _ => return Ok(None),
+        };
+
+        let packet: Vec<u8> = self.input.drain(..end + 3).collect();
+        let data = &packet[1..end];
+        let checksum = std::str::from_utf8(&packet[end + 1..]).ok().and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        if checksum == Some(checksum_of(data)) {
+            self.write_raw(b"+")?;
+            Ok(Some(String::from_utf8_lossy(data).into_owned()))
+        } else {
+            self.write_raw(b"-")?;
+            self.next_packet()
+        }
+    }
+
+    /// Handles a packet, returning the reply. Continuing and stepping reply
+    /// once execution stops, so they return None.
+    fn handle(&mut self, chip: &mut Chip, packet: &str) -> Option<String> {
+        let (command, arguments) = packet.split_at(packet.chars().next().map_or(0, char::len_utf8));
+
+        match command {
+            "\x03" => {
+                self.debugger.pause(chip);
+                if self.running {
+                    self.running = false;
+                    return Some("S02".to_string());
+                }
+                None
+            }
+            "c" | "s" => {
+                if let Some(address) = parse_hex(arguments) {
+                    chip.registers_mut().set_pc(address as u16);
+                }
+                if command == "c" {
+                    self.debugger.resume(chip);
+                } else {
+                    self.debugger.step(chip, 1);
+                }
+                self.running = true;
+                None
+            }
+            "D" => {
+                let _ = self.send("OK");
+                self.disconnect(chip);
+                None
+            }
+            "k" => {
+                self.quit = true;
+                self.disconnect(chip);
+                None
+            }
+            _ => Some(self.reply(chip, command, arguments).unwrap_or_else(|| "E01".to_string())),
+        }
+    }
+
+    /// Replies to a packet that doesn't affect execution, or returns None if
+    /// the packet is malformed.
+    fn reply(&mut self, chip: &mut Chip, command: &str, arguments: &str) -> Option<String> {
+        let reply = match command {
+            "?" => "S05".to_string(),
+            "g" => (0..REGISTER_COUNT).map(|n| encode_hex(&read_register(chip, n))).collect(),
+            "G" => {
+                let bytes = decode_hex(arguments)?;
+                let mut offset = 0;
+                for n in 0..REGISTER_COUNT {
+                    let size = register_size(n);
+                    if let Some(value) = bytes.get(offset..offset + size) {
+                        write_register(chip, n, value);
+                    }
+                    offset += size;
+                }
+                "OK".to_string()
+            }
+            "p" => {
+                let n = parse_hex(arguments).filter(|n| *n < REGISTER_COUNT)?;
+                encode_hex(&read_register(chip, n))
+            }
+            "P" => {
+                let (n, value) = arguments.split_once('=')?;
+                let n = parse_hex(n).filter(|n| *n < REGISTER_COUNT)?;
+                let value = decode_hex(value).filter(|value| value.len() == register_size(n))?;
+                write_register(chip, n, &value);
+                "OK".to_string()
+            }
+            "m" => {
+                let (address, len) = parse_range(arguments)?;
+                let address = address as u16;
+                let bytes: Vec<u8> = (0..len.min(PACKET_SIZE / 2))
+                    .map(|offset| chip.memory().peek(address.wrapping_add(offset as u16)))
+                    .collect();
+                encode_hex(&bytes)
+            }
+            "M" => {
+                let (range, data) = arguments.split_once(':')?;
+                let (address, len) = parse_range(range)?;
+                let address = address as u16;
+                let bytes = decode_hex(data).filter(|bytes| bytes.len() == len)?;
+                for (offset, byte) in bytes.iter().enumerate() {
+                    chip.memory_mut().poke(address.wrapping_add(offset as u16), *byte);
+                }
+                "OK".to_string()
+            }
+            "Z" | "z" => self.handle_breakpoint(chip, command == "Z", arguments),
+            "q" => self.handle_query(arguments),
+            "H" | "T" => "OK".to_string(),
+            // An empty reply tells the client the packet isn't supported.
+            _ => String::new(),
+        };
+        Some(reply)
+    }
+
+    fn handle_breakpoint(&mut self, chip: &mut Chip, insert: bool, arguments: &str) -> String {
+        let mut fields = arguments.split(',');
+        let kind = fields.next();
+        let address = match fields.next().and_then(parse_hex) {
+            Some(address) => address as u16,
+            None => return "E01".to_string(),
+        };
+
+        let access = match kind {
+            Some("0") => {
+                if insert {
+                    self.debugger.add_breakpoint(Breakpoint { address, condition: None });
+                } else {
+                    self.debugger.remove_breakpoint(address);
+                }
+                return "OK".to_string();
+            }
+            Some("2") => Access::WRITE,
+            Some("3") => Access::READ,
+            Some("4") => Access::READ | Access::WRITE,
+            _ => return String::new(),
+        };
+
+        let len = match fields.next().map_or(Some(1), parse_hex) {
+            Some(len) if len <= ADDRESS_SPACE => len,
+            _ => return "E01".to_string(),
+        };
+        for offset in 0..len {
+            let address = address.wrapping_add(offset as u16);
+            if insert {
+                chip.memory_mut().add_watchpoint(address, access);
+            } else {
+                chip.memory_mut().remove_watchpoint(address);
+            }
+        }
+        "OK".to_string()
+    }
+
+    fn handle_query(&self, query: &str) -> String {
+        if query.starts_with("Supported") {
+            format!("PacketSize={:x};qXfer:features:read+", PACKET_SIZE)
+        } else if let Some(range) = query.strip_prefix("Xfer:features:read:target.xml:") {
+            let (offset, len) = match parse_range(range) {
+                Some(range) => range,
+                None => return "E01".to_string(),
+            };
+            let document = TARGET_XML.as_bytes();
+            let start = offset.min(document.len());
+            let end = start.saturating_add(len).min(document.len());
+            // 'l' marks the last part of the document, 'm' that more follows.
+            let marker = if end == document.len() { 'l' } else { 'm' };
+            format!("{}{}", marker, String::from_utf8_lossy(&document[start..end]))
+        } else if query == "Attached" {
+            "1".to_string()
+        } else if query == "C" {
+            "QC1".to_string()
+        } else if query == "fThreadInfo" {
+            "m1".to_string()
+        } else if query == "sThreadInfo" {
+            "l".to_string()
+        } else {
+            String::new()
+        }
+    }
+
+    fn send(&mut self, data: &str) -> io::Result<()> {
+        let packet = format!("${}#{:02x}", data, checksum_of(data.as_bytes()));
+        self.write_raw(packet.as_bytes())
+    }
+
+    fn write_raw(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let client = match &mut self.client {
+            Some(client) => client,
+            None => return Ok(()),
+        };
+
+        let mut written = 0;
+        while written < bytes.len() {
+            match client.write(&bytes[written..]) {
+                Ok(0) => return Err(io::Error::new(ErrorKind::WriteZero, "connection closed")),
+                Ok(len) => written += len,
+                Err(err) if err.kind() == ErrorKind::WouldBlock || err.kind() == ErrorKind::Interrupted => {
+                    std::thread::yield_now()
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn stop_reply(stop: Stop, chip: &Chip) -> String {
+    match stop {
+        Stop::Watchpoint(hit) => {
+            let watched = chip
+                .memory()
+                .watchpoints()
+                .find(|(address, _)| *address == hit.address)
+                .map(|(_, access)| access);
+            let kind = if watched == Some(Access::READ | Access::WRITE) {
+                "awatch"
+            } else if hit.access == Access::READ {
+                "rwatch"
+            } else {
+                "watch"
+            };
+            format!("T05{}:{:x};", kind, hit.address)
+        }
+        _ => "S05".to_string(),
+    }
+}
+
+fn register_size(n: usize) -> usize {
+    match n {
+        16 | 17 => 2,
+        _ => 1,
+    }
+}
+
+fn read_register(chip: &Chip, n: usize) -> Vec<u8> {
+    let registers = chip.registers();
+    match n {
+        0..=15 => vec![registers.v(n as u8)],
+        16 => registers.i().to_be_bytes().to_vec(),
+        17 => registers.pc().to_be_bytes().to_vec(),
+        18 => vec![registers.stack().len() as u8],
+        19 => vec![chip.timers().delay()],
+        _ => vec![chip.timers().sound()],
+    }
+}
+
+fn write_register(chip: &mut Chip, n: usize, value: &[u8]) {
+    let word = || u16::from_be_bytes([value[0], value[1]]);
+    match n {
+        0..=15 => chip.registers_mut().set_v(n as u8, value[0]),
+        16 => chip.registers_mut().set_i(word()),
+        17 => chip.registers_mut().set_pc(word()),
+        // The stack grows with zeroed return addresses, or is popped.
+        18 => {
+            let registers = chip.registers_mut();
+            while registers.stack().len() < value[0] as usize {
+                registers.push_stack(0);
+            }
+            while registers.stack().len() > value[0] as usize {
+                registers.pop_stack();
+            }
+        }
+        19 => chip.timers_mut().set_delay(value[0]),
+        _ => chip.timers_mut().set_sound(value[0]),
+    }
+}
+
+fn checksum_of(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte))
+}
+
+fn parse_hex(text: &str) -> Option<usize> {
+    usize::from_str_radix(text, 16).ok()
+}
+
+/// Parses an `address,length` pair.
+fn parse_range(text: &str) -> Option<(usize, usize)> {
+    let (address, len) = text.split_once(',')?;
+    Some((parse_hex(address)?, parse_hex(len)?))
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    text.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            let pair = std::str::from_utf8(pair).ok().filter(|pair| pair.len() == 2)?;
+            u8::from_str_radix(pair, 16).ok()
+        })
+        .collect()
+}
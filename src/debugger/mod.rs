@@ -0,0 +1,21 @@
+//! A debugger for the emulator core. It runs a `Chip` instruction by
+//! instruction, stopping on breakpoints, watchpoints and completed steps by
+//! pausing the chip, and is driven by text commands from a REPL.
+
+mod breakpoint;
+pub use breakpoint::*;
+
+mod command;
+pub use command::*;
+
+mod gdb;
+pub use gdb::*;
+
+mod session;
+pub use session::*;
+
+mod source;
+pub use source::*;
+
+mod repl;
+pub use repl::*;
@@ -0,0 +1,256 @@
+use crate::debugger::{Command, Debugger, Source, Stop, HELP};
+use crate::emulator::{Access, Chip, Instruction};
+use crate::rewind::Rewind;
+use crate::trace::{StdoutSink, TraceSink};
+use std::io::{BufRead, Write};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+
+const PROMPT: &str = "(chirp8) ";
+
+/// Drives a debugger with commands read from stdin. Stdin is read on its own
+/// thread, so the REPL can be used alongside a window whose event loop calls
+/// `run_cycle` instead of `Chip::cpu_cycle`.
+pub struct Repl {
+    debugger: Debugger,
+    /// The program's source, given with `spawn_with_source`.
+    source: Option<Source>,
+    lines: Receiver<String>,
+    quit: bool,
+}
+
+impl Repl {
+    /// Starts reading commands from stdin. The chip is paused, so breakpoints
+    /// can be set before the program starts.
+    pub fn spawn(chip: &mut Chip) -> Self {
+        Self::spawn_with_source(chip, None)
+    }
+
+    /// Starts reading commands from stdin, with the source the program was
+    /// assembled from. Its `:breakpoint` directives become breakpoints, and
+    /// the source line is shown wherever execution stops.
+    pub fn spawn_with_source(chip: &mut Chip, source: Option<Source>) -> Self {
+        let (sender, lines) = mpsc::channel();
+        std::thread::spawn(move || {
+            let stdin = std::io::stdin();
+            for line in stdin.lock().lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut debugger = Debugger::new();
+        debugger.pause(chip);
+
+        println!("Debugger ready, type help for a list of commands.");
+        for (breakpoint, name) in source.iter().flat_map(Source::breakpoints) {
+            debugger.add_breakpoint(breakpoint);
+            println!("Breakpoint {} at {}.", name, breakpoint);
+        }
+        print_location(chip, source.as_ref());
+        prompt();
+
+        Self { debugger, source, lines, quit: false }
+    }
+
+    /// Executes the commands typed since the last call, then runs the rest of
+    /// the chip's cycle. Reverse steps go back through the rewind history.
+    pub fn run_cycle(&mut self, chip: &mut Chip, rewind: &mut Rewind) {
+        loop {
+            match self.lines.try_recv() {
+                Ok(line) => {
+                    if !line.trim().is_empty() {
+                        self.execute_line(chip, rewind, &line);
+                    }
+                    prompt();
+                }
+                Err(TryRecvError::Empty) => break,
+                // Stdin was closed, which leaves nothing to control the chip.
+                Err(TryRecvError::Disconnected) => {
+                    self.quit = true;
+                    break;
+                }
+            }
+        }
+
+        if let Some(stop) = self.debugger.run_cycle(chip) {
+            // The prompt has already been printed, so start a new line.
+            println!();
+            print_stop(stop, self.source.as_ref());
+            print_location(chip, self.source.as_ref());
+            prompt();
+        }
+    }
+
+    /// Whether the quit command was given, or stdin was closed.
+    pub fn quit_requested(&self) -> bool {
+        self.quit
+    }
+
+    fn execute_line(&mut self, chip: &mut Chip, rewind: &mut Rewind, line: &str) {
+        let command = match Command::parse(line) {
+            Ok(command) => command,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+
+        match command {
+            Command::Break(breakpoint) => {
+                self.debugger.add_breakpoint(breakpoint);
+                println!("Breakpoint at {}.", breakpoint);
+            }
+            Command::Delete(address) => {
+                if !self.debugger.remove_breakpoint(address) {
+                    println!("There is no breakpoint at {:#05x}.", address);
+                }
+            }
+            Command::Watch(address, access) => {
+                chip.memory_mut().add_watchpoint(address, access);
+                println!("Watching {:#06x} for {}.", address, access_name(access));
+            }
+            Command::Unwatch(address) => {
+                if !chip.memory_mut().remove_watchpoint(address) {
+                    println!("There is no watchpoint at {:#06x}.", address);
+                }
+            }
+            Command::List => {
+                for breakpoint in self.debugger.breakpoints() {
+                    println!("break {}", breakpoint);
+                }
+                for (address, access) in chip.memory().watchpoints() {
+                    println!("watch {:#06x} for {}", address, access_name(access));
+                }
+            }
+            Command::Step(count) => self.debugger.step(chip, count),
+            Command::Next => self.debugger.step_over(chip),
+            Command::Finish => {
+                if !self.debugger.step_out(chip) {
+                    println!("Not in a subroutine.");
+                }
+            }
+            Command::ReverseStep => {
+                self.debugger.pause(chip);
+                if rewind.step_back(chip) {
+                    print_location(chip, self.source.as_ref());
+                } else {
+                    println!("There is no earlier history to step back through.");
+                }
+            }
+            Command::Continue => self.debugger.resume(chip),
+            Command::Pause => {
+                self.debugger.pause(chip);
+                print_location(chip, self.source.as_ref());
+            }
+            Command::Registers => print_registers(chip),
+            Command::Timers => {
+                println!("DT {:#04x}  ST {:#04x}", chip.timers().delay(), chip.timers().sound());
+            }
+            Command::Keys => {
+                let pressed: Vec<String> = (0..16)
+                    .filter(|key| chip.keyboard().is_pressed(*key))
+                    .map(|key| format!("{:X}", key))
+                    .collect();
+                if pressed.is_empty() {
+                    println!("No keys are pressed.");
+                } else {
+                    println!("Pressed: {}", pressed.join(" "));
+                }
+            }
+            Command::Memory(address, len) => print_memory(chip, address, len),
+            Command::Trace(categories) => {
+                let tracer = chip.tracer_mut();
+                tracer.set_categories(categories);
+                if !categories.is_empty() && !tracer.has_sinks() {
+                    tracer.add_sink(Box::new(StdoutSink));
+                }
+                println!("Tracing {}.", categories);
+            }
+            Command::History(count) => match chip.tracer().ring_buffer() {
+                Some(ring_buffer) if !ring_buffer.is_empty() => {
+                    let skipped = ring_buffer.len().saturating_sub(count.unwrap_or(ring_buffer.len()));
+                    for record in ring_buffer.records().skip(skipped) {
+                        StdoutSink.record(&record);
+                    }
+                }
+                Some(_) => println!("No instructions have been traced yet."),
+                None => println!("There is no trace buffer, start with --trace-buffer <n>."),
+            },
+            Command::Help => println!("{}", HELP),
+            Command::Quit => self.quit = true,
+        }
+    }
+}
+
+fn prompt() {
+    print!("{}", PROMPT);
+    let _ = std::io::stdout().flush();
+}
+
+fn access_name(access: Access) -> &'static str {
+    if access == Access::READ | Access::WRITE {
+        "reads and writes"
+    } else if access == Access::READ {
+        "reads"
+    } else {
+        "writes"
+    }
+}
+
+fn print_stop(stop: Stop, source: Option<&Source>) {
+    match stop {
+        Stop::Breakpoint(breakpoint) => match source.and_then(|source| source.breakpoint_name(breakpoint.address)) {
+            Some(name) => println!("Stopped at breakpoint {} ({}).", breakpoint, name),
+            None => println!("Stopped at breakpoint {}.", breakpoint),
+        },
+        Stop::Watchpoint(hit) => {
+            let access = if hit.access == Access::READ { "read from" } else { "written to" };
+            println!("Stopped after {:#06x} was {}.", hit.address, access);
+        }
+        Stop::Step => {}
+        Stop::Halted => println!("The program paused itself."),
+    }
+}
+
+/// Prints the instruction about to be executed, and the source line it was
+/// assembled from.
+fn print_location(chip: &Chip, source: Option<&Source>) {
+    let pc = chip.registers().pc();
+    let opcode = (chip.memory().peek(pc) as u16) << 8 | chip.memory().peek(pc.wrapping_add(1)) as u16;
+    match Instruction::decode(opcode) {
+        Ok(instruction) => println!("{:#05x}: {}", pc, instruction),
+        Err(err) => println!("{:#05x}: {}", pc, err),
+    }
+    if let Some((line, text)) = source.and_then(|source| source.line(pc)) {
+        println!("{:>5} | {}", line, text);
+    }
+}
+
+fn print_registers(chip: &Chip) {
+    let registers = chip.registers();
+    for row in 0..2 {
+        let values: Vec<String> = (row * 8..row * 8 + 8)
+            .map(|x| format!("V{:X} {:02X}", x, registers.v(x)))
+            .collect();
+        println!("{}", values.join("  "));
+    }
+    println!("I  {:#06x}  PC {:#06x}", registers.i(), registers.pc());
+
+    let stack: Vec<String> = registers.stack().iter().map(|address| format!("{:#05x}", address)).collect();
+    if stack.is_empty() {
+        println!("The stack is empty.");
+    } else {
+        println!("Stack: {}", stack.join(" "));
+    }
+}
+
+fn print_memory(chip: &Chip, address: u16, len: u16) {
+    for row in (0..len).step_by(8) {
+        let start = address.wrapping_add(row);
+        let bytes: Vec<String> = (0..(len - row).min(8))
+            .map(|offset| format!("{:02X}", chip.memory().peek(start.wrapping_add(offset))))
+            .collect();
+        println!("{:#06x}: {}", start, bytes.join(" "));
+    }
+}
@@ -0,0 +1,179 @@
+use crate::debugger::Breakpoint;
+use crate::emulator::{Chip, Instruction, WatchHit};
+
+/// Why the debugger stopped execution.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stop {
+    Breakpoint(Breakpoint),
+    Watchpoint(WatchHit),
+    /// A step, step over or step out completed.
+    Step,
+    /// The program paused itself, by exiting or jumping to its own address.
+    Halted,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Mode {
+    Run,
+    /// Stops after the given number of instructions.
+    Step(usize),
+    /// Stops when a call returns to the address after it.
+    StepOver { address: u16, depth: usize },
+    /// Stops when the stack gets shallower than the given depth.
+    StepOut { depth: usize },
+}
+
+/// Runs a chip while watching for breakpoints, watchpoints and steps. The
+/// chip is stopped by pausing it, so a stopped chip can be inspected and
+/// changed freely before execution is resumed.
+pub struct Debugger {
+    breakpoints: Vec<Breakpoint>,
+    mode: Mode,
+    /// Set when execution is resumed, so the breakpoint execution stopped at
+    /// doesn't stop it again straight away.
+    resuming: bool,
+    /// The address of the last executed instruction. Instructions that wait,
+    /// for a key or for the display, execute at the same address over and
+    /// over, and only hit a breakpoint the first time.
+    last_pc: Option<u16>,
+}
+
+impl Debugger {
+    pub fn new() -> Self {
+        Self {
+            breakpoints: Vec::new(),
+            mode: Mode::Run,
+            resuming: false,
+            last_pc: None,
+        }
+    }
+
+    /// Adds a breakpoint, replacing any breakpoint already at its address.
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        self.remove_breakpoint(breakpoint.address);
+        self.breakpoints.push(breakpoint);
+    }
+
+    pub fn remove_breakpoint(&mut self, address: u16) -> bool {
+        let len = self.breakpoints.len();
+        self.breakpoints.retain(|breakpoint| breakpoint.address != address);
+        self.breakpoints.len() != len
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    pub fn pause(&mut self, chip: &mut Chip) {
+        self.mode = Mode::Run;
+        chip.set_paused(true);
+    }
+
+    pub fn resume(&mut self, chip: &mut Chip) {
+        self.start(chip, Mode::Run);
+    }
+
+    /// Executes the given number of instructions, then stops.
+    pub fn step(&mut self, chip: &mut Chip, count: usize) {
+        if count > 0 {
+            self.start(chip, Mode::Step(count));
+        }
+    }
+
+    /// Executes one instruction, or a whole subroutine if the instruction is a
+    /// call.
+    pub fn step_over(&mut self, chip: &mut Chip) {
+        let pc = chip.registers().pc();
+        let opcode = (chip.memory().peek(pc) as u16) << 8 | chip.memory().peek(pc.wrapping_add(1)) as u16;
+
+        let mode = match Instruction::decode(opcode) {
+            Ok(Instruction::CALL_addr(_)) => Mode::StepOver {
+                address: pc.wrapping_add(2),
+                depth: chip.registers().stack().len(),
+            },
+            _ => Mode::Step(1),
+        };
+        self.start(chip, mode);
+    }
+
+    /// Runs until the current subroutine returns. Returns false, without
+    /// running, if no subroutine is being executed.
+    pub fn step_out(&mut self, chip: &mut Chip) -> bool {
+        let depth = chip.registers().stack().len();
+        if depth == 0 {
+            return false;
+        }
+
+        self.start(chip, Mode::StepOut { depth });
+        true
+    }
+
+    fn start(&mut self, chip: &mut Chip, mode: Mode) {
+        self.mode = mode;
+        self.resuming = true;
+        // Forget accesses made while the debugger wasn't running the chip.
+        chip.memory_mut().take_watch_hit();
+        chip.set_paused(false);
+    }
+
+    /// Runs the rest of the chip's current cycle, like `Chip::cpu_cycle`,
+    /// unless execution stops first.
+    pub fn run_cycle(&mut self, chip: &mut Chip) -> Option<Stop> {
+        while !chip.paused() {
+            let pc = chip.registers().pc();
+
+            if !self.resuming && self.last_pc != Some(pc) {
+                let registers = chip.registers();
+                if let Some(breakpoint) = self.breakpoints.iter().find(|breakpoint| breakpoint.hit(registers)) {
+                    let breakpoint = *breakpoint;
+                    return self.stop(chip, Stop::Breakpoint(breakpoint));
+                }
+            }
+            self.resuming = false;
+
+            chip.step();
+            self.last_pc = Some(pc);
+
+            if chip.paused() {
+                self.mode = Mode::Run;
+                return Some(Stop::Halted);
+            }
+            if let Some(hit) = chip.memory_mut().take_watch_hit() {
+                return self.stop(chip, Stop::Watchpoint(hit));
+            }
+            if self.step_completed(chip) {
+                return self.stop(chip, Stop::Step);
+            }
+
+            if chip.cycle_position() == 0 {
+                break;
+            }
+        }
+        None
+    }
+
+    fn step_completed(&mut self, chip: &Chip) -> bool {
+        let registers = chip.registers();
+        match &mut self.mode {
+            Mode::Run => false,
+            Mode::Step(count) => {
+                *count -= 1;
+                *count == 0
+            }
+            Mode::StepOver { address, depth } => registers.pc() == *address && registers.stack().len() == *depth,
+            Mode::StepOut { depth } => registers.stack().len() < *depth,
+        }
+    }
+
+    fn stop(&mut self, chip: &mut Chip, stop: Stop) -> Option<Stop> {
+        self.mode = Mode::Run;
+        chip.set_paused(true);
+        Some(stop)
+    }
+}
+
+impl Default for Debugger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,43 @@
+use crate::assembler::{Program, SourceMap};
+use crate::debugger::Breakpoint;
+
+/// The Octo source a program was assembled from, so the debugger can show the
+/// line being executed and stop at the program's `:breakpoint` directives.
+#[derive(Clone, Debug)]
+pub struct Source {
+    lines: Vec<String>,
+    source_map: SourceMap,
+    breakpoints: Vec<(u16, String)>,
+}
+
+impl Source {
+    pub fn new(text: &str, program: &Program) -> Self {
+        Self {
+            lines: text.lines().map(str::to_string).collect(),
+            source_map: program.source_map.clone(),
+            breakpoints: program.breakpoints.clone(),
+        }
+    }
+
+    /// The number and text of the line the address was assembled from.
+    pub fn line(&self, address: u16) -> Option<(usize, &str)> {
+        let line = self.source_map.line(address)?;
+        let text = self.lines.get(line.checked_sub(1)?)?;
+        Some((line, text.trim()))
+    }
+
+    /// A breakpoint for each `:breakpoint` directive, and its name.
+    pub fn breakpoints(&self) -> impl Iterator<Item = (Breakpoint, &str)> + '_ {
+        self.breakpoints
+            .iter()
+            .map(|(address, name)| (Breakpoint { address: *address, condition: None }, name.as_str()))
+    }
+
+    /// The name of the `:breakpoint` directive at the address.
+    pub fn breakpoint_name(&self, address: u16) -> Option<&str> {
+        self.breakpoints
+            .iter()
+            .find(|(breakpoint, _)| *breakpoint == address)
+            .map(|(_, name)| name.as_str())
+    }
+}
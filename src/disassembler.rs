@@ -0,0 +1,264 @@
+use crate::emulator::Instruction;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// The address programs are loaded at, and where execution starts.
+pub const PROGRAM_START: u16 = 0x200;
+
+/// What a byte of the program was found to be used as.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ByteKind {
+    /// Never reached by control flow, nor read as a sprite.
+    Unknown,
+    /// The first byte of an instruction.
+    Code,
+    /// A byte of an instruction other than its first.
+    Operand,
+    /// Read by a Dxyn instruction after I was set by Annn.
+    Sprite,
+}
+
+/// Why an address was given a label. When an address is labelled for several
+/// reasons, the one listed first wins.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LabelKind {
+    /// The target of a CALL.
+    Subroutine,
+    /// The target of a JP.
+    Jump,
+    /// An address loaded into I.
+    Data,
+}
+
+/// The result of disassembling a program. Code is separated from data by
+/// following control flow from the start of the program, so code that is
+/// only reached through computed jumps (Bnnn) is listed as unknown bytes.
+pub struct Disassembly {
+    rom: Vec<u8>,
+    kinds: Vec<ByteKind>,
+    labels: BTreeMap<u16, LabelKind>,
+}
+
+/// The state that is carried along a path of control flow.
+#[derive(Clone, Copy)]
+struct Path {
+    address: u16,
+    /// The value of I, when it is known.
+    i: Option<u16>,
+    /// The number of selected XO-CHIP drawing planes.
+    planes: u16,
+}
+
+impl Disassembly {
+    /// Disassembles a program, as it would be loaded at `PROGRAM_START`.
+    pub fn new(rom: &[u8]) -> Self {
+        let mut disassembly = Self {
+            rom: rom.to_vec(),
+            kinds: vec![ByteKind::Unknown; rom.len()],
+            labels: BTreeMap::new(),
+        };
+
+        disassembly.add_label(PROGRAM_START, LabelKind::Jump);
+        disassembly.trace(Path { address: PROGRAM_START, i: None, planes: 1 });
+        disassembly
+    }
+
+    fn trace(&mut self, start: Path) {
+        use Instruction::*;
+
+        let mut queue = vec![start];
+        while let Some(mut path) = queue.pop() {
+            while let Some(opcode) = self.word(path.address) {
+                if matches!(self.kind(path.address), Some(ByteKind::Code) | Some(ByteKind::Operand)) {
+                    break;
+                }
+
+                let instruction = match Instruction::decode(opcode) {
+                    Ok(instruction) => instruction,
+                    Err(_) => break,
+                };
+                if instruction == LD_I_long && self.word(path.address.wrapping_add(2)).is_none() {
+                    break;
+                }
+
+                self.set_kind(path.address, ByteKind::Code);
+                for offset in 1..instruction.size() {
+                    self.set_kind(path.address.wrapping_add(offset), ByteKind::Operand);
+                }
+                let next = path.address.wrapping_add(instruction.size());
+
+                match instruction {
+                    JP_addr(addr) => {
+                        self.add_label(addr, LabelKind::Jump);
+                        queue.push(Path { address: addr, ..path });
+                        break;
+                    }
+                    JP_V0_addr(addr) => {
+                        self.add_label(addr, LabelKind::Jump);
+                        break;
+                    }
+                    CALL_addr(addr) => {
+                        self.add_label(addr, LabelKind::Subroutine);
+                        queue.push(Path { address: addr, i: None, ..path });
+                        // The subroutine may have changed I.
+                        path.i = None;
+                    }
+                    RET | EXIT => break,
+                    SE_Vx_byte(..) | SNE_Vx_byte(..) | SE_Vx_Vy(..) | SNE_Vx_Vy(..) | SKP_Vx(..)
+                    | SKNP_Vx(..) => {
+                        let skipped = match self.word(next) {
+                            Some(0xF000) => 4,
+                            _ => 2,
+                        };
+                        queue.push(Path { address: next.wrapping_add(skipped), ..path });
+                    }
+                    LD_I_addr(addr) => {
+                        self.add_label(addr, LabelKind::Data);
+                        path.i = Some(addr);
+                    }
+                    LD_I_long => {
+                        let addr = self.word(path.address.wrapping_add(2)).unwrap_or(0);
+                        self.add_label(addr, LabelKind::Data);
+                        path.i = Some(addr);
+                    }
+                    PLANE_n(n) => path.planes = (n & 0b11).count_ones() as u16,
+                    DRW_Vx_Vy_n(_, _, n) => {
+                        if let Some(i) = path.i {
+                            let len = if n == 0 { 32 } else { n as u16 };
+                            for offset in 0..len * path.planes {
+                                self.mark_sprite(i.wrapping_add(offset));
+                            }
+                        }
+                    }
+                    // Depending on the quirks, loading and storing registers
+                    // may leave I pointing somewhere else.
+                    ADD_I_Vx(_) | LD_F_Vx(_) | LD_HF_Vx(_) | LD_I_Vx(_) | LD_Vx_I(_) => path.i = None,
+                    _ => {}
+                }
+
+                path.address = next;
+            }
+        }
+    }
+
+    fn index(&self, address: u16) -> Option<usize> {
+        let index = address.checked_sub(PROGRAM_START)? as usize;
+        if index < self.rom.len() {
+            Some(index)
+        } else {
+            None
+        }
+    }
+
+    fn word(&self, address: u16) -> Option<u16> {
+        let left = *self.rom.get(self.index(address)?)? as u16;
+        let right = *self.rom.get(self.index(address.wrapping_add(1))?)? as u16;
+        Some(left << 8 | right)
+    }
+
+    fn set_kind(&mut self, address: u16, kind: ByteKind) {
+        if let Some(index) = self.index(address) {
+            self.kinds[index] = kind;
+        }
+    }
+
+    /// Marks a byte as sprite data, unless it is already known to be code.
+    fn mark_sprite(&mut self, address: u16) {
+        if self.kind(address) == Some(ByteKind::Unknown) {
+            self.set_kind(address, ByteKind::Sprite);
+        }
+    }
+
+    fn add_label(&mut self, address: u16, kind: LabelKind) {
+        let label = self.labels.entry(address).or_insert(kind);
+        *label = (*label).min(kind);
+    }
+
+    /// What the byte at the address was found to be used as, or None if the
+    /// address is outside of the program.
+    pub fn kind(&self, address: u16) -> Option<ByteKind> {
+        self.index(address).map(|index| self.kinds[index])
+    }
+
+    /// The name of the label at the address, if there is one.
+    pub fn label(&self, address: u16) -> Option<String> {
+        self.labels.get(&address).map(|kind| {
+            let prefix = match kind {
+                LabelKind::Subroutine => "sub",
+                LabelKind::Jump => "label",
+                LabelKind::Data => "data",
+            };
+            format!("{}_{:03X}", prefix, address)
+        })
+    }
+
+    /// The address operand of the instruction, with its label if it has one.
+    fn target(&self, address: u16) -> String {
+        self.label(address).unwrap_or_else(|| format!("{:#05x}", address))
+    }
+
+    /// Formats the instruction at the address, using labels for addresses.
+    fn format_instruction(&self, address: u16, instruction: Instruction) -> String {
+        use Instruction::*;
+
+        match instruction {
+            JP_addr(addr) => format!("JP   {}", self.target(addr)),
+            CALL_addr(addr) => format!("CALL {}", self.target(addr)),
+            LD_I_addr(addr) => format!("LD   I, {}", self.target(addr)),
+            JP_V0_addr(addr) => format!("JP   V0, {}", self.target(addr)),
+            LD_I_long => {
+                let addr = self.word(address.wrapping_add(2)).unwrap_or(0);
+                format!("LD   I, long {}", self.target(addr))
+            }
+            _ => instruction.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Disassembly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut index = 0;
+        while index < self.rom.len() {
+            let address = PROGRAM_START.wrapping_add(index as u16);
+
+            if let Some(label) = self.label(address) {
+                writeln!(f, "{}:", label)?;
+            }
+
+            match self.kinds[index] {
+                ByteKind::Code => {
+                    let opcode = self.word(address).unwrap_or(0);
+                    let instruction = Instruction::decode(opcode).expect("code was decoded while tracing");
+                    let size = instruction.size() as usize;
+                    let bytes: Vec<String> = self.rom[index..index + size]
+                        .iter()
+                        .map(|byte| format!("{:02X}", byte))
+                        .collect();
+
+                    writeln!(
+                        f,
+                        "    {:#05x}: {:<12} {}",
+                        address,
+                        bytes.join(" "),
+                        self.format_instruction(address, instruction)
+                    )?;
+                    index += size;
+                }
+                kind => {
+                    let byte = self.rom[index];
+                    write!(f, "    {:#05x}: {:<12} DB   {:#04x}", address, format!("{:02X}", byte), byte)?;
+                    if kind == ByteKind::Sprite {
+                        let pixels: String = (0..8)
+                            .rev()
+                            .map(|bit| if byte >> bit & 1 == 1 { '#' } else { '.' })
+                            .collect();
+                        write!(f, "  ; {}", pixels)?;
+                    }
+                    writeln!(f)?;
+                    index += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,11 +1,15 @@
 #![allow(non_snake_case)]
-use std::borrow::BorrowMut;
-
-use crate::emulator::{Display, Keyboard, Memory, Registers, Timers};
-use ears::AudioController;
-use rand::Rng;
-
-const INSTRUCTIONS_PER_CYCLE: usize = 10;
+use crate::emulator::{
+    sprites, vip_cycles, Display, Instruction, Keyboard, Memory, Quirks, RandomKind, RandomSource, Registers, StateError, StateReader,
+    StateWriter, Timers, Timing, Xorshift, STATE_MAGIC, STATE_VERSION, VIP_INTERPRETER_CYCLES,
+};
+use crate::database::{Database, Game};
+use crate::rom::RomHash;
+use crate::trace::{Category, Note, RegisterSnapshot, TraceRecord, Tracer};
+
+/// The number of instructions executed per cycle, which is a frame at 60 Hz,
+/// unless the chip is given another with `set_instructions_per_cycle`.
+pub const DEFAULT_INSTRUCTIONS_PER_CYCLE: usize = 10;
 
 pub struct Chip {
     memory: Memory,
@@ -13,88 +17,266 @@ pub struct Chip {
     keyboard: Keyboard,
     display: Display,
     timers: Timers,
-    sound: Option<ears::Sound>,
+    quirks: Quirks,
+    rpl_flags: [u8; 16],
+    audio_pattern: Option<[u8; 16]>,
+    pitch: u8,
+    random: Box<dyn RandomSource>,
     paused: bool,
     waiting_for_key: bool,
-    first_instruction: bool,
+    /// The number of instructions executed so far in the current cycle.
+    cycle_position: usize,
+    instructions_per_cycle: usize,
+    timing: Timing,
+    /// The machine cycles left in the current cycle with COSMAC VIP timing.
+    /// An instruction that runs past the end of a cycle takes the cycles it
+    /// overran by from the next one.
+    vip_cycles_left: i32,
+    tracer: Tracer,
+    /// Set by instructions that do something unusual, for the trace.
+    trace_note: Option<Note>,
 }
 
 impl Chip {
-    pub fn new() -> Self {
-        let sound = match ears::Sound::new("./sound/440hz.wav") {
-            Ok(mut sound) => {
-                sound.set_looping(true);
-                Some(sound)
-            },
-            Err(err) => {
-                println!("{}", err);
-                None
-            }
-        };
-
+    pub fn new(quirks: Quirks) -> Self {
         Self {
-            memory: Memory::new(),
+            memory: Memory::with_size(quirks.memory_size()),
             registers: Registers::new(),
             keyboard: Keyboard::new(),
-            // TODO: Command line arguments
+            // Frontends set the scale and palette through display_mut.
             display: Display::new(10, (0.0, 0.0, 0.0), (1.0, 1.0, 1.0)),
             timers: Timers::new(),
-            sound,
+            quirks,
+            rpl_flags: [0; 16],
+            audio_pattern: None,
+            pitch: 64,
+            random: Box::new(Xorshift::from_entropy()),
             paused: false,
             waiting_for_key: false,
-            first_instruction: true,
+            cycle_position: 0,
+            instructions_per_cycle: DEFAULT_INSTRUCTIONS_PER_CYCLE,
+            timing: Timing::Instructions,
+            vip_cycles_left: VIP_INTERPRETER_CYCLES as i32,
+            tracer: Tracer::new(),
+            trace_note: None,
         }
     }
 
+    /// Executes the rest of the current cycle, which is cut short if the chip
+    /// gets paused.
     pub fn cpu_cycle(&mut self) {
-        self.first_instruction = true;
+        while !self.paused() {
+            self.step();
 
-        for _ in 0..INSTRUCTIONS_PER_CYCLE {
-            if !self.paused() {
-                let instruction = self.fetch_instruction();
-                self.execute_instruction(instruction);
+            if self.cycle_position == 0 {
+                break;
             }
-            self.first_instruction = false;
         }
+    }
 
-        if let Some(sound) = self.sound.borrow_mut() {
-            if self.timers.sound() > 0  && !sound.is_playing() {
-                sound.play();
-            } else if self.timers.sound() == 0 && sound.is_playing() {
-                sound.stop();
-            }
+    /// Executes a single instruction, unless the chip is paused. The timers
+    /// are updated after the last instruction of each cycle.
+    pub fn step(&mut self) {
+        if self.paused() {
+            return;
+        }
+
+        let instruction = self.fetch_instruction();
+        if self.timing == Timing::CosmacVip {
+            self.vip_cycles_left -= vip_cycles(instruction, &self.registers) as i32;
         }
+        self.execute_instruction(instruction);
 
-        if !self.paused() {
+        self.cycle_position += 1;
+        let cycle_ended = match self.timing {
+            Timing::Instructions => self.cycle_position >= self.instructions_per_cycle,
+            Timing::CosmacVip => self.vip_cycles_left <= 0,
+        };
+        if cycle_ended {
+            self.cycle_position = 0;
+            if self.timing == Timing::CosmacVip {
+                self.vip_cycles_left += VIP_INTERPRETER_CYCLES as i32;
+            }
             self.timers.update();
             self.keyboard.update();
         }
+    }
 
+    /// The number of instructions executed so far in the current cycle.
+    pub fn cycle_position(&self) -> usize {
+        self.cycle_position
     }
 
-    pub fn load_rom(&mut self, rom_data: &[u8]) {
-        self.memory.load_rom(rom_data);
+    pub fn instructions_per_cycle(&self) -> usize {
+        self.instructions_per_cycle
     }
 
-    pub fn load_rom_from_path(&mut self, path: &std::path::Path) -> std::io::Result<()> {
-        let mut file = std::fs::File::open(path)?;
-        let mut rom_data = Vec::new();
+    /// Sets how many instructions are executed per cycle, which is at least
+    /// one. If the current cycle is already past the new length, it ends after
+    /// the next instruction. It has no effect with COSMAC VIP timing.
+    pub fn set_instructions_per_cycle(&mut self, instructions: usize) {
+        self.instructions_per_cycle = instructions.max(1);
+    }
 
-        std::io::Read::read_to_end(&mut file, &mut rom_data)?;
+    pub fn timing(&self) -> Timing {
+        self.timing
+    }
 
-        self.load_rom(&rom_data);
+    /// Sets how the length of cycles is decided. Switching to COSMAC VIP
+    /// timing starts the machine cycle count of the current cycle afresh.
+    pub fn set_timing(&mut self, timing: Timing) {
+        if timing != self.timing {
+            self.vip_cycles_left = VIP_INTERPRETER_CYCLES as i32;
+        }
+        self.timing = timing;
+    }
+
+    /// Loads a ROM where programs start, failing with
+    /// `io::ErrorKind::InvalidData` if it is too large for the memory of the
+    /// chip's platform.
+    pub fn load_rom(&mut self, rom_data: &[u8]) -> std::io::Result<()> {
+        self.memory.load_rom(rom_data)
+    }
 
+    /// Starts over with the ROM loaded into fresh memory and registers, as if
+    /// the machine had been switched off and on. The quirks, the number of
+    /// instructions per cycle, the random number source, the display's scale
+    /// and palette, watchpoints and tracing are kept, as are the SUPER-CHIP
+    /// RPL flags, which persist between programs. The chip is left unchanged
+    /// if the ROM doesn't fit in memory.
+    pub fn reset(&mut self, rom_data: &[u8]) -> std::io::Result<()> {
+        let mut memory = Memory::with_size(self.quirks.memory_size());
+        for (address, access) in self.memory.watchpoints() {
+            memory.add_watchpoint(address, access);
+        }
+        memory.load_rom(rom_data)?;
+        let mut display = Display::new(self.display.scale(), (0.0, 0.0, 0.0), (1.0, 1.0, 1.0));
+        display.set_palette(self.display.palette());
+
+        self.memory = memory;
+        self.registers = Registers::new();
+        self.keyboard = Keyboard::new();
+        self.display = display;
+        self.timers = Timers::new();
+        self.audio_pattern = None;
+        self.pitch = 64;
+        self.paused = false;
+        self.waiting_for_key = false;
+        self.cycle_position = 0;
+        self.vip_cycles_left = VIP_INTERPRETER_CYCLES as i32;
         Ok(())
     }
 
-    pub fn process_input(&mut self, input: glutin::event::KeyboardInput) {
-        self.keyboard.process_input(input);
+    /// Loads a ROM from a file. If the ROM is in the game database, the chip
+    /// takes on its quirks, speed and colors, and the game is returned.
+    pub fn load_rom_from_path(&mut self, path: &std::path::Path) -> std::io::Result<Option<&'static Game>> {
+        let rom_data = std::fs::read(path)?;
+        // The game's platform decides how much memory the ROM has to fit in.
+        let game = Database::builtin().get(RomHash::of(&rom_data));
+        if let Some(game) = game {
+            game.apply(self);
+        }
+        self.load_rom(&rom_data)?;
+        Ok(game)
+    }
+
+    /// Presses the key with the given value on the hexadecimal keypad.
+    pub fn press_key(&mut self, key: u8) {
+        self.keyboard.press(key);
+    }
+
+    /// Releases the key with the given value on the hexadecimal keypad.
+    pub fn release_key(&mut self, key: u8) {
+        self.keyboard.release(key);
+    }
+
+    /// Whether the buzzer should be sounding, which is the case for as long as
+    /// the sound timer is active.
+    pub fn sound_active(&self) -> bool {
+        self.timers.sound() > 0
     }
 
     pub fn display(&self) -> &Display {
         &self.display
     }
 
+    /// The display, for frontends to set its scale and palette.
+    pub fn display_mut(&mut self) -> &mut Display {
+        &mut self.display
+    }
+
+    pub fn registers(&self) -> &Registers {
+        &self.registers
+    }
+
+    pub fn registers_mut(&mut self) -> &mut Registers {
+        &mut self.registers
+    }
+
+    pub fn memory(&self) -> &Memory {
+        &self.memory
+    }
+
+    pub fn memory_mut(&mut self) -> &mut Memory {
+        &mut self.memory
+    }
+
+    pub fn timers(&self) -> &Timers {
+        &self.timers
+    }
+
+    pub fn timers_mut(&mut self) -> &mut Timers {
+        &mut self.timers
+    }
+
+    pub fn keyboard(&self) -> &Keyboard {
+        &self.keyboard
+    }
+
+    /// The XO-CHIP audio pattern buffer, once the program has loaded one.
+    /// Its 128 bits are played back one after the other while the sound timer
+    /// is active, at the rate returned by `playback_rate`, in place of the
+    /// beeper's tone.
+    pub fn audio_pattern(&self) -> Option<[u8; 16]> {
+        self.audio_pattern
+    }
+
+    pub fn pitch(&self) -> u8 {
+        self.pitch
+    }
+
+    /// The rate in bits per second the audio pattern buffer is played back at.
+    pub fn playback_rate(&self) -> f64 {
+        4000.0 * 2f64.powf((self.pitch as f64 - 64.0) / 48.0)
+    }
+
+    pub fn quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    /// Sets the quirks, resizing memory if the platform's size differs. Bytes
+    /// past the end of smaller memory are lost.
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.memory.resize(quirks.memory_size());
+        self.quirks = quirks;
+    }
+
+    /// Replaces the source of random numbers, which is a `Xorshift` seeded
+    /// from the operating system by default.
+    pub fn set_random_source(&mut self, random: Box<dyn RandomSource>) {
+        self.random = random;
+    }
+
+    pub fn random_kind(&self) -> RandomKind {
+        self.random.kind()
+    }
+
+    /// Sets the state of the random number source, so runs that start from
+    /// the same seed and input are the same.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.random.set_state(seed);
+    }
+
     pub fn paused(&self) -> bool {
         self.paused
     }
@@ -103,78 +285,222 @@ impl Chip {
         self.paused = paused;
     }
 
+    /// Saves everything a program can change, in a versioned binary format
+    /// that can be restored with `load_state`. The quirks, the number of
+    /// instructions per cycle, the display's scale and palette, debugging state
+    /// and tracing belong to the frontend and are not saved.
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut writer = StateWriter::new();
+        STATE_MAGIC.iter().for_each(|byte| writer.u8(*byte));
+        writer.u16(STATE_VERSION);
+
+        self.memory.write_state(&mut writer);
+        self.registers.write_state(&mut writer);
+        self.timers.write_state(&mut writer);
+        self.display.write_state(&mut writer);
+        self.keyboard.write_state(&mut writer);
+        self.rpl_flags.iter().for_each(|flag| writer.u8(*flag));
+        writer.bool(self.audio_pattern.is_some());
+        self.audio_pattern.unwrap_or_default().iter().for_each(|byte| writer.u8(*byte));
+        writer.u8(self.pitch);
+        writer.u8(self.random.kind().code());
+        writer.u64(self.random.state());
+        writer.bool(self.paused);
+        writer.bool(self.waiting_for_key);
+        writer.u32(self.cycle_position as u32);
+        writer.u32(self.vip_cycles_left as u32);
+
+        writer.into_bytes()
+    }
+
+    /// Restores a state saved by `save_state`. The chip is left unchanged if
+    /// the state can't be loaded.
+    pub fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        let mut reader = StateReader::new(state);
+
+        let mut magic = [0; 4];
+        for byte in magic.iter_mut() {
+            *byte = reader.u8().map_err(|_| StateError::InvalidMagic)?;
+        }
+        if magic != STATE_MAGIC {
+            return Err(StateError::InvalidMagic);
+        }
+        let version = reader.u16()?;
+        if version != STATE_VERSION {
+            return Err(StateError::UnsupportedVersion { found: version, expected: STATE_VERSION });
+        }
+
+        let memory = self.memory.read_state(&mut reader)?;
+        let registers = Registers::read_state(&mut reader)?;
+        let timers = Timers::read_state(&mut reader)?;
+        let display = self.display.read_state(&mut reader)?;
+        let keyboard = Keyboard::read_state(&mut reader)?;
+        let mut rpl_flags = [0; 16];
+        for flag in rpl_flags.iter_mut() {
+            *flag = reader.u8()?;
+        }
+        let audio_pattern_loaded = reader.bool()?;
+        let mut audio_pattern = [0; 16];
+        for byte in audio_pattern.iter_mut() {
+            *byte = reader.u8()?;
+        }
+        let audio_pattern = Some(audio_pattern).filter(|_| audio_pattern_loaded);
+        let pitch = reader.u8()?;
+        let random_kind = RandomKind::from_code(reader.u8()?).ok_or(StateError::Invalid("unknown random source"))?;
+        let random = reader.u64()?;
+        let paused = reader.bool()?;
+        let waiting_for_key = reader.bool()?;
+        let cycle_position = reader.u32()? as usize;
+        let vip_cycles_left = reader.u32()? as i32;
+        reader.finish()?;
+        let random_source = match random_kind.source() {
+            _ if random_kind == self.random.kind() => None,
+            Some(source) => Some(source),
+            None => return Err(StateError::Invalid("the state was saved with a custom random source")),
+        };
+
+        self.memory = memory;
+        self.registers = registers;
+        self.timers = timers;
+        self.display = display;
+        self.keyboard = keyboard;
+        self.rpl_flags = rpl_flags;
+        self.audio_pattern = audio_pattern;
+        self.pitch = pitch;
+        if let Some(source) = random_source {
+            self.random = source;
+        }
+        self.random.set_state(random);
+        self.paused = paused;
+        self.waiting_for_key = waiting_for_key;
+        self.cycle_position = cycle_position;
+        self.vip_cycles_left = vip_cycles_left;
+        Ok(())
+    }
+
+    pub fn tracer(&self) -> &Tracer {
+        &self.tracer
+    }
+
+    pub fn tracer_mut(&mut self) -> &mut Tracer {
+        &mut self.tracer
+    }
+
     fn fetch_instruction(&mut self) -> u16 {
-        let left = self.memory.read(self.registers.pc()) as u16;
-        let right = self.memory.read(self.registers.pc() + 1) as u16;
+        let instruction = self.read_word(self.registers.pc());
 
         self.registers.increment_pc();
 
+        instruction
+    }
+
+    /// Reads an instruction, without triggering watchpoints.
+    fn read_word(&self, address: u16) -> u16 {
+        let left = self.memory.peek(address) as u16;
+        let right = self.memory.peek(address.wrapping_add(1)) as u16;
+
         left << 8 | right
     }
 
-    fn execute_instruction(&mut self, instruction: u16) {
-        let addr = instruction & 0x0FFF; // 0x0nnn
-        let x = ((instruction & 0x0F00) >> 8) as u8; // 0x0x00
-        let y = ((instruction & 0x00F0) >> 4) as u8; // 0x00x0
-        let byte = (instruction & 0x00FF) as u8; // 0x00xx
-        let nibble = (instruction & 0x000F) as u8; // 0x000x
+    /// Skips over the next instruction, which is 4 bytes long if it is the
+    /// XO-CHIP F000 NNNN instruction.
+    fn skip_next_instruction(&mut self) {
+        let next = self.read_word(self.registers.pc());
 
-        match instruction & 0xF000 {
-            0x0000 => match instruction {
-                0x00E0 => self.CLS(),
-                0x00EE => self.RET(),
-                _ => self.debug_println_instruction("INVD", format!("{:<#06x}", instruction))
-            },
-            0x1000 => self.JP_addr(addr),
-            0x2000 => self.CALL_addr(addr),
-            0x3000 => self.SE_Vx_byte(x, byte),
-            0x4000 => self.SNE_Vx_byte(x, byte),
-            0x5000 => self.SE_Vx_Vy(x, y),
-            0x6000 => self.LD_Vx_byte(x, byte),
-            0x7000 => self.ADD_Vx_byte(x, byte),
-            0x8000 => match instruction & 0x000F {
-                0x0 => self.LD_Vx_Vy(x, y),
-                0x1 => self.OR_Vx_Vy(x, y),
-                0x2 => self.AND_Vx_Vy(x, y),
-                0x3 => self.XOR_Vx_Vy(x, y),
-                0x4 => self.ADD_Vx_Vy(x, y),
-                0x5 => self.SUB_Vx_Vy(x, y),
-                0x6 => self.SHR_Vx_Vy(x, y),
-                0x7 => self.SUBN_Vx_Vy(x, y),
-                0xE => self.SHL_Vx_Vy(x, y),
-                _ => self.debug_println_instruction("INVD", format!("{:<#06x}", instruction))
-            },
-            0x9000 => match instruction & 0x000F {
-                0x0 => self.SNE_Vx_Vy(x, y),
-                _ => self.debug_println_instruction("INVD", format!("{:<#06x}", instruction))
-            },
-            0xA000 => self.LD_I_addr(addr),
-            0xB000 => self.JP_V0_addr(addr),
-            0xC000 => self.RND_Vx_byte(x, byte),
-            0xD000 => self.DRW_Vx_Vy_n(x, y, nibble),
-            0xE000 => match instruction & 0x00FF {
-                0x9E => self.SKP_Vx(x),
-                0xA1 => self.SKNP_Vx(x),
-                _ => self.debug_println_instruction("INVD", format!("{:<#06x}", instruction))
-            },
-            0xF000 => match instruction & 0x00FF {
-                0x07 => self.LD_Vx_DT(x),
-                0x0A => self.LD_Vx_K(x),
-                0x15 => self.LD_DT_Vx(x),
-                0x18 => self.LD_ST_Vx(x),
-                0x1E => self.ADD_I_Vx(x),
-                0x29 => self.LD_F_Vx(x),
-                0x33 => self.LD_B_Vx(x),
-                0x55 => self.LD_I_Vx(x),
-                0x65 => self.LD_Vx_I(x),
-                _ => self.debug_println_instruction("INVD", format!("{:<#06x}", instruction))
-            },
-            _ => self.debug_println_instruction("INVD", format!("{:<#06x}", instruction))
+        self.registers.increment_pc();
+        if next == 0xF000 {
+            self.registers.increment_pc();
+        }
+    }
+
+    fn execute_instruction(&mut self, opcode: u16) {
+        let pc = self.registers.pc().wrapping_sub(2);
+        let instruction = Instruction::decode(opcode).ok();
+        let category = Category::of(instruction);
+
+        // Waiting for a key executes the same instruction every cycle, so only
+        // trace it once.
+        let before = if self.tracer.is_enabled(category) && !self.waiting_for_key {
+            Some(RegisterSnapshot::of(&self.registers))
+        } else {
+            None
+        };
+        self.trace_note = None;
+
+        match instruction {
+            Some(instruction) => self.dispatch(instruction),
+            None => self.trace_note = Some(Note::InvalidInstruction),
+        }
+
+        if let Some(before) = before {
+            let record = TraceRecord {
+                pc,
+                opcode,
+                instruction,
+                category,
+                note: self.trace_note.take(),
+                before,
+                after: RegisterSnapshot::of(&self.registers),
+            };
+            self.tracer.record(&record);
         }
     }
 
-    fn debug_println_instruction(&self, instruction: impl Into<String>, description: impl Into<String>) {
-        println!("{:<#05x}: {:<16} # {}", self.registers.pc() - 2, instruction.into(), description.into());
+    fn dispatch(&mut self, instruction: Instruction) {
+        use Instruction::*;
+
+        match instruction {
+            SCD_nibble(nibble) => self.SCD_nibble(nibble),
+            SCU_nibble(nibble) => self.SCU_nibble(nibble),
+            CLS => self.CLS(),
+            RET => self.RET(),
+            SCR => self.SCR(),
+            SCL => self.SCL(),
+            EXIT => self.EXIT(),
+            LOW => self.LOW(),
+            HIGH => self.HIGH(),
+            JP_addr(addr) => self.JP_addr(addr),
+            CALL_addr(addr) => self.CALL_addr(addr),
+            SE_Vx_byte(x, byte) => self.SE_Vx_byte(x, byte),
+            SNE_Vx_byte(x, byte) => self.SNE_Vx_byte(x, byte),
+            SE_Vx_Vy(x, y) => self.SE_Vx_Vy(x, y),
+            LD_I_Vx_Vy(x, y) => self.LD_I_Vx_Vy(x, y),
+            LD_Vx_Vy_I(x, y) => self.LD_Vx_Vy_I(x, y),
+            LD_Vx_byte(x, byte) => self.LD_Vx_byte(x, byte),
+            ADD_Vx_byte(x, byte) => self.ADD_Vx_byte(x, byte),
+            LD_Vx_Vy(x, y) => self.LD_Vx_Vy(x, y),
+            OR_Vx_Vy(x, y) => self.OR_Vx_Vy(x, y),
+            AND_Vx_Vy(x, y) => self.AND_Vx_Vy(x, y),
+            XOR_Vx_Vy(x, y) => self.XOR_Vx_Vy(x, y),
+            ADD_Vx_Vy(x, y) => self.ADD_Vx_Vy(x, y),
+            SUB_Vx_Vy(x, y) => self.SUB_Vx_Vy(x, y),
+            SHR_Vx_Vy(x, y) => self.SHR_Vx_Vy(x, y),
+            SUBN_Vx_Vy(x, y) => self.SUBN_Vx_Vy(x, y),
+            SHL_Vx_Vy(x, y) => self.SHL_Vx_Vy(x, y),
+            SNE_Vx_Vy(x, y) => self.SNE_Vx_Vy(x, y),
+            LD_I_addr(addr) => self.LD_I_addr(addr),
+            JP_V0_addr(addr) => self.JP_V0_addr(addr),
+            RND_Vx_byte(x, byte) => self.RND_Vx_byte(x, byte),
+            DRW_Vx_Vy_n(x, y, nibble) => self.DRW_Vx_Vy_n(x, y, nibble),
+            SKP_Vx(x) => self.SKP_Vx(x),
+            SKNP_Vx(x) => self.SKNP_Vx(x),
+            LD_I_long => self.LD_I_long(),
+            PLANE_n(n) => self.PLANE_n(n),
+            AUDIO => self.AUDIO(),
+            LD_Vx_DT(x) => self.LD_Vx_DT(x),
+            LD_Vx_K(x) => self.LD_Vx_K(x),
+            LD_DT_Vx(x) => self.LD_DT_Vx(x),
+            LD_ST_Vx(x) => self.LD_ST_Vx(x),
+            ADD_I_Vx(x) => self.ADD_I_Vx(x),
+            LD_F_Vx(x) => self.LD_F_Vx(x),
+            LD_HF_Vx(x) => self.LD_HF_Vx(x),
+            LD_B_Vx(x) => self.LD_B_Vx(x),
+            PITCH_Vx(x) => self.PITCH_Vx(x),
+            LD_I_Vx(x) => self.LD_I_Vx(x),
+            LD_Vx_I(x) => self.LD_Vx_I(x),
+            LD_R_Vx(x) => self.LD_R_Vx(x),
+            LD_Vx_R(x) => self.LD_Vx_R(x),
+        }
     }
 
     // --- Instructions ---
@@ -182,7 +508,6 @@ impl Chip {
     /// 00E0 - CLS
     /// Clear the display.
     fn CLS(&mut self) {
-        self.debug_println_instruction("CLS", "Clear the display.");
         self.display.clear();
     }
 
@@ -192,22 +517,76 @@ impl Chip {
     /// The interpreter sets the program counter to the address at the top of
     /// the stack, then subtracts 1 from the stack pointer.
     fn RET(&mut self) {
-        self.debug_println_instruction("RET", "Return from a subroutine.");
-
         let pc = self.registers.pop_stack();
         self.registers.set_pc(pc);
     }
 
+    /// 00Cn - SCD nibble
+    /// Scroll display n lines down.
+    ///
+    /// Introduced by SUPER-CHIP. The rows scrolled in at the top are blank.
+    fn SCD_nibble(&mut self, nibble: u8) {
+        self.display.scroll_down(nibble as usize);
+    }
+
+    /// 00Dn - SCU nibble
+    /// Scroll display n lines up.
+    ///
+    /// Introduced by XO-CHIP. The rows scrolled in at the bottom are blank.
+    fn SCU_nibble(&mut self, nibble: u8) {
+        self.display.scroll_up(nibble as usize);
+    }
+
+    /// 00FB - SCR
+    /// Scroll display 4 pixels right.
+    ///
+    /// Introduced by SUPER-CHIP. The columns scrolled in on the left are blank.
+    fn SCR(&mut self) {
+        self.display.scroll_right(4);
+    }
+
+    /// 00FC - SCL
+    /// Scroll display 4 pixels left.
+    ///
+    /// Introduced by SUPER-CHIP. The columns scrolled in on the right are
+    /// blank.
+    fn SCL(&mut self) {
+        self.display.scroll_left(4);
+    }
+
+    /// 00FD - EXIT
+    /// Exit the interpreter.
+    ///
+    /// Introduced by SUPER-CHIP. The interpreter is paused, as there is
+    /// nothing left to execute.
+    fn EXIT(&mut self) {
+        self.set_paused(true);
+    }
+
+    /// 00FE - LOW
+    /// Disable extended screen mode.
+    ///
+    /// Introduced by SUPER-CHIP. Switches back to the 64x32 resolution.
+    fn LOW(&mut self) {
+        self.display.set_hires(false);
+    }
+
+    /// 00FF - HIGH
+    /// Enable extended screen mode.
+    ///
+    /// Introduced by SUPER-CHIP. Switches to the 128x64 resolution.
+    fn HIGH(&mut self) {
+        self.display.set_hires(true);
+    }
+
     /// 1nnn - JP addr
     /// Jump to location nnn.
     /// 
     /// The interpreter sets the program counter to nnn.
     fn JP_addr(&mut self, addr: u16) {
-        self.debug_println_instruction(format!("JP   {:#05x}", addr), "The interpreter sets the program counter to addr.");
-
-        if addr == self.registers.pc() - 2 {
+        if addr == self.registers.pc().wrapping_sub(2) {
             self.set_paused(true);
-            self.debug_println_instruction("PAUS", "The previous instruction jumped to its own address.")
+            self.trace_note = Some(Note::SelfJump);
         }
 
         self.registers.set_pc(addr);
@@ -219,8 +598,6 @@ impl Chip {
     /// The interpreter increments the stack pointer, then puts the current PC
     /// on the top of the stack. The PC is then set to nnn.
     fn CALL_addr(&mut self, addr: u16) {
-        self.debug_println_instruction(format!("CALL {:#05x}", addr), "Call subroutine at addr.");
-
         self.registers.push_stack(self.registers.pc());
         self.registers.set_pc(addr);
     }
@@ -230,10 +607,8 @@ impl Chip {
     /// The interpreter compares register Vx to kk, and if they are equal,
     /// increments the program counter by 2.
     fn SE_Vx_byte(&mut self, x: u8, byte: u8) {
-        self.debug_println_instruction(format!("SE   V{:01x}, {:#04x}", x, byte), "Skip next instruction if Vx = byte.");
-
         if self.registers.v(x) == byte {
-            self.registers.increment_pc();
+            self.skip_next_instruction();
         }
     }
 
@@ -243,10 +618,8 @@ impl Chip {
     /// The interpreter compares register Vx to kk, and if they are not equal,
     /// increments the program counter by 2.
     fn SNE_Vx_byte(&mut self, x: u8, byte: u8) {
-        self.debug_println_instruction(format!("SNE  V{:01x}, {:#04x}", x, byte), "Skip next instruction if Vx != byte.");
-
         if self.registers.v(x) != byte {
-            self.registers.increment_pc();
+            self.skip_next_instruction();
         }
     }
 
@@ -256,10 +629,39 @@ impl Chip {
     /// The interpreter compares register Vx to register Vy, and if they are
     /// equal, increments the program counter by 2.
     fn SE_Vx_Vy(&mut self, x: u8, y: u8) {
-        self.debug_println_instruction(format!("SE   V{:01x}, V{:01x}", x, y), "Skip next instruction if Vx = Vy.");
-
         if self.registers.v(x) == self.registers.v(y) {
-            self.registers.increment_pc();
+            self.skip_next_instruction();
+        }
+    }
+
+    /// 5xy2 - LD [I], Vx-Vy
+    /// Store registers Vx through Vy in memory starting at location I.
+    ///
+    /// Introduced by XO-CHIP. If x is greater than y, the registers are
+    /// stored in reverse order. I is not modified.
+    fn LD_I_Vx_Vy(&mut self, x: u8, y: u8) {
+        for (offset, register) in Self::register_range(x, y).enumerate() {
+            self.memory.write(self.registers.i().wrapping_add(offset as u16), self.registers.v(register));
+        }
+    }
+
+    /// 5xy3 - LD Vx-Vy, [I]
+    /// Read registers Vx through Vy from memory starting at location I.
+    ///
+    /// Introduced by XO-CHIP. If x is greater than y, the registers are
+    /// loaded in reverse order. I is not modified.
+    fn LD_Vx_Vy_I(&mut self, x: u8, y: u8) {
+        for (offset, register) in Self::register_range(x, y).enumerate() {
+            self.registers.set_v(register, self.memory.read(self.registers.i().wrapping_add(offset as u16)));
+        }
+    }
+
+    /// The registers from x to y inclusive, counting down if x is greater.
+    fn register_range(x: u8, y: u8) -> Box<dyn Iterator<Item = u8>> {
+        if x <= y {
+            Box::new(x..=y)
+        } else {
+            Box::new((y..=x).rev())
         }
     }
 
@@ -268,8 +670,6 @@ impl Chip {
     /// 
     /// The interpreter puts the value kk into register Vx.
     fn LD_Vx_byte(&mut self, x: u8, byte: u8) {
-        self.debug_println_instruction(format!("LD   V{:01x}, {:#04x}", x, byte), "Set Vx = byte.");
-
         self.registers.set_v(x, byte);
     }
 
@@ -279,8 +679,6 @@ impl Chip {
     /// Adds the value kk to the value of register Vx, then stores the result
     /// in Vx.
     fn ADD_Vx_byte(&mut self, x: u8, byte: u8) {
-        self.debug_println_instruction(format!("ADD  V{:01x}, {:#04x}", x, byte), "Set Vx = Vx + byte.");
-
         self.registers.set_v(x, self.registers.v(x).wrapping_add(byte));
     }
 
@@ -289,8 +687,6 @@ impl Chip {
     /// 
     /// Stores the value of register Vy in register Vx.
     fn LD_Vx_Vy(&mut self, x: u8, y: u8) {
-        self.debug_println_instruction(format!("LD   V{:01x}, V{:01x}", x, y), "Set Vx = Vy.");
-
         self.registers.set_v(x, self.registers.v(y));
     }
 
@@ -302,10 +698,10 @@ impl Chip {
     /// if either bit is 1, then the same bit in the result is also 1.
     /// Otherwise, it is 0.
     fn OR_Vx_Vy(&mut self, x: u8, y: u8) {
-        self.debug_println_instruction(format!("OR   V{:01x}, V{:01x}", x, y), "Set Vx = Vx OR Vy.");
-
         self.registers.set_v(x, self.registers.v(x) | self.registers.v(y));
-        self.registers.set_vf(0);   // According to the chip-8-test-suite: AND, OR, and XOR should set the flag register to 0.
+        if self.quirks.logic_resets_vf {
+            self.registers.set_vf(0);
+        }
     }
 
     /// 8xy2 - AND Vx, Vy
@@ -316,10 +712,10 @@ impl Chip {
     /// values, and if both bits are 1, then the same bit in the result is also
     /// 1. Otherwise, it is 0.
     fn AND_Vx_Vy(&mut self, x: u8, y: u8) {
-        self.debug_println_instruction(format!("AND  V{:01x}, V{:01x}", x, y), "Set Vx = Vx AND Vy.");
-
         self.registers.set_v(x, self.registers.v(x) & self.registers.v(y));
-        self.registers.set_vf(0);   // According to the chip-8-test-suite: AND, OR, and XOR should set the flag register to 0.
+        if self.quirks.logic_resets_vf {
+            self.registers.set_vf(0);
+        }
     }
 
     /// 8xy3 - XOR Vx, Vy
@@ -330,10 +726,10 @@ impl Chip {
     /// two values, and if the bits are not both the same, then the
     /// corresponding bit in the result is set to 1. Otherwise, it is 0.
     fn XOR_Vx_Vy(&mut self, x: u8, y: u8) {
-        self.debug_println_instruction(format!("XOR  V{:01x}, V{:01x}", x, y), "Set Vx = Vx XOR Vy.");
-
         self.registers.set_v(x, self.registers.v(x) ^ self.registers.v(y));
-        self.registers.set_vf(0);   // According to the chip-8-test-suite: AND, OR, and XOR should set the flag register to 0.
+        if self.quirks.logic_resets_vf {
+            self.registers.set_vf(0);
+        }
     }
 
     /// 8xy4 - ADD Vx, Vy
@@ -343,8 +739,6 @@ impl Chip {
     /// than 8 bits (i.e., > 255,) VF is set to 1, otherwise 0. Only the lowest
     /// 8 bits of the result are kept, and stored in Vx.
     fn ADD_Vx_Vy(&mut self, x: u8, y: u8) {
-        self.debug_println_instruction(format!("ADD  V{:01x}, V{:01x}", x, y), "Set Vx = Vx + Vy, set VF = carry.");
-
         let sum = self.registers.v(x) as u16 + self.registers.v(y) as u16;
 
         self.registers.set_v(x, (sum & 0x00FF) as u8);
@@ -358,8 +752,6 @@ impl Chip {
     /// If Vx > Vy, then VF is set to 1, otherwise 0. Then Vy is subtracted from
     /// Vx, and the results stored in Vx.
     fn SUB_Vx_Vy(&mut self, x: u8, y: u8) {
-        self.debug_println_instruction(format!("SUB  V{:01x}, V{:01x}", x, y), "Set Vx = Vx - Vy, set VF = NOT borrow.");
-
         let not_borrow = self.registers.v(x) >= self.registers.v(y);
 
         self.registers.set_v(x, self.registers.v(x).wrapping_sub(self.registers.v(y)));
@@ -370,15 +762,14 @@ impl Chip {
     /// 8xy6 - SHR Vx {, Vy}
     /// Set Vx = Vx SHR 1.
     /// 
-    /// If the least-significant bit of Vx is 1, then VF is set to 1, otherwise
-    /// 0. Then Vx is divided by 2.
+    /// If the least-significant bit of Vy is 1, then VF is set to 1, otherwise
+    /// 0. Then Vy is divided by 2 and the result is stored in Vx. With the
+    /// `shift_vx` quirk, Vx is shifted in place and Vy is ignored.
     fn SHR_Vx_Vy(&mut self, x: u8, y: u8) {
-        self.debug_println_instruction(format!("SHR  V{:01x} {{,V{:01x}}}", x, y), "Set Vx = Vy SHR 1, VF = lost bit.");
-
-        let v_y = self.registers.v(y);
-        self.registers.set_v(x, v_y >> 1);
+        let value = if self.quirks.shift_vx { self.registers.v(x) } else { self.registers.v(y) };
+        self.registers.set_v(x, value >> 1);
 
-        self.registers.set_vf(v_y & 0b00000001);
+        self.registers.set_vf(value & 0b00000001);
     }
 
     /// 8xy7 - SUBN Vx, Vy
@@ -387,8 +778,6 @@ impl Chip {
     /// If Vy > Vx, then VF is set to 1, otherwise 0. Then Vx is subtracted from
     /// Vy, and the results stored in Vx.
     fn SUBN_Vx_Vy(&mut self, x: u8, y: u8) {
-        self.debug_println_instruction(format!("SUBN V{:01x}, V{:01x}", x, y), "Set Vx = Vy - Vx, set VF = NOT borrow.");
-
         let not_borrow = self.registers.v(y) >= self.registers.v(x);
 
         self.registers.set_v(x, self.registers.v(y).wrapping_sub(self.registers.v(x)));
@@ -398,15 +787,14 @@ impl Chip {
     /// 8xyE - SHL Vx {, Vy}
     /// Set Vx = Vx SHL 1.
     /// 
-    /// If the most-significant bit of Vx is 1, then VF is set to 1, otherwise
-    /// to 0. Then Vx is multiplied by 2.
+    /// If the most-significant bit of Vy is 1, then VF is set to 1, otherwise
+    /// to 0. Then Vy is multiplied by 2 and the result is stored in Vx. With
+    /// the `shift_vx` quirk, Vx is shifted in place and Vy is ignored.
     fn SHL_Vx_Vy(&mut self, x: u8, y: u8) {
-        self.debug_println_instruction(format!("SHL  V{:01x} {{, V{:01x}}}", x, y), "Set Vy = Vx SHL 1, VF = lost bit.");
+        let value = if self.quirks.shift_vx { self.registers.v(x) } else { self.registers.v(y) };
+        self.registers.set_v(x, value << 1);
 
-        let v_y = self.registers.v(y);
-        self.registers.set_v(x, v_y << 1);
-
-        self.registers.set_vf((v_y & 0b10000000 == 0b10000000) as u8);
+        self.registers.set_vf((value & 0b10000000 == 0b10000000) as u8);
     }
 
     /// 9xy0 - SNE Vx, Vy
@@ -415,10 +803,8 @@ impl Chip {
     /// The values of Vx and Vy are compared, and if they are not equal, the
     /// program counter is increased by 2.
     fn SNE_Vx_Vy(&mut self, x: u8, y: u8) {
-        self.debug_println_instruction(format!("SNE  V{:01x}, V{:01x}", x, y), "Skip next instruction if Vx != Vy.");
-
         if self.registers.v(x) != self.registers.v(y) {
-            self.registers.increment_pc();
+            self.skip_next_instruction();
         }
     }
 
@@ -427,19 +813,18 @@ impl Chip {
     /// 
     /// The value of register I is set to nnn.
     fn LD_I_addr(&mut self, addr: u16) {
-        self.debug_println_instruction(format!("LD   I, {:#05x}", addr), "Set I = addr.");
-
         self.registers.set_i(addr);
     }
 
     /// Bnnn - JP V0, addr
     /// Jump to location nnn + V0.
     /// 
-    /// The program counter is set to nnn plus the value of V0.
+    /// The program counter is set to nnn plus the value of V0. With the
+    /// `jump_vx` quirk, Vx is used instead, where x is the highest nibble of
+    /// nnn.
     fn JP_V0_addr(&mut self, addr: u16) {
-        self.debug_println_instruction(format!("JP   V0, {:#05x}", addr), "Jump to the location addr + V0.");
-
-        self.registers.set_pc((self.registers.v(0) as u16).wrapping_add(addr));
+        let x = if self.quirks.jump_vx { (addr >> 8) as u8 } else { 0 };
+        self.registers.set_pc((self.registers.v(x) as u16).wrapping_add(addr));
     }
 
     /// Cxkk - RND Vx, byte
@@ -449,9 +834,7 @@ impl Chip {
     /// ANDed with the value kk. The results are stored in Vx. See instruction
     /// 8xy2 for more information on AND.
     fn RND_Vx_byte(&mut self, x: u8, byte: u8) {
-        self.debug_println_instruction(format!("RND  V{:01x}, {:#04x}", x, byte), "Set Vx = random byte AND byte.");
-
-        let rand_u8: u8 = rand::thread_rng().gen();
+        let rand_u8 = self.random.next_byte();
 
         self.registers.set_v(x, rand_u8 & byte);
     }
@@ -469,22 +852,39 @@ impl Chip {
     /// the screen. See instruction 8xy3 for more information on XOR, and
     /// section 2.4, Display, for more information on the Chip-8 screen and
     /// sprites.
+    ///
+    /// SUPER-CHIP added Dxy0, which draws a 16x16 sprite made of 32 bytes,
+    /// two bytes per row. XO-CHIP draws to every selected plane, reading the
+    /// sprite for each plane one after the other.
     fn DRW_Vx_Vy_n(&mut self, x: u8, y: u8, nibble: u8) {
-        if !self.first_instruction {
-            self.debug_println_instruction("WAIT", "Wait for the start of the cycle to draw a sprite.");
-            self.registers.set_pc(self.registers.pc() - 2);
+        if self.quirks.display_wait && self.cycle_position != 0 {
+            self.trace_note = Some(Note::DisplayWait);
+            self.registers.set_pc(self.registers.pc().wrapping_sub(2));
+            // The interpreter idles until the display interrupt and draws
+            // after it, so the draw charged by `step` is given back and the
+            // wait only spends what is left of the frame.
+            if self.timing == Timing::CosmacVip {
+                let opcode = 0xD000 | (x as u16) << 8 | (y as u16) << 4 | nibble as u16;
+                self.vip_cycles_left += vip_cycles(opcode, &self.registers) as i32;
+                self.vip_cycles_left = self.vip_cycles_left.min(0);
+            }
             return;
         }
 
-        self.debug_println_instruction(format!("DRW  V{:01x}, V{:01x}, {:#03x}", x, y, nibble), "Display n-byte sprite starting at memory location I at (Vx, Vy), set VF = collision.");
-
+        let planes = self.display.selected_planes().count_ones() as u16;
+        let len = if nibble == 0 { 32 } else { nibble as u16 };
         let mut sprite: Vec<u8> = Vec::new();
 
-        for i in 0..nibble {
-            sprite.push(self.memory.read(self.registers.i().wrapping_add(i as u16)));
+        for i in 0..len * planes {
+            sprite.push(self.memory.read(self.registers.i().wrapping_add(i)));
         }
 
-        let collision = self.display.draw_sprite(self.registers.v(x), self.registers.v(y), sprite.as_slice());
+        let wrap = self.quirks.wrap_sprites;
+        let collision = if nibble == 0 {
+            self.display.draw_large_sprite(self.registers.v(x), self.registers.v(y), sprite.as_slice(), wrap)
+        } else {
+            self.display.draw_sprite(self.registers.v(x), self.registers.v(y), sprite.as_slice(), wrap)
+        };
 
         self.registers.set_vf(collision as u8);
     }
@@ -495,10 +895,8 @@ impl Chip {
     /// Checks the keyboard, and if the key corresponding to the value of Vx is
     /// currently in the down position, PC is increased by 2.
     fn SKP_Vx(&mut self, x: u8) {
-        self.debug_println_instruction(format!("SKP  V{:01x}, K", x), "Skip next instruction if key with the value of Vx is pressed.");
-
         if self.keyboard.is_pressed(self.registers.v(x)) {
-            self.registers.increment_pc();
+            self.skip_next_instruction();
         }
     }
 
@@ -508,11 +906,44 @@ impl Chip {
     /// Checks the keyboard, and if the key corresponding to the value of Vx is
     /// currently in the up position, PC is increased by 2.
     fn SKNP_Vx(&mut self, x: u8) {
-        self.debug_println_instruction(format!("SKNP V{:01x}, K", x), "Skip next instruction if key with the value of Vx is not pressed.");
-
         if !self.keyboard.is_pressed(self.registers.v(x)) {
-            self.registers.increment_pc();
+            self.skip_next_instruction();
+        }
+    }
+
+    /// F000 nnnn - LD I, long nnnn
+    /// Set I = nnnn.
+    ///
+    /// Introduced by XO-CHIP. The 16-bit address is stored in the 2 bytes
+    /// following the instruction, allowing all 64KB of memory to be addressed.
+    fn LD_I_long(&mut self) {
+        let addr = self.read_word(self.registers.pc());
+
+        self.registers.increment_pc();
+        self.registers.set_i(addr);
+    }
+
+    /// Fn01 - PLANE n
+    /// Select drawing planes n.
+    ///
+    /// Introduced by XO-CHIP. Bit 0 of n selects the first plane, bit 1 the
+    /// second plane. Drawing, clearing and scrolling only affect the selected
+    /// planes.
+    fn PLANE_n(&mut self, n: u8) {
+        self.display.select_planes(n);
+    }
+
+    /// F002 - AUDIO
+    /// Load the audio pattern buffer from memory starting at location I.
+    ///
+    /// Introduced by XO-CHIP. The 16 bytes starting at I are copied into the
+    /// audio pattern buffer, which is played while the sound timer is active.
+    fn AUDIO(&mut self) {
+        let mut audio_pattern = [0; 16];
+        for (i, byte) in audio_pattern.iter_mut().enumerate() {
+            *byte = self.memory.read(self.registers.i().wrapping_add(i as u16));
         }
+        self.audio_pattern = Some(audio_pattern);
     }
 
     /// Fx07 - LD Vx, DT
@@ -520,8 +951,6 @@ impl Chip {
     /// 
     /// The value of DT is placed into Vx.
     fn LD_Vx_DT(&mut self, x: u8) {
-        self.debug_println_instruction(format!("LD   V{:01x}, DT", x), "Set Vx = delay timer value.");
-
         self.registers.set_v(x, self.timers.delay());
     }
 
@@ -531,11 +960,6 @@ impl Chip {
     /// All execution stops until a key is pressed, then the value of that key
     /// is stored in Vx.
     fn LD_Vx_K(&mut self, x: u8) {
-        // Only print this once per instruction.
-        if !self.waiting_for_key {
-            self.debug_println_instruction(format!("LD   V{:01x}, K", x), "Wait for a key press, store the value of the key in Vx.");
-        }
-
         match self.keyboard.just_released() {
             Some(key) => {
                 self.waiting_for_key = false;
@@ -544,7 +968,7 @@ impl Chip {
             None => {
                 self.waiting_for_key = true;
                 // If no key is pressed, jump back to this instruction.
-                self.registers.set_pc(self.registers.pc() - 2);
+                self.registers.set_pc(self.registers.pc().wrapping_sub(2));
             }
         }
     }
@@ -554,8 +978,6 @@ impl Chip {
     /// 
     /// DT is set equal to the value of Vx.
     fn LD_DT_Vx(&mut self, x: u8) {
-        self.debug_println_instruction(format!("LD   DT, V{:01x}", x), "Set delay timer = Vx.");
-
         self.timers.set_delay(self.registers.v(x));
     }
 
@@ -564,8 +986,6 @@ impl Chip {
     /// 
     /// ST is set equal to the value of Vx.
     fn LD_ST_Vx(&mut self, x: u8) {
-        self.debug_println_instruction(format!("LD   ST, V{:01x}", x), "Set sound timer = Vx.");
-
         self.timers.set_sound(self.registers.v(x));
     }
 
@@ -574,7 +994,6 @@ impl Chip {
     /// 
     /// The values of I and Vx are added, and the results are stored in I.
     fn ADD_I_Vx(&mut self, x: u8) {
-        self.debug_println_instruction(format!("ADD  I, V{:01x}", x), "Set I = I + Vx.");
         self.registers.set_i(self.registers.i().wrapping_add(self.registers.v(x) as u16));
     }
 
@@ -585,9 +1004,16 @@ impl Chip {
     /// corresponding to the value of Vx. See section 2.4, Display, for more
     /// information on the Chip-8 hexadecimal font.
     fn LD_F_Vx(&mut self, x: u8) {
-        self.debug_println_instruction(format!("LD   F, V{:01x}", x), "Set I = location of sprite for digit Vx.");
+        self.registers.set_i(sprites::FONT_ADDRESS + self.registers.v(x) as u16 * 5);
+    }
 
-        self.registers.set_i(self.registers.v(x) as u16 * 5);
+    /// Fx30 - LD HF, Vx
+    /// Set I = location of large sprite for digit Vx.
+    ///
+    /// Introduced by SUPER-CHIP. The value of I is set to the location of the
+    /// 8x10 hexadecimal sprite corresponding to the value of Vx.
+    fn LD_HF_Vx(&mut self, x: u8) {
+        self.registers.set_i(sprites::BIG_FONT_ADDRESS + (self.registers.v(x) & 0x0F) as u16 * 10);
     }
 
     /// Fx33 - LD B, Vx
@@ -597,8 +1023,6 @@ impl Chip {
     /// digit in memory at location in I, the tens digit at location I+1, and
     /// the ones digit at location I+2.
     fn LD_B_Vx(&mut self, x: u8) {
-        self.debug_println_instruction(format!("LD   B, V{:01x}", x), "Store BCD representation of Vx in memory locations I, I+1, and I+2.");
-
         let value = self.registers.v(x);
         let (hundreds, value) = (value / 100, value % 100);
         let (tens, ones) = (value / 10, value % 10);
@@ -608,35 +1032,62 @@ impl Chip {
         self.memory.write(self.registers.i().wrapping_add(2), ones);
     }
 
+    /// Fx3A - PITCH Vx
+    /// Set the audio pattern playback pitch = Vx.
+    ///
+    /// Introduced by XO-CHIP. The pattern buffer is played back at
+    /// 4000 * 2^((Vx - 64) / 48) bits per second.
+    fn PITCH_Vx(&mut self, x: u8) {
+        self.pitch = self.registers.v(x);
+    }
+
     /// Fx55 - LD [I], Vx
     /// Store registers V0 through Vx in memory starting at location I.
     ///
     /// The interpreter copies the values of registers V0 through Vx into
-    /// memory, starting at the address in I.
+    /// memory, starting at the address in I. The `load_store_increment_i` quirk
+    /// decides where I is left.
     fn LD_I_Vx(&mut self, x: u8) {
-        self.debug_println_instruction(format!("LD   I, V{:01x}", x), "Store registers V0 through Vx in memory starting at location I.");
-
         for i in 0..=x {
             self.memory.write(self.registers.i().wrapping_add(i as u16), self.registers.v(i));
         }
-        // According to the chip-8-test-suite: The i register should be set to I + x + 1.
-        // TODO: Some modern emulators did not do this, so some games break with this. Make it toggle-able.
-        self.registers.set_i(self.registers.i().wrapping_add(x as u16 + 1));
+        let increment = self.quirks.load_store_increment_i.amount(x);
+        self.registers.set_i(self.registers.i().wrapping_add(increment));
     }
 
     /// Fx65 - LD Vx, [I]
     /// Read registers V0 through Vx from memory starting at location I.
     /// 
     /// The interpreter reads values from memory starting at location I into
-    /// registers V0 through Vx.
+    /// registers V0 through Vx. The `load_store_increment_i` quirk decides where
+    /// I is left.
     fn LD_Vx_I(&mut self, x: u8) {
-        self.debug_println_instruction(format!("LD   V{:01x}, I", x), "Read registers V0 through Vx from memory starting at location I.");
-
         for i in 0..=x {
             self.registers.set_v(i, self.memory.read(self.registers.i().wrapping_add(i as u16)));
         }
-        // According to the chip-8-test-suite: The i register should be set to I + x + 1.
-        // TODO: Some modern emulators did not do this, so some games break with this. Make it toggle-able.
-        self.registers.set_i(self.registers.i().wrapping_add(x as u16 + 1));
+        let increment = self.quirks.load_store_increment_i.amount(x);
+        self.registers.set_i(self.registers.i().wrapping_add(increment));
+    }
+
+    /// Fx75 - LD R, Vx
+    /// Store registers V0 through Vx in the RPL user flags.
+    ///
+    /// Introduced by SUPER-CHIP, where the HP-48 calculators kept these flags
+    /// between programs. SUPER-CHIP only has 8 flags, XO-CHIP extends this to
+    /// all 16 registers.
+    fn LD_R_Vx(&mut self, x: u8) {
+        for i in 0..=x {
+            self.rpl_flags[i as usize] = self.registers.v(i);
+        }
+    }
+
+    /// Fx85 - LD Vx, R
+    /// Read registers V0 through Vx from the RPL user flags.
+    ///
+    /// Introduced by SUPER-CHIP. See instruction Fx75.
+    fn LD_Vx_R(&mut self, x: u8) {
+        for i in 0..=x {
+            self.registers.set_v(i, self.rpl_flags[i as usize]);
+        }
     }
 }
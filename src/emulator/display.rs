@@ -1,23 +1,62 @@
-const WIDTH: usize = 64;
-const HEIGHT: usize = 32;
+use crate::emulator::{StateError, StateReader, StateWriter};
+
+const LORES_WIDTH: usize = 64;
+const LORES_HEIGHT: usize = 32;
+const HIRES_WIDTH: usize = 128;
+const HIRES_HEIGHT: usize = 64;
+
+/// The largest resolution the display can switch to. Buffers that need to hold
+/// the display in any mode should be sized with these.
+pub const MAX_WIDTH: usize = HIRES_WIDTH;
+pub const MAX_HEIGHT: usize = HIRES_HEIGHT;
+
+/// The number of drawing planes. Each pixel holds one bit per plane.
+pub const PLANES: usize = 2;
+
+/// Parses a color written as six hexadecimal digits, optionally preceded by
+/// `#`, into components from 0 to 1.
+pub fn parse_color(color: &str) -> Option<(f32, f32, f32)> {
+    let digits = color.strip_prefix('#').unwrap_or(color);
+    if digits.len() != 6 {
+        return None;
+    }
+
+    let value = u32::from_str_radix(digits, 16).ok()?;
+    let component = |shift: u32| (value >> shift & 0xFF) as f32 / 255.0;
+    Some((component(16), component(8), component(0)))
+}
 
 /// The original implementation of the Chip-8 language used a 64x32-pixel
 /// monochrome display with this format:
 ///
 /// -------------------
-/// |(0,0)	    (63,0)|
+/// |(0,0)      (63,0)|
 /// |                 |
-/// |(0,31)	   (63,31)|
+/// |(0,31)    (63,31)|
 /// -------------------
 ///
 /// Chip-8 draws graphics on screen through the use of sprites. A sprite is a
 /// group of bytes which are a binary representation of the desired picture.
 /// Chip-8 sprites may be up to 15 bytes, for a possible sprite size of 8x15.
+///
+/// SUPER-CHIP added a 128x64-pixel high resolution mode that programs can
+/// switch to at runtime, along with 16x16 sprites and scrolling.
+///
+/// XO-CHIP added a second drawing plane. Drawing, clearing and scrolling only
+/// affect the currently selected planes, and each combination of planes is
+/// shown in its own color, for 4 colors in total.
 pub struct Display {
-    pixels: [u32; HEIGHT * WIDTH / 32],
+    /// One byte per pixel, stored row by row. Bit 0 is the pixel's value in
+    /// the first plane, bit 1 in the second plane.
+    pixels: Vec<u8>,
+    width: usize,
+    height: usize,
+    /// A mask of the planes affected by drawing, clearing and scrolling.
+    selected_planes: u8,
     scale: u32,
-    background_color: (f32, f32, f32),
-    foreground_color: (f32, f32, f32),
+    /// The colors of the background, the first plane, the second plane, and
+    /// of both planes overlapping, in that order.
+    palette: [(f32, f32, f32); 4],
 }
 
 impl Display {
@@ -27,80 +66,266 @@ impl Display {
         foreground_color: (f32, f32, f32),
     ) -> Self {
         Self {
-            pixels: [0; HEIGHT * WIDTH / 32],
+            pixels: vec![0; LORES_WIDTH * LORES_HEIGHT],
+            width: LORES_WIDTH,
+            height: LORES_HEIGHT,
+            selected_planes: 0b01,
             scale,
-            background_color,
-            foreground_color,
+            palette: [
+                background_color,
+                foreground_color,
+                (0.6, 0.6, 0.6),
+                (0.3, 0.3, 0.3),
+            ],
         }
     }
 
+    /// Clears the selected planes.
     pub fn clear(&mut self) {
-        self.pixels = [0; HEIGHT * WIDTH / 32];
+        let mask = !self.selected_planes;
+        self.pixels.iter_mut().for_each(|pixel| *pixel &= mask);
+    }
+
+    pub fn hires(&self) -> bool {
+        self.width == HIRES_WIDTH
+    }
+
+    /// Switches between the 64x32 and 128x64 resolutions, clearing all planes.
+    pub fn set_hires(&mut self, hires: bool) {
+        let (width, height) = if hires {
+            (HIRES_WIDTH, HIRES_HEIGHT)
+        } else {
+            (LORES_WIDTH, LORES_HEIGHT)
+        };
+
+        self.width = width;
+        self.height = height;
+        self.pixels = vec![0; width * height];
+    }
+
+    pub fn selected_planes(&self) -> u8 {
+        self.selected_planes
+    }
+
+    /// Selects the planes affected by drawing, clearing and scrolling. Bit 0
+    /// selects the first plane, bit 1 the second plane.
+    pub fn select_planes(&mut self, planes: u8) {
+        self.selected_planes = planes & 0b11;
     }
 
     /// Draws a list of bytes onto the screen. Each byte being one row.
+    /// If more than one plane is selected, the sprite holds the rows for each
+    /// of them one after the other, starting with the first plane.
+    /// Returns true if drawing collides with already drawn pixel.
+    pub fn draw_sprite(&mut self, x: u8, y: u8, sprite: &[u8], wrap: bool) -> bool {
+        self.draw_planes(x, y, sprite, 1, wrap)
+    }
+
+    /// Draws 16x16 sprites onto the screen. Each pair of bytes being one row.
     /// Returns true if drawing collides with already drawn pixel.
-    pub fn draw_sprite(&mut self, mut x: u8, mut y: u8, sprite: &[u8]) -> bool {
+    pub fn draw_large_sprite(&mut self, x: u8, y: u8, sprite: &[u8], wrap: bool) -> bool {
+        self.draw_planes(x, y, sprite, 2, wrap)
+    }
+
+    fn draw_planes(&mut self, x: u8, y: u8, sprite: &[u8], bytes_per_row: usize, wrap: bool) -> bool {
+        let planes = self.selected_planes.count_ones() as usize;
+        if planes == 0 {
+            return false;
+        }
+
         let mut collided = false;
+        let mut chunks = sprite.chunks(sprite.len() / planes);
+        for plane in (0..PLANES as u8).map(|i| 1 << i) {
+            if self.selected_planes & plane == 0 {
+                continue;
+            }
 
-        let origin_x = x;
-        let origin_y = y;
-        for byte in sprite {
-            for i in (0..8).rev() {
-                let bit = (byte >> i) & 0b00000001;
-                if bit == 1 {
-                    if self.draw_pixel(x, y) {
-                        collided = true;
-                    }
-                }
-                x += 1;
+            let data = chunks.next().unwrap_or(&[]);
+            let rows = data.chunks(bytes_per_row).map(|row| {
+                (row[0] as u16) << 8 | *row.get(1).unwrap_or(&0) as u16
+            });
 
-                // If the sprite starts at the right and gets cut off, break.
-                if origin_x < WIDTH as u8 && x > WIDTH as u8 {
-                    break;
-                }
+            if self.draw_rows(x, y, rows, bytes_per_row * 8, plane, wrap) {
+                collided = true;
             }
-            y += 1;
-            x = origin_x;
+        }
+        collided
+    }
+
+    /// Draws rows of up to 16 pixels onto a plane, most significant bit first.
+    /// The origin wraps around the screen, but anything drawn past the right
+    /// or bottom edge is cut off, unless `wrap` is set.
+    fn draw_rows(
+        &mut self,
+        x: u8,
+        y: u8,
+        rows: impl Iterator<Item = u16>,
+        width: usize,
+        plane: u8,
+        wrap: bool,
+    ) -> bool {
+        let mut collided = false;
 
-            // If the sprite starts at the bottom and gets cut off, break.
-            if origin_y < HEIGHT as u8 && y > HEIGHT as u8 {
+        let origin_x = x as usize % self.width;
+        let origin_y = y as usize % self.height;
+        for (row_index, row) in rows.enumerate() {
+            let y = origin_y + row_index;
+            if y >= self.height && !wrap {
                 break;
             }
+
+            for column in 0..width {
+                let x = origin_x + column;
+                if x >= self.width && !wrap {
+                    break;
+                }
+
+                if (row << column) & 0x8000 != 0 && self.draw_pixel(x, y, plane) {
+                    collided = true;
+                }
+            }
         }
         collided
     }
 
-    /// Draws a pixel onto the screen.
+    /// Draws a pixel onto a plane of the screen.
     /// Returns true if drawing collides with already drawn pixel.
-    pub fn draw_pixel(&mut self, mut x: u8, mut y: u8) -> bool {
+    pub fn draw_pixel(&mut self, x: usize, y: usize, plane: u8) -> bool {
         // Loop x and y if they go out of the display's bounds.
-        while x >= WIDTH as u8 {
-            x -= WIDTH as u8;
+        let index = (y % self.height) * self.width + x % self.width;
+
+        self.pixels[index] ^= plane;
+
+        // If the pixel is off, then it collided and this returns true.
+        self.pixels[index] & plane == 0
+    }
+
+    /// Scrolls the selected planes down by n pixels, filling the top with
+    /// blank rows.
+    pub fn scroll_down(&mut self, n: usize) {
+        let n = n.min(self.height);
+        for y in (0..self.height).rev() {
+            for x in 0..self.width {
+                let value = if y >= n { self.pixels[(y - n) * self.width + x] } else { 0 };
+                self.set_selected(x, y, value);
+            }
         }
-        while y >= HEIGHT as u8 {
-            y -= HEIGHT as u8;
+    }
+
+    /// Scrolls the selected planes up by n pixels, filling the bottom with
+    /// blank rows.
+    pub fn scroll_up(&mut self, n: usize) {
+        let n = n.min(self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let value = if y + n < self.height { self.pixels[(y + n) * self.width + x] } else { 0 };
+                self.set_selected(x, y, value);
+            }
         }
+    }
 
-        self.pixels[x as usize] ^= 0b10000000000000000000000000000000 >> y;
+    /// Scrolls the selected planes right by n pixels, filling the left with
+    /// blank columns.
+    pub fn scroll_right(&mut self, n: usize) {
+        let n = n.min(self.width);
+        for y in 0..self.height {
+            for x in (0..self.width).rev() {
+                let value = if x >= n { self.pixels[y * self.width + x - n] } else { 0 };
+                self.set_selected(x, y, value);
+            }
+        }
+    }
 
-        // If the pixel is off, then it collided and this returns true.
-        (self.pixels[x as usize] << y) >> HEIGHT - 1 == 0
+    /// Scrolls the selected planes left by n pixels, filling the right with
+    /// blank columns.
+    pub fn scroll_left(&mut self, n: usize) {
+        let n = n.min(self.width);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let value = if x + n < self.width { self.pixels[y * self.width + x + n] } else { 0 };
+                self.set_selected(x, y, value);
+            }
+        }
+    }
+
+    /// Replaces the bits of the selected planes at (x, y) with those of value.
+    fn set_selected(&mut self, x: usize, y: usize, value: u8) {
+        let index = y * self.width + x;
+        self.pixels[index] = (self.pixels[index] & !self.selected_planes) | (value & self.selected_planes);
+    }
+
+    /// The pixels of the display, stored row by row. The slice is
+    /// `width() * height()` bytes long, and each byte holds one bit per plane.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
     }
 
-    pub fn pixels(&self) -> [u32; HEIGHT * WIDTH / 32] {
-        self.pixels
+    pub fn height(&self) -> usize {
+        self.height
     }
 
     pub fn scale(&self) -> u32 {
         self.scale
     }
 
+    pub fn set_scale(&mut self, scale: u32) {
+        self.scale = scale;
+    }
+
     pub fn background_color(&self) -> (f32, f32, f32) {
-        self.background_color
+        self.palette[0]
     }
 
     pub fn foreground_color(&self) -> (f32, f32, f32) {
-        self.foreground_color
+        self.palette[1]
+    }
+
+    /// The color of each combination of planes, indexed by a pixel's value.
+    pub fn palette(&self) -> [(f32, f32, f32); 4] {
+        self.palette
+    }
+
+    pub fn set_palette(&mut self, palette: [(f32, f32, f32); 4]) {
+        self.palette = palette;
+    }
+
+    /// Writes what programs can change. The scale and palette belong to the
+    /// frontend, and are left out.
+    pub(crate) fn write_state(&self, writer: &mut StateWriter) {
+        writer.bool(self.hires());
+        writer.u8(self.selected_planes);
+        writer.bytes(&self.pixels);
+    }
+
+    /// Reads a display written by `write_state`, keeping this display's scale
+    /// and palette.
+    pub(crate) fn read_state(&self, reader: &mut StateReader) -> Result<Self, StateError> {
+        let (width, height) = if reader.bool()? {
+            (HIRES_WIDTH, HIRES_HEIGHT)
+        } else {
+            (LORES_WIDTH, LORES_HEIGHT)
+        };
+        let selected_planes = reader.u8()?;
+        let pixels = reader.bytes()?;
+
+        if pixels.len() != width * height {
+            return Err(StateError::Invalid("the display doesn't match its resolution"));
+        }
+        if selected_planes >> PLANES != 0 || pixels.iter().any(|pixel| pixel >> PLANES != 0) {
+            return Err(StateError::Invalid("the display uses planes that don't exist"));
+        }
+
+        Ok(Self {
+            pixels: pixels.to_vec(),
+            width,
+            height,
+            selected_planes,
+            scale: self.scale,
+            palette: self.palette,
+        })
     }
 }
@@ -0,0 +1,94 @@
+use crate::emulator::Display;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// A picture of the display, in 8-bit RGB, rendered on the CPU so it can be
+/// taken without a window.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    width: u32,
+    height: u32,
+    /// Three bytes per pixel, stored row by row.
+    rgb: Vec<u8>,
+}
+
+impl Display {
+    /// Renders the display with each of its pixels as a square of `scale`
+    /// image pixels, which is at least 1, colored by `palette` as the window
+    /// colors it.
+    pub fn to_image(&self, scale: u32, palette: [(f32, f32, f32); 4]) -> Image {
+        let scale = scale.max(1) as usize;
+        let colors = palette.map(|(r, g, b)| [to_byte(r), to_byte(g), to_byte(b)]);
+
+        let width = self.width() * scale;
+        let mut rgb = Vec::with_capacity(width * self.height() * scale * 3);
+        for row in self.pixels().chunks(self.width()) {
+            let start = rgb.len();
+            for &pixel in row {
+                for _ in 0..scale {
+                    rgb.extend_from_slice(&colors[pixel as usize & 0b11]);
+                }
+            }
+            for _ in 1..scale {
+                rgb.extend_from_within(start..start + width * 3);
+            }
+        }
+
+        Image { width: width as u32, height: (self.height() * scale) as u32, rgb }
+    }
+
+    /// Renders the display stretched over an image of `width` by `height`
+    /// pixels, each at least 1, taking the display pixel nearest to each image
+    /// pixel as the window does. High resolution displays are rendered this
+    /// way at the window's size, as their pixels are half the window's scale.
+    pub fn to_image_sized(&self, width: u32, height: u32, palette: [(f32, f32, f32); 4]) -> Image {
+        let (width, height) = (width.max(1) as usize, height.max(1) as usize);
+        let colors = palette.map(|(r, g, b)| [to_byte(r), to_byte(g), to_byte(b)]);
+
+        let mut rgb = Vec::with_capacity(width * height * 3);
+        for y in 0..height {
+            let row = &self.pixels()[y * self.height() / height * self.width()..][..self.width()];
+            for x in 0..width {
+                rgb.extend_from_slice(&colors[row[x * self.width() / width] as usize & 0b11]);
+            }
+        }
+
+        Image { width: width as u32, height: height as u32, rgb }
+    }
+}
+
+impl Image {
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The pixels, three bytes of red, green and blue each, stored row by row.
+    pub fn rgb(&self) -> &[u8] {
+        &self.rgb
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 3] {
+        let index = (y as usize * self.width as usize + x as usize) * 3;
+        [self.rgb[index], self.rgb[index + 1], self.rgb[index + 2]]
+    }
+
+    pub fn write_png(&self, writer: impl Write) -> Result<(), png::EncodingError> {
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&self.rgb)
+    }
+
+    pub fn save_png(&self, path: impl AsRef<Path>) -> Result<(), png::EncodingError> {
+        self.write_png(BufWriter::new(File::create(path)?))
+    }
+}
+
+fn to_byte(component: f32) -> u8 {
+    (component.clamp(0.0, 1.0) * 255.0).round() as u8
+}
@@ -0,0 +1,334 @@
+#![allow(non_camel_case_types)]
+use std::fmt;
+use thiserror::Error;
+
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+#[error("{0:#06x} is not a valid instruction")]
+pub struct DecodeError(pub u16);
+
+/// A decoded Chip-8 instruction, including the SUPER-CHIP and XO-CHIP
+/// extensions. Variants are named after the instruction's mnemonic and
+/// operands, and hold the operands in the order they appear in that name.
+///
+/// Register operands are given by their index, x and y in the opcode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction {
+    /// 00Cn
+    SCD_nibble(u8),
+    /// 00Dn
+    SCU_nibble(u8),
+    /// 00E0
+    CLS,
+    /// 00EE
+    RET,
+    /// 00FB
+    SCR,
+    /// 00FC
+    SCL,
+    /// 00FD
+    EXIT,
+    /// 00FE
+    LOW,
+    /// 00FF
+    HIGH,
+    /// 1nnn
+    JP_addr(u16),
+    /// 2nnn
+    CALL_addr(u16),
+    /// 3xkk
+    SE_Vx_byte(u8, u8),
+    /// 4xkk
+    SNE_Vx_byte(u8, u8),
+    /// 5xy0
+    SE_Vx_Vy(u8, u8),
+    /// 5xy2
+    LD_I_Vx_Vy(u8, u8),
+    /// 5xy3
+    LD_Vx_Vy_I(u8, u8),
+    /// 6xkk
+    LD_Vx_byte(u8, u8),
+    /// 7xkk
+    ADD_Vx_byte(u8, u8),
+    /// 8xy0
+    LD_Vx_Vy(u8, u8),
+    /// 8xy1
+    OR_Vx_Vy(u8, u8),
+    /// 8xy2
+    AND_Vx_Vy(u8, u8),
+    /// 8xy3
+    XOR_Vx_Vy(u8, u8),
+    /// 8xy4
+    ADD_Vx_Vy(u8, u8),
+    /// 8xy5
+    SUB_Vx_Vy(u8, u8),
+    /// 8xy6
+    SHR_Vx_Vy(u8, u8),
+    /// 8xy7
+    SUBN_Vx_Vy(u8, u8),
+    /// 8xyE
+    SHL_Vx_Vy(u8, u8),
+    /// 9xy0
+    SNE_Vx_Vy(u8, u8),
+    /// Annn
+    LD_I_addr(u16),
+    /// Bnnn
+    JP_V0_addr(u16),
+    /// Cxkk
+    RND_Vx_byte(u8, u8),
+    /// Dxyn
+    DRW_Vx_Vy_n(u8, u8, u8),
+    /// Ex9E
+    SKP_Vx(u8),
+    /// ExA1
+    SKNP_Vx(u8),
+    /// F000 nnnn. The address is stored in the 2 bytes following the opcode,
+    /// so it is not part of the decoded instruction.
+    LD_I_long,
+    /// Fn01
+    PLANE_n(u8),
+    /// F002
+    AUDIO,
+    /// Fx07
+    LD_Vx_DT(u8),
+    /// Fx0A
+    LD_Vx_K(u8),
+    /// Fx15
+    LD_DT_Vx(u8),
+    /// Fx18
+    LD_ST_Vx(u8),
+    /// Fx1E
+    ADD_I_Vx(u8),
+    /// Fx29
+    LD_F_Vx(u8),
+    /// Fx30
+    LD_HF_Vx(u8),
+    /// Fx33
+    LD_B_Vx(u8),
+    /// Fx3A
+    PITCH_Vx(u8),
+    /// Fx55
+    LD_I_Vx(u8),
+    /// Fx65
+    LD_Vx_I(u8),
+    /// Fx75
+    LD_R_Vx(u8),
+    /// Fx85
+    LD_Vx_R(u8),
+}
+
+impl Instruction {
+    pub fn decode(opcode: u16) -> Result<Instruction, DecodeError> {
+        use Instruction::*;
+
+        let addr = opcode & 0x0FFF; // 0x0nnn
+        let x = ((opcode & 0x0F00) >> 8) as u8; // 0x0x00
+        let y = ((opcode & 0x00F0) >> 4) as u8; // 0x00x0
+        let byte = (opcode & 0x00FF) as u8; // 0x00xx
+        let nibble = (opcode & 0x000F) as u8; // 0x000x
+
+        let instruction = match opcode & 0xF000 {
+            0x0000 => match opcode {
+                0x00E0 => CLS,
+                0x00EE => RET,
+                0x00FB => SCR,
+                0x00FC => SCL,
+                0x00FD => EXIT,
+                0x00FE => LOW,
+                0x00FF => HIGH,
+                _ if opcode & 0xFFF0 == 0x00C0 => SCD_nibble(nibble),
+                _ if opcode & 0xFFF0 == 0x00D0 => SCU_nibble(nibble),
+                _ => return Err(DecodeError(opcode)),
+            },
+            0x1000 => JP_addr(addr),
+            0x2000 => CALL_addr(addr),
+            0x3000 => SE_Vx_byte(x, byte),
+            0x4000 => SNE_Vx_byte(x, byte),
+            0x5000 => match nibble {
+                0x0 => SE_Vx_Vy(x, y),
+                0x2 => LD_I_Vx_Vy(x, y),
+                0x3 => LD_Vx_Vy_I(x, y),
+                _ => return Err(DecodeError(opcode)),
+            },
+            0x6000 => LD_Vx_byte(x, byte),
+            0x7000 => ADD_Vx_byte(x, byte),
+            0x8000 => match nibble {
+                0x0 => LD_Vx_Vy(x, y),
+                0x1 => OR_Vx_Vy(x, y),
+                0x2 => AND_Vx_Vy(x, y),
+                0x3 => XOR_Vx_Vy(x, y),
+                0x4 => ADD_Vx_Vy(x, y),
+                0x5 => SUB_Vx_Vy(x, y),
+                0x6 => SHR_Vx_Vy(x, y),
+                0x7 => SUBN_Vx_Vy(x, y),
+                0xE => SHL_Vx_Vy(x, y),
+                _ => return Err(DecodeError(opcode)),
+            },
+            0x9000 => match nibble {
+                0x0 => SNE_Vx_Vy(x, y),
+                _ => return Err(DecodeError(opcode)),
+            },
+            0xA000 => LD_I_addr(addr),
+            0xB000 => JP_V0_addr(addr),
+            0xC000 => RND_Vx_byte(x, byte),
+            0xD000 => DRW_Vx_Vy_n(x, y, nibble),
+            0xE000 => match byte {
+                0x9E => SKP_Vx(x),
+                0xA1 => SKNP_Vx(x),
+                _ => return Err(DecodeError(opcode)),
+            },
+            _ => match byte {
+                0x00 if x == 0 => LD_I_long,
+                0x01 => PLANE_n(x),
+                0x02 if x == 0 => AUDIO,
+                0x07 => LD_Vx_DT(x),
+                0x0A => LD_Vx_K(x),
+                0x15 => LD_DT_Vx(x),
+                0x18 => LD_ST_Vx(x),
+                0x1E => ADD_I_Vx(x),
+                0x29 => LD_F_Vx(x),
+                0x30 => LD_HF_Vx(x),
+                0x33 => LD_B_Vx(x),
+                0x3A => PITCH_Vx(x),
+                0x55 => LD_I_Vx(x),
+                0x65 => LD_Vx_I(x),
+                0x75 => LD_R_Vx(x),
+                0x85 => LD_Vx_R(x),
+                _ => return Err(DecodeError(opcode)),
+            },
+        };
+
+        Ok(instruction)
+    }
+
+    pub fn encode(&self) -> u16 {
+        use Instruction::*;
+
+        let xy = |prefix: u16, x: u8, y: u8, suffix: u16| {
+            prefix | (x as u16 & 0xF) << 8 | (y as u16 & 0xF) << 4 | suffix
+        };
+        let xkk = |prefix: u16, x: u8, byte: u8| prefix | (x as u16 & 0xF) << 8 | byte as u16;
+        let nnn = |prefix: u16, addr: u16| prefix | (addr & 0x0FFF);
+
+        match *self {
+            SCD_nibble(n) => 0x00C0 | (n as u16 & 0xF),
+            SCU_nibble(n) => 0x00D0 | (n as u16 & 0xF),
+            CLS => 0x00E0,
+            RET => 0x00EE,
+            SCR => 0x00FB,
+            SCL => 0x00FC,
+            EXIT => 0x00FD,
+            LOW => 0x00FE,
+            HIGH => 0x00FF,
+            JP_addr(addr) => nnn(0x1000, addr),
+            CALL_addr(addr) => nnn(0x2000, addr),
+            SE_Vx_byte(x, byte) => xkk(0x3000, x, byte),
+            SNE_Vx_byte(x, byte) => xkk(0x4000, x, byte),
+            SE_Vx_Vy(x, y) => xy(0x5000, x, y, 0x0),
+            LD_I_Vx_Vy(x, y) => xy(0x5000, x, y, 0x2),
+            LD_Vx_Vy_I(x, y) => xy(0x5000, x, y, 0x3),
+            LD_Vx_byte(x, byte) => xkk(0x6000, x, byte),
+            ADD_Vx_byte(x, byte) => xkk(0x7000, x, byte),
+            LD_Vx_Vy(x, y) => xy(0x8000, x, y, 0x0),
+            OR_Vx_Vy(x, y) => xy(0x8000, x, y, 0x1),
+            AND_Vx_Vy(x, y) => xy(0x8000, x, y, 0x2),
+            XOR_Vx_Vy(x, y) => xy(0x8000, x, y, 0x3),
+            ADD_Vx_Vy(x, y) => xy(0x8000, x, y, 0x4),
+            SUB_Vx_Vy(x, y) => xy(0x8000, x, y, 0x5),
+            SHR_Vx_Vy(x, y) => xy(0x8000, x, y, 0x6),
+            SUBN_Vx_Vy(x, y) => xy(0x8000, x, y, 0x7),
+            SHL_Vx_Vy(x, y) => xy(0x8000, x, y, 0xE),
+            SNE_Vx_Vy(x, y) => xy(0x9000, x, y, 0x0),
+            LD_I_addr(addr) => nnn(0xA000, addr),
+            JP_V0_addr(addr) => nnn(0xB000, addr),
+            RND_Vx_byte(x, byte) => xkk(0xC000, x, byte),
+            DRW_Vx_Vy_n(x, y, n) => xy(0xD000, x, y, n as u16 & 0xF),
+            SKP_Vx(x) => xkk(0xE000, x, 0x9E),
+            SKNP_Vx(x) => xkk(0xE000, x, 0xA1),
+            LD_I_long => 0xF000,
+            PLANE_n(n) => xkk(0xF000, n, 0x01),
+            AUDIO => 0xF002,
+            LD_Vx_DT(x) => xkk(0xF000, x, 0x07),
+            LD_Vx_K(x) => xkk(0xF000, x, 0x0A),
+            LD_DT_Vx(x) => xkk(0xF000, x, 0x15),
+            LD_ST_Vx(x) => xkk(0xF000, x, 0x18),
+            ADD_I_Vx(x) => xkk(0xF000, x, 0x1E),
+            LD_F_Vx(x) => xkk(0xF000, x, 0x29),
+            LD_HF_Vx(x) => xkk(0xF000, x, 0x30),
+            LD_B_Vx(x) => xkk(0xF000, x, 0x33),
+            PITCH_Vx(x) => xkk(0xF000, x, 0x3A),
+            LD_I_Vx(x) => xkk(0xF000, x, 0x55),
+            LD_Vx_I(x) => xkk(0xF000, x, 0x65),
+            LD_R_Vx(x) => xkk(0xF000, x, 0x75),
+            LD_Vx_R(x) => xkk(0xF000, x, 0x85),
+        }
+    }
+
+    /// The size of the instruction in memory, in bytes.
+    pub fn size(&self) -> u16 {
+        match self {
+            Instruction::LD_I_long => 4,
+            _ => 2,
+        }
+    }
+}
+
+/// Prints the instruction's mnemonic as written in Cowgod's Chip-8 Technical
+/// Reference, with the mnemonic padded so that operands line up.
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Instruction::*;
+
+        match *self {
+            SCD_nibble(n) => write!(f, "SCD  {:#03x}", n),
+            SCU_nibble(n) => write!(f, "SCU  {:#03x}", n),
+            CLS => write!(f, "CLS"),
+            RET => write!(f, "RET"),
+            SCR => write!(f, "SCR"),
+            SCL => write!(f, "SCL"),
+            EXIT => write!(f, "EXIT"),
+            LOW => write!(f, "LOW"),
+            HIGH => write!(f, "HIGH"),
+            JP_addr(addr) => write!(f, "JP   {:#05x}", addr),
+            CALL_addr(addr) => write!(f, "CALL {:#05x}", addr),
+            SE_Vx_byte(x, byte) => write!(f, "SE   V{:X}, {:#04x}", x, byte),
+            SNE_Vx_byte(x, byte) => write!(f, "SNE  V{:X}, {:#04x}", x, byte),
+            SE_Vx_Vy(x, y) => write!(f, "SE   V{:X}, V{:X}", x, y),
+            LD_I_Vx_Vy(x, y) => write!(f, "LD   [I], V{:X}-V{:X}", x, y),
+            LD_Vx_Vy_I(x, y) => write!(f, "LD   V{:X}-V{:X}, [I]", x, y),
+            LD_Vx_byte(x, byte) => write!(f, "LD   V{:X}, {:#04x}", x, byte),
+            ADD_Vx_byte(x, byte) => write!(f, "ADD  V{:X}, {:#04x}", x, byte),
+            LD_Vx_Vy(x, y) => write!(f, "LD   V{:X}, V{:X}", x, y),
+            OR_Vx_Vy(x, y) => write!(f, "OR   V{:X}, V{:X}", x, y),
+            AND_Vx_Vy(x, y) => write!(f, "AND  V{:X}, V{:X}", x, y),
+            XOR_Vx_Vy(x, y) => write!(f, "XOR  V{:X}, V{:X}", x, y),
+            ADD_Vx_Vy(x, y) => write!(f, "ADD  V{:X}, V{:X}", x, y),
+            SUB_Vx_Vy(x, y) => write!(f, "SUB  V{:X}, V{:X}", x, y),
+            SHR_Vx_Vy(x, y) => write!(f, "SHR  V{:X}, V{:X}", x, y),
+            SUBN_Vx_Vy(x, y) => write!(f, "SUBN V{:X}, V{:X}", x, y),
+            SHL_Vx_Vy(x, y) => write!(f, "SHL  V{:X}, V{:X}", x, y),
+            SNE_Vx_Vy(x, y) => write!(f, "SNE  V{:X}, V{:X}", x, y),
+            LD_I_addr(addr) => write!(f, "LD   I, {:#05x}", addr),
+            JP_V0_addr(addr) => write!(f, "JP   V0, {:#05x}", addr),
+            RND_Vx_byte(x, byte) => write!(f, "RND  V{:X}, {:#04x}", x, byte),
+            DRW_Vx_Vy_n(x, y, n) => write!(f, "DRW  V{:X}, V{:X}, {:#03x}", x, y, n),
+            SKP_Vx(x) => write!(f, "SKP  V{:X}", x),
+            SKNP_Vx(x) => write!(f, "SKNP V{:X}", x),
+            LD_I_long => write!(f, "LD   I, long"),
+            PLANE_n(n) => write!(f, "PLANE {:#03x}", n),
+            AUDIO => write!(f, "AUDIO"),
+            LD_Vx_DT(x) => write!(f, "LD   V{:X}, DT", x),
+            LD_Vx_K(x) => write!(f, "LD   V{:X}, K", x),
+            LD_DT_Vx(x) => write!(f, "LD   DT, V{:X}", x),
+            LD_ST_Vx(x) => write!(f, "LD   ST, V{:X}", x),
+            ADD_I_Vx(x) => write!(f, "ADD  I, V{:X}", x),
+            LD_F_Vx(x) => write!(f, "LD   F, V{:X}", x),
+            LD_HF_Vx(x) => write!(f, "LD   HF, V{:X}", x),
+            LD_B_Vx(x) => write!(f, "LD   B, V{:X}", x),
+            PITCH_Vx(x) => write!(f, "PITCH V{:X}", x),
+            LD_I_Vx(x) => write!(f, "LD   [I], V{:X}", x),
+            LD_Vx_I(x) => write!(f, "LD   V{:X}, [I]", x),
+            LD_R_Vx(x) => write!(f, "LD   R, V{:X}", x),
+            LD_Vx_R(x) => write!(f, "LD   V{:X}, R", x),
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use glutin::event::{ElementState, KeyboardInput, VirtualKeyCode};
+use crate::emulator::{StateError, StateReader, StateWriter};
 
 /// The computers which originally used the Chip-8 Language had a 16-key
 /// hexadecimal keypad with the following layout:
@@ -9,7 +9,8 @@ use glutin::event::{ElementState, KeyboardInput, VirtualKeyCode};
 /// |A|0|B|F|
 ///
 /// This layout must be mapped into various other configurations to fit the
-/// keyboards of today's platforms.
+/// keyboards of today's platforms. That mapping is left to the frontend, which
+/// reports keys by their value on the keypad.
 pub struct Keyboard {
     keys: [bool; 16],
     just_released: Option<u8>,
@@ -20,35 +21,23 @@ impl Keyboard {
         Self { keys: [false; 16], just_released: None, }
     }
 
-    pub fn process_input(&mut self, input: KeyboardInput) {
-        if let Some(keycode) = input.virtual_keycode {
-            let key: Option<u8> = match keycode {
-                VirtualKeyCode::X => Some(0x0),
-                VirtualKeyCode::Key1 => Some(0x1),
-                VirtualKeyCode::Key2 => Some(0x2),
-                VirtualKeyCode::Key3 => Some(0x3),
-                VirtualKeyCode::Q => Some(0x4),
-                VirtualKeyCode::W => Some(0x5),
-                VirtualKeyCode::E => Some(0x6),
-                VirtualKeyCode::A => Some(0x7),
-                VirtualKeyCode::S => Some(0x8),
-                VirtualKeyCode::D => Some(0x9),
-                VirtualKeyCode::Z => Some(0xA),
-                VirtualKeyCode::C => Some(0xB),
-                VirtualKeyCode::Key4 => Some(0xC),
-                VirtualKeyCode::R => Some(0xD),
-                VirtualKeyCode::F => Some(0xE),
-                VirtualKeyCode::V => Some(0xF),
-                _ => None
-            };
-            
-            if let Some(key) = key {
-                self.keys[key as usize] = input.state == ElementState::Pressed;
-                if input.state == ElementState::Released {
-                    self.just_released = Some(key);
-                }
-            }
+    /// Marks a key as held down. Keys above 0xF are ignored.
+    pub fn press(&mut self, key: u8) {
+        if key > 0xF {
+            return;
+        }
+
+        self.keys[key as usize] = true;
+    }
+
+    /// Marks a key as released. Keys above 0xF are ignored.
+    pub fn release(&mut self, key: u8) {
+        if key > 0xF {
+            return;
         }
+
+        self.keys[key as usize] = false;
+        self.just_released = Some(key);
     }
 
     pub fn is_pressed(&self, key: u8) -> bool {
@@ -67,4 +56,32 @@ impl Keyboard {
     pub fn update(&mut self) {
         self.just_released = None;
     }
+
+    /// Writes the held keys as a mask, followed by the key released this
+    /// cycle, or 0xFF if there is none.
+    pub(crate) fn write_state(&self, writer: &mut StateWriter) {
+        let held = (0..16).filter(|key| self.keys[*key]).fold(0, |mask, key| mask | 1 << key);
+        writer.u16(held);
+        writer.u8(self.just_released.unwrap_or(0xFF));
+    }
+
+    pub(crate) fn read_state(reader: &mut StateReader) -> Result<Self, StateError> {
+        let held = reader.u16()?;
+        let mut keyboard = Self::new();
+        for (key, pressed) in keyboard.keys.iter_mut().enumerate() {
+            *pressed = held >> key & 1 == 1;
+        }
+        keyboard.just_released = match reader.u8()? {
+            0xFF => None,
+            key if key <= 0xF => Some(key),
+            _ => return Err(StateError::Invalid("the released key is not on the keypad")),
+        };
+        Ok(keyboard)
+    }
+}
+
+impl Default for Keyboard {
+    fn default() -> Self {
+        Self::new()
+    }
 }
@@ -1,4 +1,11 @@
+use crate::emulator::{StateError, StateReader, StateWriter};
+use bitflags::bitflags;
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::io;
+
 const PROGRAM_OFFSET: u16 = 512;
+const MEMORY_SIZE: usize = 0x1000;
 
 /// The Chip-8 language is capable of accessing up to 4KB (4,096 bytes) of RAM,
 /// from location 0x000 (0) to 0xFFF (4095). The first 512 bytes, from 0x000
@@ -27,29 +34,162 @@ const PROGRAM_OFFSET: u16 = 512;
 /// | Reserved for  |
 /// |  interpreter  |
 /// +---------------+= 0x000 (0) Start of Chip-8 RAM
+///
+/// XO-CHIP extends the address space to 64KB (65,536 bytes), from 0x0000 to
+/// 0xFFFF. Other platforms keep 4KB, as programs written for them may rely on
+/// addresses past 0xFFF wrapping around to the start; `Quirks::memory_size`
+/// gives the size for a platform.
 #[derive(Debug)]
 pub struct Memory {
-    ram: [u8; 4096],
+    ram: Vec<u8>,
+    watchpoints: BTreeMap<u16, Access>,
+    /// The first access to a watched address since the last call to
+    /// `take_watch_hit`. Reads don't take `&mut self`, hence the cell.
+    watch_hit: Cell<Option<WatchHit>>,
+}
+
+bitflags! {
+    /// The kinds of memory access a watchpoint stops on.
+    pub struct Access: u8 {
+        const READ = 0b01;
+        const WRITE = 0b10;
+    }
+}
+
+/// An access to a watched address.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WatchHit {
+    pub address: u16,
+    pub access: Access,
 }
 
 impl Memory {
+    /// Creates the original interpreter's 4KB of memory.
     pub fn new() -> Self {
-        let mut memory = Self { ram: [0; 4096] };
+        Self::with_size(MEMORY_SIZE)
+    }
+
+    /// Creates memory of the given size in bytes, which must be a power of
+    /// two. Addresses past the end wrap around to the start.
+    pub fn with_size(size: usize) -> Self {
+        debug_assert!(size.is_power_of_two());
+
+        let mut memory = Self {
+            ram: vec![0; size],
+            watchpoints: BTreeMap::new(),
+            watch_hit: Cell::new(None),
+        };
         crate::emulator::sprites::load_default_sprites(&mut memory);
         memory
     }
 
     pub fn read(&self, address: u16) -> u8 {
-        self.ram[address as usize]
+        self.watch(address, Access::READ);
+        self.peek(address)
     }
 
     pub fn write(&mut self, address: u16, value: u8) {
-        self.ram[address as usize] = value;
+        self.watch(address, Access::WRITE);
+        self.poke(address, value);
+    }
+
+    /// Reads a byte without triggering watchpoints.
+    pub fn peek(&self, address: u16) -> u8 {
+        self.ram[self.index(address)]
+    }
+
+    /// Writes a byte without triggering watchpoints.
+    pub fn poke(&mut self, address: u16, value: u8) {
+        let index = self.index(address);
+        self.ram[index] = value;
     }
 
-    pub fn load_rom(&mut self, rom_data: &[u8]) {
-        for (i, byte) in rom_data.iter().enumerate() {
-            self.write(PROGRAM_OFFSET + (i as u16), *byte);
+    fn index(&self, address: u16) -> usize {
+        address as usize & (self.ram.len() - 1)
+    }
+
+    fn watch(&self, address: u16, access: Access) {
+        if self.watch_hit.get().is_some() {
+            return;
         }
+
+        let address = self.index(address) as u16;
+        if let Some(watched) = self.watchpoints.get(&address) {
+            if watched.contains(access) {
+                self.watch_hit.set(Some(WatchHit { address, access }));
+            }
+        }
+    }
+
+    /// Watches an address for the given kinds of access, replacing any
+    /// watchpoint already on it.
+    pub fn add_watchpoint(&mut self, address: u16, access: Access) {
+        let address = self.index(address) as u16;
+        self.watchpoints.insert(address, access);
+    }
+
+    pub fn remove_watchpoint(&mut self, address: u16) -> bool {
+        let address = self.index(address) as u16;
+        self.watchpoints.remove(&address).is_some()
+    }
+
+    pub fn watchpoints(&self) -> impl Iterator<Item = (u16, Access)> + '_ {
+        self.watchpoints.iter().map(|(address, access)| (*address, *access))
+    }
+
+    pub(crate) fn write_state(&self, writer: &mut StateWriter) {
+        writer.bytes(&self.ram);
+    }
+
+    /// Reads memory written by `write_state`, keeping this memory's
+    /// watchpoints.
+    pub(crate) fn read_state(&self, reader: &mut StateReader) -> Result<Self, StateError> {
+        let ram = reader.bytes()?;
+        if !ram.len().is_power_of_two() {
+            return Err(StateError::Invalid("the memory size is not a power of two"));
+        }
+
+        Ok(Self {
+            ram: ram.to_vec(),
+            watchpoints: self.watchpoints.clone(),
+            watch_hit: Cell::new(None),
+        })
+    }
+
+    /// Returns the first access to a watched address since the last call, and
+    /// forgets it.
+    pub fn take_watch_hit(&mut self) -> Option<WatchHit> {
+        self.watch_hit.take()
+    }
+
+    pub fn size(&self) -> usize {
+        self.ram.len()
+    }
+
+    /// Changes the size of memory, which must be a power of two, keeping the
+    /// bytes that still fit.
+    pub fn resize(&mut self, size: usize) {
+        debug_assert!(size.is_power_of_two());
+        self.ram.resize(size, 0);
+    }
+
+    /// Copies a ROM to where programs start, failing with
+    /// `io::ErrorKind::InvalidData` if it doesn't fit in the rest of memory.
+    pub fn load_rom(&mut self, rom_data: &[u8]) -> io::Result<()> {
+        let space = self.ram.len() - PROGRAM_OFFSET as usize;
+        if rom_data.len() > space {
+            let message = format!("the ROM is {} bytes, but only {} fit in memory", rom_data.len(), space);
+            return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+        }
+
+        let start = PROGRAM_OFFSET as usize;
+        self.ram[start..start + rom_data.len()].copy_from_slice(rom_data);
+        Ok(())
+    }
+}
+
+impl Default for Memory {
+    fn default() -> Self {
+        Self::new()
     }
 }
@@ -4,6 +4,12 @@ pub use chip::*;
 mod display;
 pub use display::*;
 
+mod image;
+pub use image::*;
+
+mod instruction;
+pub use instruction::*;
+
 mod keyboard;
 pub use keyboard::*;
 
@@ -13,7 +19,19 @@ pub use registers::*;
 mod memory;
 pub use memory::*;
 
+mod quirks;
+pub use quirks::*;
+
+mod random;
+pub use random::*;
+
 pub mod sprites;
 
+mod state;
+pub use state::*;
+
 mod timers;
 pub use timers::*;
+
+mod timing;
+pub use timing::*;
@@ -0,0 +1,153 @@
+use std::str::FromStr;
+use thiserror::Error;
+
+/// The Chip-8 language was never formally specified, and the interpreters
+/// that followed the original COSMAC VIP one changed the behaviour of a few
+/// instructions. Programs written for one interpreter may rely on these
+/// differences, so each of them can be toggled individually.
+///
+/// See https://github.com/Timendus/chip8-test-suite#quirks-test for a list of
+/// which platforms use which behaviour.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Quirks {
+    /// 8xy6/8xyE shift Vx in place and ignore Vy, instead of storing the
+    /// shifted value of Vy in Vx.
+    pub shift_vx: bool,
+    /// How far Fx55/Fx65 move I after storing or loading registers.
+    pub load_store_increment_i: IncrementI,
+    /// 8xy1/8xy2/8xy3 reset the flag register VF to 0.
+    pub logic_resets_vf: bool,
+    /// Bnnn jumps to nnn + Vx, where x is the highest nibble of nnn, instead
+    /// of nnn + V0.
+    pub jump_vx: bool,
+    /// Dxyn waits for the start of the next frame (the vertical blank
+    /// interrupt) before drawing, limiting programs to one sprite per frame.
+    pub display_wait: bool,
+    /// Dxyn wraps sprites that cross the edge of the screen around to the
+    /// opposite side, instead of cutting them off.
+    pub wrap_sprites: bool,
+    /// Memory is 64KB, as XO-CHIP's is, instead of the 4KB other platforms
+    /// had, past which addresses wrap around.
+    pub extended_memory: bool,
+}
+
+/// Where Fx55/Fx65 leave I after storing or loading registers V0 through Vx.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IncrementI {
+    /// I is left unchanged.
+    None,
+    /// I is left at I + x, pointing at the last register, as CHIP-48 did.
+    X,
+    /// I is left at I + x + 1, pointing past the last register, as the
+    /// COSMAC VIP did.
+    XPlusOne,
+}
+
+impl IncrementI {
+    /// How much I moves when registers V0 through Vx are stored or loaded.
+    pub fn amount(self, x: u8) -> u16 {
+        match self {
+            IncrementI::None => 0,
+            IncrementI::X => x as u16,
+            IncrementI::XPlusOne => x as u16 + 1,
+        }
+    }
+}
+
+impl Quirks {
+    /// The original interpreter for the RCA COSMAC VIP.
+    pub const COSMAC_VIP: Quirks = Quirks {
+        shift_vx: false,
+        load_store_increment_i: IncrementI::XPlusOne,
+        logic_resets_vf: true,
+        jump_vx: false,
+        display_wait: true,
+        wrap_sprites: false,
+        extended_memory: false,
+    };
+
+    /// The CHIP-48 interpreter for the HP-48 graphing calculators.
+    pub const CHIP_48: Quirks = Quirks {
+        shift_vx: true,
+        load_store_increment_i: IncrementI::X,
+        logic_resets_vf: false,
+        jump_vx: true,
+        display_wait: false,
+        wrap_sprites: false,
+        extended_memory: false,
+    };
+
+    /// The SUPER-CHIP 1.1 interpreter for the HP-48 graphing calculators.
+    pub const SUPER_CHIP: Quirks = Quirks {
+        shift_vx: true,
+        load_store_increment_i: IncrementI::None,
+        logic_resets_vf: false,
+        jump_vx: true,
+        display_wait: false,
+        wrap_sprites: false,
+        extended_memory: false,
+    };
+
+    /// The behaviour most modern interpreters settled on for Chip-8 programs.
+    pub const MODERN: Quirks = Quirks {
+        shift_vx: false,
+        load_store_increment_i: IncrementI::XPlusOne,
+        logic_resets_vf: false,
+        jump_vx: false,
+        display_wait: false,
+        wrap_sprites: false,
+        extended_memory: false,
+    };
+
+    /// The XO-CHIP extension, as implemented by Octo.
+    pub const XO_CHIP: Quirks = Quirks {
+        shift_vx: false,
+        load_store_increment_i: IncrementI::XPlusOne,
+        logic_resets_vf: false,
+        jump_vx: false,
+        display_wait: false,
+        wrap_sprites: true,
+        extended_memory: true,
+    };
+
+    /// The size of memory in bytes.
+    pub fn memory_size(&self) -> usize {
+        if self.extended_memory {
+            0x10000
+        } else {
+            0x1000
+        }
+    }
+
+    /// The presets by the names they are parsed from.
+    pub const PRESETS: [(&'static str, Quirks); 5] = [
+        ("vip", Quirks::COSMAC_VIP),
+        ("chip-48", Quirks::CHIP_48),
+        ("super-chip", Quirks::SUPER_CHIP),
+        ("modern", Quirks::MODERN),
+        ("xo-chip", Quirks::XO_CHIP),
+    ];
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("unknown quirks preset '{0}', expected vip, chip-48, super-chip, modern or xo-chip")]
+pub struct UnknownPreset(pub String);
+
+/// Parses the name of a preset.
+impl FromStr for Quirks {
+    type Err = UnknownPreset;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Quirks::PRESETS
+            .iter()
+            .find(|(preset, _)| preset.eq_ignore_ascii_case(name))
+            .map(|(_, quirks)| *quirks)
+            .ok_or_else(|| UnknownPreset(name.to_string()))
+    }
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Self::COSMAC_VIP
+    }
+}
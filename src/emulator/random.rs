@@ -0,0 +1,180 @@
+use rand::Rng;
+
+/// Where the RND instruction gets its random bytes from. A source's state must
+/// fit in a u64, so it can be saved in save states and set from a seed.
+pub trait RandomSource {
+    fn next_byte(&mut self) -> u8;
+
+    fn state(&self) -> u64;
+
+    fn set_state(&mut self, state: u64);
+
+    /// Which source this is, so save states and movies can name it.
+    fn kind(&self) -> RandomKind {
+        RandomKind::Custom
+    }
+}
+
+/// The random sources save states and movies record, so they are restored
+/// with the source they were made with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RandomKind {
+    Xorshift,
+    CosmacVip,
+    /// A source implemented outside of chirp-8, which can't be recreated.
+    Custom,
+}
+
+/// A xorshift64* generator, and the default source.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Xorshift {
+    state: u64,
+}
+
+/// The pseudo-random algorithm of the COSMAC VIP's CHIP-8 interpreter. It
+/// keeps a 16-bit register, R9. For each random number, R9 is incremented, and
+/// the byte at address 0x100 plus R9's low byte is added to R9's high byte,
+/// which is the result. On the VIP that page held the interpreter itself, which
+/// programs loaded at 0x200 can't change, so the generator keeps its own copy
+/// of the page rather than reading the chip's memory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CosmacVipRandom {
+    r9: u16,
+    page: [u8; 256],
+}
+
+/// The page `CosmacVipRandom` reads by default. chirp-8 doesn't include the
+/// VIP's interpreter, so it is a fixed shuffle of every byte value instead,
+/// which gives the algorithm the same spread of numbers; the sequence only
+/// matches a real VIP's with the interpreter's own page, given to
+/// `CosmacVipRandom::with_page`.
+pub const VIP_RANDOM_PAGE: [u8; 256] = shuffled_bytes(0x1802);
+
+impl Xorshift {
+    /// Seeds the generator from the operating system.
+    pub fn from_entropy() -> Self {
+        Self::from_seed(rand::thread_rng().gen())
+    }
+
+    pub fn from_seed(seed: u64) -> Self {
+        let mut xorshift = Self { state: 0 };
+        xorshift.set_state(seed);
+        xorshift
+    }
+}
+
+impl RandomSource for Xorshift {
+    fn next_byte(&mut self) -> u8 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        (self.state.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 56) as u8
+    }
+
+    fn state(&self) -> u64 {
+        self.state
+    }
+
+    /// Xorshift never leaves a state of 0, so it is replaced with 1.
+    fn set_state(&mut self, state: u64) {
+        self.state = state.max(1);
+    }
+
+    fn kind(&self) -> RandomKind {
+        RandomKind::Xorshift
+    }
+}
+
+impl CosmacVipRandom {
+    pub fn new() -> Self {
+        Self::with_page(VIP_RANDOM_PAGE)
+    }
+
+    /// Reads the bytes from `page`, such as addresses 0x100 to 0x1FF of a dump
+    /// of the VIP's interpreter.
+    pub fn with_page(page: [u8; 256]) -> Self {
+        Self { r9: 0, page }
+    }
+}
+
+impl Default for CosmacVipRandom {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RandomSource for CosmacVipRandom {
+    fn next_byte(&mut self) -> u8 {
+        self.r9 = self.r9.wrapping_add(1);
+        let [high, low] = self.r9.to_be_bytes();
+        let high = high.wrapping_add(self.page[low as usize]);
+        self.r9 = u16::from_be_bytes([high, low]);
+        high
+    }
+
+    fn state(&self) -> u64 {
+        self.r9 as u64
+    }
+
+    fn set_state(&mut self, state: u64) {
+        self.r9 = state as u16;
+    }
+
+    fn kind(&self) -> RandomKind {
+        RandomKind::CosmacVip
+    }
+}
+
+impl RandomKind {
+    /// A new source of this kind, or None for a custom source.
+    pub fn source(self) -> Option<Box<dyn RandomSource>> {
+        match self {
+            RandomKind::Xorshift => Some(Box::new(Xorshift::from_seed(1))),
+            RandomKind::CosmacVip => Some(Box::new(CosmacVipRandom::new())),
+            RandomKind::Custom => None,
+        }
+    }
+
+    /// The byte the kind is recorded as.
+    pub fn code(self) -> u8 {
+        match self {
+            RandomKind::Xorshift => 0,
+            RandomKind::CosmacVip => 1,
+            RandomKind::Custom => 2,
+        }
+    }
+
+    pub fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(RandomKind::Xorshift),
+            1 => Some(RandomKind::CosmacVip),
+            2 => Some(RandomKind::Custom),
+            _ => None,
+        }
+    }
+}
+
+/// Every byte value, shuffled with Fisher-Yates by an xorshift generator
+/// started from `seed`.
+const fn shuffled_bytes(seed: u64) -> [u8; 256] {
+    let mut bytes = [0; 256];
+    let mut index = 0;
+    while index < 256 {
+        bytes[index] = index as u8;
+        index += 1;
+    }
+
+    let mut state = seed;
+    let mut index = 255;
+    while index > 0 {
+        state ^= state >> 12;
+        state ^= state << 25;
+        state ^= state >> 27;
+        let other = (state.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 32) as usize % (index + 1);
+        let byte = bytes[index];
+        bytes[index] = bytes[other];
+        bytes[other] = byte;
+        index -= 1;
+    }
+    bytes
+}
@@ -1,3 +1,6 @@
+use crate::emulator::{StateError, StateReader, StateWriter};
+use std::fmt;
+
 /// Chip-8 has 16 general purpose 8-bit registers, usually referred to as Vx,
 /// where x is a hexadecimal digit (0 through F). There is also a 16-bit
 /// register called I. This register is generally used to store memory
@@ -62,7 +65,7 @@ impl Registers {
     }
 
     pub fn increment_pc(&mut self) {
-        self.pc += 2;
+        self.pc = self.pc.wrapping_add(2);
     }
 
     pub fn push_stack(&mut self, value: u16) {
@@ -74,6 +77,32 @@ impl Registers {
         self.stack.push(value);
     }
 
+    /// The return addresses of the subroutines being executed, innermost last.
+    pub fn stack(&self) -> &[u16] {
+        &self.stack
+    }
+
+    pub(crate) fn write_state(&self, writer: &mut StateWriter) {
+        self.v.iter().for_each(|value| writer.u8(*value));
+        writer.u16(self.i);
+        writer.u16(self.pc);
+        writer.u16(self.stack.len() as u16);
+        self.stack.iter().for_each(|address| writer.u16(*address));
+    }
+
+    pub(crate) fn read_state(reader: &mut StateReader) -> Result<Self, StateError> {
+        let mut registers = Self::new();
+        for value in registers.v.iter_mut() {
+            *value = reader.u8()?;
+        }
+        registers.i = reader.u16()?;
+        registers.pc = reader.u16()?;
+        for _ in 0..reader.u16()? {
+            registers.stack.push(reader.u16()?);
+        }
+        Ok(registers)
+    }
+
     pub fn pop_stack(&mut self) -> u16 {
         self.stack.pop().unwrap_or_else(|| {
             println!("Attempted to pop empty stack. Returning 0.");
@@ -81,3 +110,34 @@ impl Registers {
         })
     }
 }
+
+impl Default for Registers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A register that can be named by programs, as opposed to the PC and stack.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Register {
+    V(u8),
+    I,
+}
+
+impl Register {
+    pub fn value(self, registers: &Registers) -> u16 {
+        match self {
+            Register::V(x) => registers.v(x) as u16,
+            Register::I => registers.i(),
+        }
+    }
+}
+
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Register::V(x) => write!(f, "V{:X}", x),
+            Register::I => write!(f, "I"),
+        }
+    }
+}
@@ -1,3 +1,30 @@
+/// The address of the 4x5 hexadecimal font, which takes up 5 bytes per digit.
+pub const FONT_ADDRESS: u16 = 0x00;
+/// The address of the SUPER-CHIP 8x10 hexadecimal font, which takes up 10
+/// bytes per digit. It directly follows the small font.
+pub const BIG_FONT_ADDRESS: u16 = 0x50;
+
+/// SUPER-CHIP only defined the digits 0 through 9, the letters are the ones
+/// used by Octo.
+const BIG_FONT: [u8; 160] = [
+    0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, // 0
+    0x18, 0x78, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0xFF, // 1
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // 2
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 3
+    0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0x03, 0x03, // 4
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 5
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 6
+    0xFF, 0xFF, 0x03, 0x03, 0x06, 0x0C, 0x18, 0x18, 0x18, 0x18, // 7
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 8
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 9
+    0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
+    0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // B
+    0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // C
+    0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // E
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0, // F
+];
+
 pub fn load_default_sprites(memory: &mut crate::emulator::Memory) {
     // Font Character: 0
     memory.write(0x00, 0b11110000); // ****
@@ -110,4 +137,8 @@ pub fn load_default_sprites(memory: &mut crate::emulator::Memory) {
     memory.write(0x4D, 0b11110000); // ****
     memory.write(0x4E, 0b10000000); // *
     memory.write(0x4F, 0b10000000); // *
+
+    for (i, byte) in BIG_FONT.iter().enumerate() {
+        memory.write(BIG_FONT_ADDRESS + i as u16, *byte);
+    }
 }
@@ -0,0 +1,128 @@
+use thiserror::Error;
+
+/// The bytes every save state starts with.
+pub const STATE_MAGIC: [u8; 4] = *b"C8ST";
+
+/// The version of the save state format written by `Chip::save_state`. It is
+/// incremented whenever the format changes, and only states of the current
+/// version can be loaded.
+pub const STATE_VERSION: u16 = 5;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum StateError {
+    #[error("not a save state")]
+    InvalidMagic,
+    #[error("save state version {found} is not supported, expected version {expected}")]
+    UnsupportedVersion { found: u16, expected: u16 },
+    #[error("the save state ends unexpectedly")]
+    Truncated,
+    #[error("the save state has {0} bytes left over")]
+    TrailingBytes(usize),
+    #[error("the save state is invalid: {0}")]
+    Invalid(&'static str),
+}
+
+/// Appends values to a save state, with multi-byte values big-endian.
+#[derive(Default)]
+pub struct StateWriter {
+    bytes: Vec<u8>,
+}
+
+/// Reads values written by a `StateWriter`, in the same order.
+pub struct StateReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl StateWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    pub fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    pub fn u16(&mut self, value: u16) {
+        self.bytes.extend_from_slice(&value.to_be_bytes());
+    }
+
+    pub fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_be_bytes());
+    }
+
+    pub fn u64(&mut self, value: u64) {
+        self.bytes.extend_from_slice(&value.to_be_bytes());
+    }
+
+    pub fn bool(&mut self, value: bool) {
+        self.u8(value as u8);
+    }
+
+    /// Writes the bytes, preceded by their length.
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        self.u32(bytes.len() as u32);
+        self.bytes.extend_from_slice(bytes);
+    }
+}
+
+impl<'a> StateReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    /// Fails if anything is left unread.
+    pub fn finish(self) -> Result<(), StateError> {
+        match self.bytes.len() {
+            0 => Ok(()),
+            len => Err(StateError::TrailingBytes(len)),
+        }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], StateError> {
+        if self.bytes.len() < len {
+            return Err(StateError::Truncated);
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    pub fn u8(&mut self) -> Result<u8, StateError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn u16(&mut self) -> Result<u16, StateError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub fn u32(&mut self) -> Result<u32, StateError> {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(self.take(4)?);
+        Ok(u32::from_be_bytes(bytes))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, StateError> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(u64::from_be_bytes(bytes))
+    }
+
+    pub fn bool(&mut self) -> Result<bool, StateError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(StateError::Invalid("a flag is neither 0 nor 1")),
+        }
+    }
+
+    /// Reads bytes written by `StateWriter::bytes`.
+    pub fn bytes(&mut self) -> Result<&'a [u8], StateError> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+}
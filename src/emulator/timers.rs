@@ -1,3 +1,5 @@
+use crate::emulator::{StateError, StateReader, StateWriter};
+
 /// Chip-8 provides 2 timers, a delay timer and a sound timer.
 //
 // The delay timer is active whenever the delay timer register (DT) is
@@ -45,4 +47,19 @@ impl Timers {
     pub fn set_sound(&mut self, value: u8) {
         self.sound = value;
     }
+
+    pub(crate) fn write_state(&self, writer: &mut StateWriter) {
+        writer.u8(self.delay);
+        writer.u8(self.sound);
+    }
+
+    pub(crate) fn read_state(reader: &mut StateReader) -> Result<Self, StateError> {
+        Ok(Self { delay: reader.u8()?, sound: reader.u8()? })
+    }
+}
+
+impl Default for Timers {
+    fn default() -> Self {
+        Self::new()
+    }
 }
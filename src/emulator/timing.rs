@@ -0,0 +1,88 @@
+use crate::emulator::{Instruction, Registers};
+
+/// The COSMAC VIP's CDP1802 runs at 1.76064 MHz, and a machine cycle takes 8
+/// clock periods, so a frame at 60 Hz lasts this many machine cycles.
+pub const VIP_CYCLES_PER_FRAME: u32 = 3668;
+
+/// The machine cycles the CDP1861 takes from each frame to fetch the display
+/// through DMA, one per byte of its 128 scanlines of 8 bytes.
+pub const VIP_DISPLAY_DMA_CYCLES: u32 = 1024;
+
+/// The machine cycles of the interrupt routine that runs every frame, which
+/// sets up the display DMA and counts the timers down.
+pub const VIP_INTERRUPT_CYCLES: u32 = 46;
+
+/// The machine cycles left for the interpreter in each frame.
+pub const VIP_INTERPRETER_CYCLES: u32 = VIP_CYCLES_PER_FRAME - VIP_DISPLAY_DMA_CYCLES - VIP_INTERRUPT_CYCLES;
+
+/// How the chip decides where its cycles, which are frames at 60 Hz, end.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Timing {
+    /// Every instruction takes the same time, and a cycle is a fixed number
+    /// of instructions.
+    #[default]
+    Instructions,
+    /// Instructions take as many machine cycles as they did in the COSMAC
+    /// VIP's interpreter, and a cycle ends once a frame's worth of machine
+    /// cycles has been spent. The display interrupt's share of each frame is
+    /// not available to the interpreter.
+    CosmacVip,
+}
+
+/// The machine cycles the COSMAC VIP's interpreter took to execute an opcode,
+/// including fetching it, given the registers before it is executed. The costs
+/// are the instruction times published in "Chip-8 Instruction Scheduling and
+/// Frequency" (Jackson S., 2019), in microseconds, converted to machine
+/// cycles. Drawing and loading or storing registers take a time that depends
+/// on their operands, which that table averages, so they are modelled on the
+/// length of the interpreter's loops instead; the table's time for drawing
+/// also includes the wait for the display interrupt, which the chip models on
+/// its own. Instructions the VIP's interpreter didn't have, and invalid ones,
+/// are charged as much as its arithmetic.
+pub fn vip_cycles(opcode: u16, registers: &Registers) -> u32 {
+    use Instruction::*;
+
+    let instruction = match Instruction::decode(opcode) {
+        Ok(instruction) => instruction,
+        Err(_) => return micros(200),
+    };
+    match instruction {
+        CLS => micros(109),
+        RET | JP_addr(_) | CALL_addr(_) | JP_V0_addr(_) => micros(105),
+        SE_Vx_byte(..) | SNE_Vx_byte(..) | LD_I_addr(_) => micros(55),
+        SE_Vx_Vy(..) | SNE_Vx_Vy(..) | SKP_Vx(_) | SKNP_Vx(_) => micros(73),
+        LD_Vx_byte(..) => micros(27),
+        ADD_Vx_byte(..) | LD_Vx_DT(_) | LD_Vx_K(_) | LD_DT_Vx(_) | LD_ST_Vx(_) => micros(45),
+        LD_Vx_Vy(..) | OR_Vx_Vy(..) | AND_Vx_Vy(..) | XOR_Vx_Vy(..) | ADD_Vx_Vy(..) | SUB_Vx_Vy(..)
+        | SHR_Vx_Vy(..) | SUBN_Vx_Vy(..) | SHL_Vx_Vy(..) => micros(200),
+        RND_Vx_byte(..) => micros(164),
+        DRW_Vx_Vy_n(x, _, nibble) => drw_cycles(registers.v(x), nibble),
+        ADD_I_Vx(_) => micros(86),
+        LD_F_Vx(_) => micros(91),
+        LD_B_Vx(_) => micros(927),
+        // 605 µs, 133 machine cycles, on average over the registers.
+        LD_I_Vx(x) | LD_Vx_I(x) => 14 + 14 * (x as u32 + 1),
+        _ => micros(200),
+    }
+}
+
+/// Converts microseconds to the nearest number of machine cycles, each of
+/// which takes 8 periods of the 1.76064 MHz clock, about 4.54 µs.
+const fn micros(micros: u32) -> u32 {
+    (micros * 1_760_640 / 8 + 500_000) / 1_000_000
+}
+
+/// Drawing a sprite costs a fixed setup, and then a cost per row. A sprite
+/// that isn't aligned on a byte of the display spans two bytes per row, and
+/// each row is shifted into place one bit at a time.
+fn drw_cycles(x: u8, nibble: u8) -> u32 {
+    let (rows, bytes_per_row) = if nibble == 0 { (16, 2) } else { (nibble as u32, 1) };
+    let shift = (x % 8) as u32;
+
+    let row = if shift == 0 {
+        24 * bytes_per_row
+    } else {
+        (24 + 16 + 6 * shift) * bytes_per_row
+    };
+    26 + rows * row
+}
@@ -0,0 +1,127 @@
+use glutin::event::VirtualKeyCode;
+
+/// The speeds the emulator can run at, as multiples of 60 frames per second.
+pub const SPEEDS: [f64; 7] = [0.25, 0.5, 0.75, 1.0, 1.5, 2.0, 4.0];
+
+/// The index of full speed in `SPEEDS`.
+pub const NORMAL_SPEED: usize = 3;
+
+/// How many times faster than the current speed fast-forwarding runs.
+pub const FAST_FORWARD: f64 = 4.0;
+
+/// The emulator's own keys, which are handled before the keypad's. None of
+/// them can be bound to the keypad, as the keymap has no names for them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hotkey {
+    /// F1 to F8 load the save slot of the same number, and save to it while
+    /// shift is held.
+    SaveSlot(u8),
+    /// F9 pauses and resumes the program.
+    Pause,
+    /// F10 runs a single frame while the program is paused.
+    FrameAdvance,
+    /// F11 starts the program over, as if the machine had been switched off
+    /// and on.
+    Reset,
+    /// F12 prints the keymap.
+    ShowKeymap,
+    /// Insert saves a screenshot.
+    Screenshot,
+    /// Holding Tab runs the program at several times its speed.
+    FastForward,
+    /// Holding Backspace runs time backwards.
+    Rewind,
+    /// = runs the program faster.
+    SpeedUp,
+    /// - runs the program slower.
+    SpeedDown,
+    /// Escape closes the emulator.
+    Quit,
+}
+
+impl Hotkey {
+    pub fn of(keycode: VirtualKeyCode) -> Option<Self> {
+        match keycode {
+            VirtualKeyCode::F1 => Some(Hotkey::SaveSlot(1)),
+            VirtualKeyCode::F2 => Some(Hotkey::SaveSlot(2)),
+            VirtualKeyCode::F3 => Some(Hotkey::SaveSlot(3)),
+            VirtualKeyCode::F4 => Some(Hotkey::SaveSlot(4)),
+            VirtualKeyCode::F5 => Some(Hotkey::SaveSlot(5)),
+            VirtualKeyCode::F6 => Some(Hotkey::SaveSlot(6)),
+            VirtualKeyCode::F7 => Some(Hotkey::SaveSlot(7)),
+            VirtualKeyCode::F8 => Some(Hotkey::SaveSlot(8)),
+            VirtualKeyCode::F9 => Some(Hotkey::Pause),
+            VirtualKeyCode::F10 => Some(Hotkey::FrameAdvance),
+            VirtualKeyCode::F11 => Some(Hotkey::Reset),
+            VirtualKeyCode::F12 => Some(Hotkey::ShowKeymap),
+            VirtualKeyCode::Insert => Some(Hotkey::Screenshot),
+            VirtualKeyCode::Tab => Some(Hotkey::FastForward),
+            VirtualKeyCode::Back => Some(Hotkey::Rewind),
+            VirtualKeyCode::Equals => Some(Hotkey::SpeedUp),
+            VirtualKeyCode::Minus => Some(Hotkey::SpeedDown),
+            VirtualKeyCode::Escape => Some(Hotkey::Quit),
+            _ => None,
+        }
+    }
+
+    /// Whether the hotkey acts for as long as it is held, rather than once
+    /// when it is pressed.
+    pub fn held(self) -> bool {
+        matches!(self, Hotkey::FastForward | Hotkey::Rewind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keymap::KeyMap;
+
+    #[test]
+    fn maps_function_keys_to_save_slots() {
+        let keys = [
+            VirtualKeyCode::F1,
+            VirtualKeyCode::F2,
+            VirtualKeyCode::F3,
+            VirtualKeyCode::F4,
+            VirtualKeyCode::F5,
+            VirtualKeyCode::F6,
+            VirtualKeyCode::F7,
+            VirtualKeyCode::F8,
+        ];
+        for (slot, key) in (1..).zip(keys.iter()) {
+            assert_eq!(Hotkey::of(*key), Some(Hotkey::SaveSlot(slot)));
+        }
+        assert_eq!(Hotkey::of(VirtualKeyCode::F9), Some(Hotkey::Pause));
+        assert_eq!(Hotkey::of(VirtualKeyCode::F12), Some(Hotkey::ShowKeymap));
+        assert_eq!(Hotkey::of(VirtualKeyCode::Insert), Some(Hotkey::Screenshot));
+        assert_eq!(Hotkey::of(VirtualKeyCode::Escape), Some(Hotkey::Quit));
+        assert_eq!(Hotkey::of(VirtualKeyCode::F13), None);
+        assert_eq!(Hotkey::of(VirtualKeyCode::Space), None);
+    }
+
+    #[test]
+    fn only_fast_forward_and_rewind_are_held() {
+        assert!(Hotkey::FastForward.held());
+        assert!(Hotkey::Rewind.held());
+        assert!(!Hotkey::Pause.held());
+        assert!(!Hotkey::FrameAdvance.held());
+        assert!(!Hotkey::SpeedUp.held());
+        assert!(!Hotkey::SaveSlot(1).held());
+    }
+
+    #[test]
+    fn no_keymap_preset_uses_a_hotkey() {
+        for name in KeyMap::PRESETS {
+            let keymap = KeyMap::preset(name).unwrap();
+            for key in keymap.host_keys() {
+                assert_eq!(Hotkey::of(key), None, "{} binds {:?}", name, key);
+            }
+        }
+    }
+
+    #[test]
+    fn speeds_go_up_from_normal() {
+        assert_eq!(SPEEDS[NORMAL_SPEED], 1.0);
+        assert!(SPEEDS.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+}
@@ -0,0 +1,333 @@
+use glutin::event::VirtualKeyCode;
+use std::collections::BTreeMap;
+use std::fmt;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum KeyMapError {
+    #[error("unknown keymap preset '{0}', expected qwerty, numpad or azerty")]
+    UnknownPreset(String),
+    #[error("'{0}' is not a key of the keypad, expected a hexadecimal digit")]
+    NotOnKeypad(String),
+    #[error("'{0}' is not the name of a key that can be bound")]
+    UnknownKeyName(String),
+}
+
+/// The host keys that can be bound to the keypad, by name. Function keys, Tab,
+/// Backspace, Insert, =, - and Escape are left out, as they are the
+/// emulator's hotkeys.
+const HOST_KEYS: [(&str, VirtualKeyCode); 64] = [
+    ("1", VirtualKeyCode::Key1),
+    ("2", VirtualKeyCode::Key2),
+    ("3", VirtualKeyCode::Key3),
+    ("4", VirtualKeyCode::Key4),
+    ("5", VirtualKeyCode::Key5),
+    ("6", VirtualKeyCode::Key6),
+    ("7", VirtualKeyCode::Key7),
+    ("8", VirtualKeyCode::Key8),
+    ("9", VirtualKeyCode::Key9),
+    ("0", VirtualKeyCode::Key0),
+    ("A", VirtualKeyCode::A),
+    ("B", VirtualKeyCode::B),
+    ("C", VirtualKeyCode::C),
+    ("D", VirtualKeyCode::D),
+    ("E", VirtualKeyCode::E),
+    ("F", VirtualKeyCode::F),
+    ("G", VirtualKeyCode::G),
+    ("H", VirtualKeyCode::H),
+    ("I", VirtualKeyCode::I),
+    ("J", VirtualKeyCode::J),
+    ("K", VirtualKeyCode::K),
+    ("L", VirtualKeyCode::L),
+    ("M", VirtualKeyCode::M),
+    ("N", VirtualKeyCode::N),
+    ("O", VirtualKeyCode::O),
+    ("P", VirtualKeyCode::P),
+    ("Q", VirtualKeyCode::Q),
+    ("R", VirtualKeyCode::R),
+    ("S", VirtualKeyCode::S),
+    ("T", VirtualKeyCode::T),
+    ("U", VirtualKeyCode::U),
+    ("V", VirtualKeyCode::V),
+    ("W", VirtualKeyCode::W),
+    ("X", VirtualKeyCode::X),
+    ("Y", VirtualKeyCode::Y),
+    ("Z", VirtualKeyCode::Z),
+    ("Numpad0", VirtualKeyCode::Numpad0),
+    ("Numpad1", VirtualKeyCode::Numpad1),
+    ("Numpad2", VirtualKeyCode::Numpad2),
+    ("Numpad3", VirtualKeyCode::Numpad3),
+    ("Numpad4", VirtualKeyCode::Numpad4),
+    ("Numpad5", VirtualKeyCode::Numpad5),
+    ("Numpad6", VirtualKeyCode::Numpad6),
+    ("Numpad7", VirtualKeyCode::Numpad7),
+    ("Numpad8", VirtualKeyCode::Numpad8),
+    ("Numpad9", VirtualKeyCode::Numpad9),
+    ("NumpadDivide", VirtualKeyCode::NumpadDivide),
+    ("NumpadMultiply", VirtualKeyCode::NumpadMultiply),
+    ("NumpadSubtract", VirtualKeyCode::NumpadSubtract),
+    ("NumpadAdd", VirtualKeyCode::NumpadAdd),
+    ("NumpadEnter", VirtualKeyCode::NumpadEnter),
+    ("NumpadDecimal", VirtualKeyCode::NumpadDecimal),
+    ("Up", VirtualKeyCode::Up),
+    ("Down", VirtualKeyCode::Down),
+    ("Left", VirtualKeyCode::Left),
+    ("Right", VirtualKeyCode::Right),
+    ("Space", VirtualKeyCode::Space),
+    ("Enter", VirtualKeyCode::Return),
+    ("Comma", VirtualKeyCode::Comma),
+    ("Period", VirtualKeyCode::Period),
+    ("Slash", VirtualKeyCode::Slash),
+    ("Semicolon", VirtualKeyCode::Semicolon),
+    ("LBracket", VirtualKeyCode::LBracket),
+    ("RBracket", VirtualKeyCode::RBracket),
+];
+
+/// The keypad keys in the layout of the original keypad, row by row.
+const KEYPAD_LAYOUT: [u8; 16] = [0x1, 0x2, 0x3, 0xC, 0x4, 0x5, 0x6, 0xD, 0x7, 0x8, 0x9, 0xE, 0xA, 0x0, 0xB, 0xF];
+
+/// Which host keys press which keys of the hexadecimal keypad. Any number of
+/// host keys can press the same keypad key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyMap {
+    bindings: BTreeMap<u8, Vec<VirtualKeyCode>>,
+}
+
+impl KeyMap {
+    /// The names of the presets, as accepted by `preset`.
+    pub const PRESETS: [&'static str; 3] = ["qwerty", "numpad", "azerty"];
+
+    /// A preset by name:
+    ///
+    /// - `qwerty`: the keypad's layout on the left block of a QWERTY keyboard,
+    ///   1-4, Q-R, A-F and Z-V.
+    /// - `numpad`: the digits on the number pad's digits, C to E down its
+    ///   right column of -, + and Enter, and A, B and F on /, * and the
+    ///   decimal point.
+    /// - `azerty`: the same block as `qwerty` on an AZERTY keyboard, 1-4, A-R,
+    ///   Q-F and W-V.
+    pub fn preset(name: &str) -> Result<Self, KeyMapError> {
+        let host_keys = match name.to_ascii_lowercase().as_str() {
+            "qwerty" => ["1", "2", "3", "4", "Q", "W", "E", "R", "A", "S", "D", "F", "Z", "X", "C", "V"],
+            "azerty" => ["1", "2", "3", "4", "A", "Z", "E", "R", "Q", "S", "D", "F", "W", "X", "C", "V"],
+            "numpad" => [
+                "Numpad1", "Numpad2", "Numpad3", "NumpadSubtract",
+                "Numpad4", "Numpad5", "Numpad6", "NumpadAdd",
+                "Numpad7", "Numpad8", "Numpad9", "NumpadEnter",
+                "NumpadDivide", "Numpad0", "NumpadMultiply", "NumpadDecimal",
+            ],
+            _ => return Err(KeyMapError::UnknownPreset(name.to_string())),
+        };
+
+        let mut keymap = Self::empty();
+        for (key, name) in KEYPAD_LAYOUT.iter().zip(host_keys.iter()) {
+            keymap.bind(*key, host_key(name)?);
+        }
+        Ok(keymap)
+    }
+
+    pub fn empty() -> Self {
+        Self { bindings: BTreeMap::new() }
+    }
+
+    /// Makes the host key press the keypad key, in addition to the host keys
+    /// already bound to it. A host key only ever presses one keypad key.
+    pub fn bind(&mut self, key: u8, host_key: VirtualKeyCode) {
+        for host_keys in self.bindings.values_mut() {
+            host_keys.retain(|bound| *bound != host_key);
+        }
+        self.bindings.entry(key).or_default().push(host_key);
+    }
+
+    /// Replaces the host keys of keypad keys with the named ones, as written
+    /// in the configuration file: a hexadecimal digit for the keypad key, and
+    /// a list of host key names.
+    pub fn rebind(&mut self, bindings: &BTreeMap<String, Vec<String>>) -> Result<(), KeyMapError> {
+        for (key, host_keys) in bindings {
+            let key = keypad_key(key)?;
+            let host_keys = host_keys.iter().map(|name| host_key(name)).collect::<Result<Vec<_>, _>>()?;
+
+            self.bindings.remove(&key);
+            for host_key in host_keys {
+                self.bind(key, host_key);
+            }
+        }
+        Ok(())
+    }
+
+    /// Binds the arrow keys to the keypad keys a game uses for directions, as
+    /// listed in the game database.
+    pub fn bind_directions(&mut self, keys: &BTreeMap<String, u8>) {
+        let arrows = [
+            ("up", VirtualKeyCode::Up),
+            ("down", VirtualKeyCode::Down),
+            ("left", VirtualKeyCode::Left),
+            ("right", VirtualKeyCode::Right),
+        ];
+        for (direction, arrow) in &arrows {
+            if let Some(key) = keys.get(*direction).filter(|key| **key <= 0xF) {
+                self.bind(*key, *arrow);
+            }
+        }
+    }
+
+    /// The keypad key a host key presses.
+    pub fn key(&self, host_key: VirtualKeyCode) -> Option<u8> {
+        self.bindings
+            .iter()
+            .find(|(_, host_keys)| host_keys.contains(&host_key))
+            .map(|(key, _)| *key)
+    }
+
+    /// The host keys bound to any keypad key.
+    pub fn host_keys(&self) -> impl Iterator<Item = VirtualKeyCode> + '_ {
+        self.bindings.values().flatten().copied()
+    }
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self::preset("qwerty").unwrap()
+    }
+}
+
+/// Shows the keypad in its original layout, with the host keys bound to each
+/// key.
+impl fmt::Display for KeyMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in KEYPAD_LAYOUT.chunks(4) {
+            let mut line = String::new();
+            for key in row {
+                let host_keys: Vec<&str> = self
+                    .bindings
+                    .get(key)
+                    .into_iter()
+                    .flatten()
+                    .map(|host_key| host_key_name(*host_key))
+                    .collect();
+                let host_keys = match host_keys.is_empty() {
+                    true => "-".to_string(),
+                    false => host_keys.join("/"),
+                };
+                line += &format!("{:X}: {:<18}", key, host_keys);
+            }
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+fn keypad_key(name: &str) -> Result<u8, KeyMapError> {
+    match u8::from_str_radix(name, 16) {
+        Ok(key) if name.len() == 1 => Ok(key),
+        _ => Err(KeyMapError::NotOnKeypad(name.to_string())),
+    }
+}
+
+/// Finds a host key by name, ignoring case.
+pub fn host_key(name: &str) -> Result<VirtualKeyCode, KeyMapError> {
+    HOST_KEYS
+        .iter()
+        .find(|(key_name, _)| key_name.eq_ignore_ascii_case(name))
+        .map(|(_, host_key)| *host_key)
+        .ok_or_else(|| KeyMapError::UnknownKeyName(name.to_string()))
+}
+
+fn host_key_name(host_key: VirtualKeyCode) -> &'static str {
+    HOST_KEYS
+        .iter()
+        .find(|(_, key)| *key == host_key)
+        .map_or("?", |(name, _)| name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bindings(pairs: &[(&str, &[&str])]) -> BTreeMap<String, Vec<String>> {
+        pairs
+            .iter()
+            .map(|(key, names)| (key.to_string(), names.iter().map(|name| name.to_string()).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn presets_cover_the_keypad() {
+        for name in KeyMap::PRESETS {
+            let keymap = KeyMap::preset(name).unwrap();
+            assert_eq!(keymap.host_keys().count(), 16, "{}", name);
+            let mut keys: Vec<u8> = keymap.host_keys().filter_map(|host_key| keymap.key(host_key)).collect();
+            keys.sort_unstable();
+            assert_eq!(keys, (0..16).collect::<Vec<u8>>(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn presets_follow_the_keyboard() {
+        let qwerty = KeyMap::preset("QWERTY").unwrap();
+        assert_eq!(qwerty, KeyMap::default());
+        assert_eq!(qwerty.key(VirtualKeyCode::Key1), Some(0x1));
+        assert_eq!(qwerty.key(VirtualKeyCode::Key4), Some(0xC));
+        assert_eq!(qwerty.key(VirtualKeyCode::Q), Some(0x4));
+        assert_eq!(qwerty.key(VirtualKeyCode::X), Some(0x0));
+        assert_eq!(qwerty.key(VirtualKeyCode::V), Some(0xF));
+        assert_eq!(qwerty.key(VirtualKeyCode::Up), None);
+
+        let azerty = KeyMap::preset("azerty").unwrap();
+        assert_eq!(azerty.key(VirtualKeyCode::A), Some(0x4));
+        assert_eq!(azerty.key(VirtualKeyCode::Z), Some(0x5));
+        assert_eq!(azerty.key(VirtualKeyCode::Q), Some(0x7));
+        assert_eq!(azerty.key(VirtualKeyCode::W), Some(0xA));
+
+        let numpad = KeyMap::preset("numpad").unwrap();
+        assert_eq!(numpad.key(VirtualKeyCode::Numpad0), Some(0x0));
+        assert_eq!(numpad.key(VirtualKeyCode::NumpadEnter), Some(0xE));
+        assert_eq!(numpad.key(VirtualKeyCode::NumpadDecimal), Some(0xF));
+
+        assert_eq!(KeyMap::preset("dvorak"), Err(KeyMapError::UnknownPreset("dvorak".to_string())));
+    }
+
+    #[test]
+    fn rebinds_keys_to_several_host_keys() {
+        let mut keymap = KeyMap::default();
+        keymap.rebind(&bindings(&[("5", &["w", "Up"]), ("a", &["Space", "Q"])])).unwrap();
+
+        assert_eq!(keymap.key(VirtualKeyCode::W), Some(0x5));
+        assert_eq!(keymap.key(VirtualKeyCode::Up), Some(0x5));
+        assert_eq!(keymap.key(VirtualKeyCode::Space), Some(0xA));
+        // Q moves from 4 to A, and the keys A and Z had are unbound.
+        assert_eq!(keymap.key(VirtualKeyCode::Q), Some(0xA));
+        assert_eq!(keymap.key(VirtualKeyCode::Z), None);
+        assert_eq!(keymap.host_keys().count(), 17);
+        assert_eq!(
+            keymap.to_string().lines().nth(1),
+            Some("4: -                 5: W/Up              6: E                 D: R")
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        let mut keymap = KeyMap::default();
+        assert_eq!(keymap.rebind(&bindings(&[("10", &["Q"])])), Err(KeyMapError::NotOnKeypad("10".to_string())));
+        assert_eq!(keymap.rebind(&bindings(&[("G", &["Q"])])), Err(KeyMapError::NotOnKeypad("G".to_string())));
+        assert_eq!(
+            keymap.rebind(&bindings(&[("1", &["Q", "F1"])])),
+            Err(KeyMapError::UnknownKeyName("F1".to_string()))
+        );
+        assert_eq!(keymap, KeyMap::default());
+    }
+
+    #[test]
+    fn binds_the_arrows_to_a_games_directions() {
+        let mut keymap = KeyMap::default();
+        let keys = [("up", 0x1), ("down", 0x4), ("left", 0x10), ("a", 0x6)];
+        keymap.bind_directions(&keys.iter().map(|(direction, key)| (direction.to_string(), *key)).collect());
+
+        assert_eq!(keymap.key(VirtualKeyCode::Up), Some(0x1));
+        assert_eq!(keymap.key(VirtualKeyCode::Down), Some(0x4));
+        assert_eq!(keymap.key(VirtualKeyCode::Left), None);
+        assert_eq!(keymap.key(VirtualKeyCode::Right), None);
+        assert_eq!(keymap.key(VirtualKeyCode::Key1), Some(0x1));
+        assert_eq!(keymap.key(VirtualKeyCode::Q), Some(0x4));
+    }
+}
@@ -0,0 +1,16 @@
+//! The CHIRP-8 emulator core. It has no dependency on a window system or an
+//! audio device: input is given as key indices, and the display and sound
+//! state are read back through the `Chip` API, so any frontend can be built on
+//! top of it.
+
+pub mod emulator;
+pub mod disassembler;
+pub mod assembler;
+pub mod audio;
+pub mod database;
+pub mod debugger;
+pub mod movie;
+pub mod rewind;
+pub mod rom;
+pub mod scheduler;
+pub mod trace;
@@ -1,67 +1,214 @@
 #![allow(dead_code)]
-mod emulator;
+mod cli;
+mod config;
+mod hotkeys;
+mod keymap;
 mod render;
+mod save_slots;
+mod screenshots;
+#[cfg(feature = "sound")]
+mod sound;
 
-use crate::emulator::Chip;
+use crate::cli::Args;
+use crate::config::Config;
+use crate::hotkeys::{Hotkey, FAST_FORWARD, NORMAL_SPEED, SPEEDS};
+use crate::keymap::KeyMap;
 use crate::render::SurfaceBuilder;
+use crate::save_slots::SaveSlots;
+use crate::screenshots::Screenshots;
+use chirp_8::assembler::assemble;
+use chirp_8::audio::{Beeper, Pattern, WavSink};
+use chirp_8::database::Database;
+use chirp_8::debugger::{GdbStub, Repl, Source};
+use chirp_8::emulator::{Chip, CosmacVipRandom, Timing};
+use chirp_8::movie::{Movie, MovieError, Player, Recorder};
+use chirp_8::rewind::Rewind;
+use chirp_8::rom::RomHash;
+use chirp_8::scheduler::{Scheduler, SystemClock};
+use chirp_8::trace::{Category, JsonLinesSink, StdoutSink};
+use clap::Parser;
 use glutin::{
-    event::{Event, WindowEvent},
+    event::{ElementState, Event, KeyboardInput, ModifiersState, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
 };
-use std::time::SystemTime;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 
-const FRAME_RATE: f64 = 60.0;
+/// The sample rate of the WAV files written by --capture-audio.
+const CAPTURE_SAMPLE_RATE: u32 = 44100;
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let event_loop = EventLoop::new();
+fn main() {
+    if let Err(err) = run(Args::parse()) {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
+    }
+}
+
+fn run(args: Args) -> Result<(), Box<dyn std::error::Error>> {
+    let rom = std::fs::read(&args.rom)
+        .map_err(|err| format!("failed to read the ROM at {}: {}", args.rom.display(), err))?;
+
+    let config = Config::load(args.config.as_deref())?;
+    let rom_hash = RomHash::of(&rom);
+    let game = Database::builtin().get(rom_hash);
+    match game {
+        Some(game) => println!("Running {}.", game.title),
+        None => println!("{} is not in the game database, using the default settings.", args.rom.display()),
+    }
+    let settings = config.settings(&args, rom_hash, game);
+    debug_assert!(settings.keymap.host_keys().all(|key| Hotkey::of(key).is_none()));
+
+    let mut chip8 = Chip::new(settings.quirks);
+    chip8
+        .load_rom(&rom)
+        .map_err(|err| format!("failed to load the ROM at {}: {}", args.rom.display(), err))?;
+    if settings.vip_timing {
+        chip8.set_timing(Timing::CosmacVip);
+    }
+    let display = chip8.display_mut();
+    display.set_scale(settings.scale);
+    let mut palette = display.palette();
+    palette[0] = settings.background;
+    palette[1] = settings.foreground;
+    display.set_palette(palette);
+    chip8.set_paused(args.paused);
+    let save_slots = SaveSlots::new(&args.rom);
+    let screenshots = Screenshots::new(&args.rom);
 
+    configure_random(&mut chip8, &args);
+    configure_tracing(&mut chip8, &args)?;
+    let source = args.source.as_deref().map(|path| load_source(path, &rom)).transpose()?;
+    let mut driver = Driver::from_args(&mut chip8, &args, source)?;
+    let mut movie = MovieMode::from_args(&mut chip8, &rom, &settings, &args)?;
+    let mut capture = AudioCapture::from_args(&settings, &args)?;
+
+    let event_loop = EventLoop::new();
     let mut surface = SurfaceBuilder::new()
         .with_title("CHIRP-8 Emulator")
-        .with_size(640, 320)
+        .with_size(64 * settings.scale, 32 * settings.scale)
         .build(&event_loop)?;
 
-    let mut chip8 = Chip::new();
-    // chip8.load_rom_from_path(std::path::Path::new("./roms/BC_test.ch8")).unwrap();
-    // chip8.load_rom_from_path(std::path::Path::new("./roms/test_opcode.ch8")).unwrap();
-    // chip8.load_rom_from_path(std::path::Path::new("./roms/IBM_Logo.ch8")).unwrap();
-    // chip8.load_rom_from_path(std::path::Path::new("./roms/Fishie_[Hap,_2005].ch8")).unwrap();
-    // chip8.load_rom_from_path(std::path::Path::new("./roms/SQRT_Test_[Sergey_Naydenov,_2010].ch8")).unwrap();
-    // chip8.load_rom_from_path(std::path::Path::new("./roms/Trip8_Demo_(2008)_[Revival_Studios].ch8")).unwrap();
-    // chip8.load_rom_from_path(std::path::Path::new("./roms/Tetris [Fran Dachille, 1991].ch8")).unwrap();
-    chip8.load_rom_from_path(std::path::Path::new("./roms/Pong (1 player).ch8")).unwrap();
-
-    // Test Suite from https://github.com/Timendus/chip8-test-suite.
-    // chip8.load_rom_from_path(std::path::Path::new("./roms/tests/1-chip8-logo.ch8")).unwrap();
-    // chip8.load_rom_from_path(std::path::Path::new("./roms/tests/2-ibm-logo.ch8")).unwrap();
-    // chip8.load_rom_from_path(std::path::Path::new("./roms/tests/3-corax+.ch8")).unwrap();
-    // chip8.load_rom_from_path(std::path::Path::new("./roms/tests/4-flags.ch8")).unwrap();
-    // chip8.load_rom_from_path(std::path::Path::new("./roms/tests/5-quirks.ch8")).unwrap();
-    // chip8.load_rom_from_path(std::path::Path::new("./roms/tests/6-keypad.ch8")).unwrap();
-    // chip8.load_rom_from_path(std::path::Path::new("./roms/tests/7-beep.ch8")).unwrap();
-
-    let mut last_cycle = SystemTime::now();
+    #[cfg(feature = "sound")]
+    let mut beeper = open_beeper(&settings);
 
-    event_loop.run(move |event, _, control_flow| {
-        *control_flow = ControlFlow::Poll;
+    let mut scheduler = Scheduler::new(SystemClock::new(), settings.cpu_hz);
+    let mut modifiers = ModifiersState::empty();
+    let mut rewind = Rewind::default();
+    let mut rewinding = false;
+    let mut fast_forwarding = false;
+    let mut advancing = false;
+    let mut speed = NORMAL_SPEED;
 
+    event_loop.run(move |event, _, control_flow| {
         match event {
-            Event::LoopDestroyed => return,
+            Event::LoopDestroyed => {
+                chip8.tracer_mut().flush();
+                movie.finish(&mut chip8);
+                if let Some(capture) = capture.take() {
+                    capture.finish();
+                }
+            }
             Event::WindowEvent { event, .. } => match event {
                 WindowEvent::Resized(size) => surface.resize(size.width, size.height),
                 WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
-                WindowEvent::KeyboardInput { input, .. } => chip8.process_input(input),
+                WindowEvent::ModifiersChanged(state) => modifiers = state,
+                WindowEvent::KeyboardInput { input, .. } => match input.virtual_keycode.and_then(Hotkey::of) {
+                    Some(hotkey) if hotkey.held() => {
+                        let pressed = input.state == ElementState::Pressed;
+                        match hotkey {
+                            Hotkey::Rewind => rewinding = pressed && movie.allows_time_travel(),
+                            _ => fast_forwarding = pressed,
+                        }
+                    }
+                    Some(hotkey) if input.state == ElementState::Pressed => match hotkey {
+                        Hotkey::SaveSlot(slot) => {
+                            if modifiers.shift() || movie.allows_time_travel() {
+                                use_save_slot(&mut chip8, &save_slots, slot, modifiers.shift())
+                            } else {
+                                println!("Save states can't be loaded while a movie is recording or playing.");
+                            }
+                        }
+                        Hotkey::Pause => chip8.set_paused(!chip8.paused()),
+                        Hotkey::FrameAdvance => advancing = chip8.paused(),
+                        Hotkey::Reset if movie.allows_time_travel() => {
+                            match chip8.reset(&rom) {
+                                Ok(()) => println!("Reset the program."),
+                                Err(err) => println!("Failed to reset the program: {}", err),
+                            }
+                        }
+                        Hotkey::Reset => println!("The program can't be reset while a movie is recording or playing."),
+                        Hotkey::ShowKeymap => print!("{}", settings.keymap),
+                        Hotkey::Screenshot => match screenshots.save(chip8.display()) {
+                            Ok(path) => println!("Saved a screenshot to {}.", path.display()),
+                            Err(err) => println!("Failed to save a screenshot: {}", err),
+                        },
+                        Hotkey::SpeedUp | Hotkey::SpeedDown => {
+                            speed = match hotkey {
+                                Hotkey::SpeedUp => (speed + 1).min(SPEEDS.len() - 1),
+                                _ => speed.saturating_sub(1),
+                            };
+                            println!("Running at {}% speed.", SPEEDS[speed] * 100.0);
+                        }
+                        Hotkey::Quit => *control_flow = ControlFlow::Exit,
+                        Hotkey::FastForward | Hotkey::Rewind => (),
+                    },
+                    Some(_) => (),
+                    None => process_input(&mut chip8, &mut movie, &settings.keymap, input),
+                },
                 _ => (),
             },
             Event::MainEventsCleared => {
-                if let Ok(elapsed) = last_cycle.elapsed() {
-                    if elapsed.as_secs_f64() > 1.0 / FRAME_RATE {
-                        chip8.cpu_cycle();
+                let mut speed = SPEEDS[speed];
+                if fast_forwarding {
+                    speed *= FAST_FORWARD;
+                }
+                scheduler.set_speed(speed);
+
+                let frames = scheduler.frames_due();
+                for _ in 0..frames {
+                    chip8.set_instructions_per_cycle(scheduler.next_frame_instructions());
+                    let emulated = !rewinding && (advancing || !chip8.paused());
+                    if rewinding {
+                        rewind.rewind_frame(&mut chip8);
+                    } else if advancing {
+                        // Only a paused program is advanced, and it is left
+                        // paused.
+                        advancing = false;
+                        chip8.set_paused(false);
+                        if run_frame(&mut chip8, &mut driver, &mut movie, &mut rewind) {
+                            *control_flow = ControlFlow::Exit;
+                        }
+                        chip8.set_paused(true);
+                    } else if run_frame(&mut chip8, &mut driver, &mut movie, &mut rewind) {
+                        *control_flow = ControlFlow::Exit;
+                    }
 
-                        last_cycle = SystemTime::now();
+                    #[cfg(feature = "sound")]
+                    if let Some(beeper) = beeper.as_mut() {
+                        beeper.set_pattern(Pattern::of(&chip8));
+                        if let Err(err) = beeper.frame(chip8.sound_active() && !chip8.paused()) {
+                            println!("Failed to play the beeper: {}", err);
+                        }
                     }
+                    // Only frames the chip ran are captured, so the audio
+                    // follows emulated time.
+                    if emulated {
+                        if let Some(Err(err)) = capture.as_mut().map(|capture| capture.frame(&chip8)) {
+                            println!("Failed to capture the audio, stopping: {}", err);
+                            capture = None;
+                        }
+                    }
+                }
+
+                if frames > 0 {
+                    surface.update_with_display(chip8.display());
+                    surface.request_redraw();
+                }
+                if *control_flow != ControlFlow::Exit {
+                    *control_flow = ControlFlow::WaitUntil(Instant::now() + scheduler.until_next_frame());
                 }
-                surface.update_with_display(chip8.display());
-                surface.request_redraw();
             }
             Event::RedrawRequested(_) => {
                 surface.update().unwrap();
@@ -71,3 +218,248 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     });
 }
+
+/// Runs a frame of the chip, returning whether the emulator should quit.
+fn run_frame(chip8: &mut Chip, driver: &mut Driver, movie: &mut MovieMode, rewind: &mut Rewind) -> bool {
+    movie.start_frame(chip8);
+    let mut quit = driver.run_cycle(chip8, rewind);
+    if let Err(err) = movie.end_frame(chip8) {
+        println!("{}", err);
+        quit = true;
+    }
+    rewind.record(chip8);
+    quit
+}
+
+/// Opens the audio device unless the sound is muted. The emulator runs
+/// silently if there is no device.
+#[cfg(feature = "sound")]
+fn open_beeper(settings: &config::Settings) -> Option<Beeper<sound::DeviceSink>> {
+    if settings.mute {
+        return None;
+    }
+    match sound::DeviceSink::open() {
+        Ok(sink) => Some(Beeper::new(settings.tone, sink)),
+        Err(err) => {
+            println!("Failed to open the audio device, the sound is off: {}", err);
+            None
+        }
+    }
+}
+
+/// Sets up the random number source from --vip-random and --seed.
+fn configure_random(chip8: &mut Chip, args: &Args) {
+    if args.vip_random {
+        chip8.set_random_source(Box::new(CosmacVipRandom::new()));
+    }
+    if let Some(seed) = args.seed {
+        chip8.set_seed(seed);
+    }
+}
+
+/// With --trace <categories>, executed instructions in the categories are
+/// printed. With --trace-json <path>, they are written to a JSON-lines file,
+/// every category being traced unless --trace is also given. With
+/// --trace-buffer <n>, the last n are kept in memory for the debugger.
+fn configure_tracing(chip8: &mut Chip, args: &Args) -> std::io::Result<()> {
+    let tracer = chip8.tracer_mut();
+    if let Some(categories) = args.trace {
+        tracer.set_categories(categories);
+        tracer.add_sink(Box::new(StdoutSink));
+    }
+    if let Some(path) = &args.trace_json {
+        if args.trace.is_none() {
+            tracer.set_categories(Category::all());
+        }
+        tracer.add_sink(Box::new(JsonLinesSink::create(path)?));
+    }
+    if let Some(capacity) = args.trace_buffer {
+        if args.trace.is_none() {
+            tracer.set_categories(Category::all());
+        }
+        tracer.set_ring_buffer(capacity);
+    }
+    Ok(())
+}
+
+/// Assembles the Octo source given with --source, which has to assemble to the
+/// ROM for its lines to match what is executed.
+fn load_source(path: &Path, rom: &[u8]) -> Result<Source, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|err| format!("failed to read the source at {}: {}", path.display(), err))?;
+    let program = assemble(&text)
+        .map_err(|err| format!("{}:{}:{}: {}", path.display(), err.line, err.column, err.message))?;
+    if program.rom != rom {
+        return Err(format!("{} doesn't assemble to the ROM", path.display()));
+    }
+    Ok(Source::new(&text, &program))
+}
+
+/// What controls the chip's execution.
+enum Driver {
+    Free,
+    /// With --debug, debugger commands are typed on stdin.
+    Repl(Repl),
+    /// With --gdb <port>, a GDB client connects to the port.
+    Gdb(GdbStub),
+}
+
+impl Driver {
+    fn from_args(chip8: &mut Chip, args: &Args, source: Option<Source>) -> std::io::Result<Self> {
+        if let Some(port) = args.gdb {
+            let stub = GdbStub::listen(("127.0.0.1", port), chip8)?;
+            println!("Waiting for a GDB client on {}.", stub.local_addr()?);
+            return Ok(Driver::Gdb(stub));
+        }
+        if args.debug {
+            return Ok(Driver::Repl(Repl::spawn_with_source(chip8, source)));
+        }
+        Ok(Driver::Free)
+    }
+
+    /// Runs a cycle of the chip, returning whether the emulator should quit.
+    fn run_cycle(&mut self, chip8: &mut Chip, rewind: &mut Rewind) -> bool {
+        match self {
+            Driver::Free => {
+                chip8.cpu_cycle();
+                false
+            }
+            Driver::Repl(repl) => {
+                repl.run_cycle(chip8, rewind);
+                repl.quit_requested()
+            }
+            Driver::Gdb(stub) => {
+                for event in stub.run_cycle(chip8) {
+                    println!("{}", event);
+                }
+                stub.quit_requested()
+            }
+        }
+    }
+}
+
+/// With --record <path>, the input is recorded into a movie that is written to
+/// the path on exit. With --play <path>, a movie's input is played back, and
+/// the keyboard is ignored until it ends. Neither can be used with a debugger,
+/// which clap enforces.
+enum MovieMode {
+    Off,
+    Recording(Recorder, PathBuf),
+    Playing(Player),
+}
+
+impl MovieMode {
+    fn from_args(chip8: &mut Chip, rom: &[u8], settings: &config::Settings, args: &Args) -> Result<Self, MovieError> {
+        if let Some(path) = &args.record {
+            let seed = args.seed.unwrap_or_else(rand::random);
+            return Ok(MovieMode::Recording(Recorder::new(chip8, rom, seed, settings.cpu_hz), path.clone()));
+        }
+        if let Some(path) = &args.play {
+            return Ok(MovieMode::Playing(Player::new(Movie::load(path)?, chip8, rom)?));
+        }
+        Ok(MovieMode::Off)
+    }
+
+    /// Rewinding and loading save states would make a movie impossible to
+    /// play back, so they are only allowed without one.
+    fn allows_time_travel(&self) -> bool {
+        matches!(self, MovieMode::Off)
+    }
+
+    fn set_key(&mut self, chip8: &mut Chip, key: u8, pressed: bool) {
+        match self {
+            MovieMode::Off if pressed => chip8.press_key(key),
+            MovieMode::Off => chip8.release_key(key),
+            MovieMode::Recording(recorder, _) => recorder.set_key(chip8, key, pressed),
+            MovieMode::Playing(_) => (),
+        }
+    }
+
+    fn start_frame(&mut self, chip8: &mut Chip) {
+        match self {
+            MovieMode::Recording(recorder, _) => recorder.start_frame(chip8),
+            MovieMode::Playing(player) => player.start_frame(chip8),
+            MovieMode::Off => (),
+        }
+    }
+
+    fn end_frame(&mut self, chip8: &mut Chip) -> Result<(), MovieError> {
+        match self {
+            MovieMode::Recording(recorder, _) => recorder.end_frame(chip8),
+            MovieMode::Playing(player) => {
+                player.end_frame(chip8)?;
+                if player.finished() {
+                    println!("The movie ended after {} frames.", player.frame());
+                    *self = MovieMode::Off;
+                }
+            }
+            MovieMode::Off => (),
+        }
+        Ok(())
+    }
+
+    /// Writes out the movie being recorded.
+    fn finish(&mut self, chip8: &mut Chip) {
+        if let MovieMode::Recording(recorder, path) = std::mem::replace(self, MovieMode::Off) {
+            let movie = recorder.finish(chip8);
+            match movie.save(&path) {
+                Ok(()) => println!("Recorded {} frames to {}.", movie.frames, path.display()),
+                Err(err) => println!("Failed to save the movie to {}: {}", path.display(), err),
+            }
+        }
+    }
+}
+
+/// With --capture-audio <path>, the beeper's tone, or the XO-CHIP pattern
+/// playing in its place, is written to a WAV file, whether or not the sound is
+/// muted or the `sound` feature is enabled.
+struct AudioCapture {
+    beeper: Beeper<WavSink<BufWriter<File>>>,
+    path: PathBuf,
+}
+
+impl AudioCapture {
+    fn from_args(settings: &config::Settings, args: &Args) -> std::io::Result<Option<Self>> {
+        match &args.capture_audio {
+            Some(path) => Ok(Some(Self {
+                beeper: Beeper::new(settings.tone, WavSink::create(path, CAPTURE_SAMPLE_RATE)?),
+                path: path.clone(),
+            })),
+            None => Ok(None),
+        }
+    }
+
+    fn frame(&mut self, chip8: &Chip) -> std::io::Result<()> {
+        self.beeper.set_pattern(Pattern::of(chip8));
+        self.beeper.frame(chip8.sound_active())
+    }
+
+    /// Fills in the WAV file's header.
+    fn finish(self) {
+        let seconds = self.beeper.sink().samples() as f64 / CAPTURE_SAMPLE_RATE as f64;
+        match self.beeper.into_sink().finish() {
+            Ok(_) => println!("Captured {:.1} seconds of audio to {}.", seconds, self.path.display()),
+            Err(err) => println!("Failed to write the audio to {}: {}", self.path.display(), err),
+        }
+    }
+}
+
+fn use_save_slot(chip8: &mut Chip, save_slots: &SaveSlots, slot: u8, save: bool) {
+    let result = if save {
+        save_slots.save(chip8, slot)
+    } else {
+        save_slots.load(chip8, slot)
+    };
+
+    match result {
+        Ok(()) if save => println!("Saved slot {}.", slot),
+        Ok(()) => println!("Loaded slot {}.", slot),
+        Err(err) => println!("Failed to use slot {}: {}", slot, err),
+    }
+}
+
+fn process_input(chip8: &mut Chip, movie: &mut MovieMode, keymap: &KeyMap, input: KeyboardInput) {
+    if let Some(key) = input.virtual_keycode.and_then(|keycode| keymap.key(keycode)) {
+        movie.set_key(chip8, key, input.state == ElementState::Pressed);
+    }
+}
@@ -0,0 +1,342 @@
+use crate::emulator::{Chip, IncrementI, Quirks, RandomKind, StateError, StateReader, StateWriter, Timing};
+use crate::rom::RomHash;
+use crate::scheduler::frame_instructions;
+use std::path::Path;
+use thiserror::Error;
+
+/// The bytes every movie starts with.
+pub const MOVIE_MAGIC: [u8; 4] = *b"C8MV";
+
+/// The version of the movie format written by `Movie::to_bytes`.
+pub const MOVIE_VERSION: u16 = 5;
+
+/// How many frames apart the state checksums of a recording are taken.
+pub const CHECKSUM_INTERVAL: u32 = 60;
+
+#[derive(Debug, Error)]
+pub enum MovieError {
+    #[error("not a movie")]
+    InvalidMagic,
+    #[error("movie version {found} is not supported, expected version {expected}")]
+    UnsupportedVersion { found: u16, expected: u16 },
+    #[error("the movie is corrupt: {0}")]
+    Corrupt(#[from] StateError),
+    #[error("the movie was recorded with ROM {expected}, but ROM {found} is loaded")]
+    RomMismatch { expected: RomHash, found: RomHash },
+    #[error("the movie was recorded with a random source that can't be recreated")]
+    CustomRandomSource,
+    #[error("playback went out of sync with the recording at frame {frame}")]
+    Desync { frame: u32 },
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// A key of the keypad being pressed or released before a frame ran.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InputEvent {
+    pub frame: u32,
+    pub key: u8,
+    pub pressed: bool,
+}
+
+/// A recording of the input given to a program from the moment it was loaded,
+/// with what is needed to play it back the same way: the ROM, the random
+/// number source and its seed, the quirks, the CPU rate and the timing.
+/// Checksums of the chip's state are taken as it is recorded, so playback
+/// notices when it stops matching.
+///
+/// Only frames the chip runs are counted, so pausing while recording leaves
+/// no trace in the movie, and input given while paused is played back before
+/// the frame that follows the pause.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Movie {
+    pub rom_hash: RomHash,
+    pub random: RandomKind,
+    pub seed: u64,
+    pub quirks: Quirks,
+    /// Instructions per second, spread over the frames as the scheduler
+    /// spreads them. It has no effect with COSMAC VIP timing.
+    pub cpu_hz: u32,
+    pub timing: Timing,
+    /// The number of frames recorded.
+    pub frames: u32,
+    /// Input in the order it was given.
+    pub events: Vec<InputEvent>,
+    /// Checksums of the chip's state after frames, by frame number.
+    pub checksums: Vec<(u32, u32)>,
+}
+
+/// Records a movie of a chip as it runs.
+pub struct Recorder {
+    movie: Movie,
+}
+
+/// Feeds a movie's input to a chip, checking that it stays in sync.
+pub struct Player {
+    movie: Movie,
+    frame: u32,
+    next_event: usize,
+    next_checksum: usize,
+}
+
+impl Movie {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, MovieError> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), MovieError> {
+        std::fs::write(path, self.to_bytes())?;
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = StateWriter::new();
+        for byte in &MOVIE_MAGIC {
+            writer.u8(*byte);
+        }
+        writer.u16(MOVIE_VERSION);
+        writer.bytes(&self.rom_hash.0);
+        writer.u8(self.random.code());
+        writer.u64(self.seed);
+        write_quirks(&mut writer, self.quirks);
+        writer.u32(self.cpu_hz);
+        writer.u8(match self.timing {
+            Timing::Instructions => 0,
+            Timing::CosmacVip => 1,
+        });
+        writer.u32(self.frames);
+        writer.u32(self.events.len() as u32);
+        for event in &self.events {
+            writer.u32(event.frame);
+            writer.u8(event.key);
+            writer.bool(event.pressed);
+        }
+        writer.u32(self.checksums.len() as u32);
+        for (frame, checksum) in &self.checksums {
+            writer.u32(*frame);
+            writer.u32(*checksum);
+        }
+        writer.into_bytes()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MovieError> {
+        let mut reader = StateReader::new(bytes);
+        let mut magic = [0; 4];
+        for byte in magic.iter_mut() {
+            *byte = reader.u8().map_err(|_| MovieError::InvalidMagic)?;
+        }
+        if magic != MOVIE_MAGIC {
+            return Err(MovieError::InvalidMagic);
+        }
+        let version = reader.u16()?;
+        if version != MOVIE_VERSION {
+            return Err(MovieError::UnsupportedVersion { found: version, expected: MOVIE_VERSION });
+        }
+
+        let mut rom_hash = [0; 20];
+        let hash = reader.bytes()?;
+        if hash.len() != rom_hash.len() {
+            return Err(StateError::Invalid("the ROM hash is not 20 bytes long").into());
+        }
+        rom_hash.copy_from_slice(hash);
+        let random = RandomKind::from_code(reader.u8()?).ok_or(StateError::Invalid("unknown random source"))?;
+        let seed = reader.u64()?;
+        let quirks = read_quirks(&mut reader)?;
+        let cpu_hz = reader.u32()?;
+        let timing = match reader.u8()? {
+            0 => Timing::Instructions,
+            1 => Timing::CosmacVip,
+            _ => return Err(StateError::Invalid("unknown timing").into()),
+        };
+        let frames = reader.u32()?;
+
+        let mut events = Vec::new();
+        for _ in 0..reader.u32()? {
+            let event = InputEvent { frame: reader.u32()?, key: reader.u8()?, pressed: reader.bool()? };
+            if event.key > 0xF {
+                return Err(StateError::Invalid("a key is not on the keypad").into());
+            }
+            events.push(event);
+        }
+        let mut checksums = Vec::new();
+        for _ in 0..reader.u32()? {
+            checksums.push((reader.u32()?, reader.u32()?));
+        }
+        reader.finish()?;
+
+        Ok(Self { rom_hash: RomHash(rom_hash), random, seed, quirks, cpu_hz, timing, frames, events, checksums })
+    }
+}
+
+impl Recorder {
+    /// Starts recording a chip that has just loaded `rom`, running at `cpu_hz`
+    /// instructions per second, and seeds its random number source with
+    /// `seed`.
+    pub fn new(chip: &mut Chip, rom: &[u8], seed: u64, cpu_hz: u32) -> Self {
+        chip.set_seed(seed);
+        Self {
+            movie: Movie {
+                rom_hash: RomHash::of(rom),
+                random: chip.random_kind(),
+                seed,
+                quirks: chip.quirks(),
+                cpu_hz,
+                timing: chip.timing(),
+                frames: 0,
+                events: Vec::new(),
+                checksums: Vec::new(),
+            },
+        }
+    }
+
+    /// Sets the number of instructions the coming frame runs. Call it before
+    /// each cycle.
+    pub fn start_frame(&mut self, chip: &mut Chip) {
+        chip.set_instructions_per_cycle(frame_instructions(self.movie.cpu_hz, self.movie.frames as u64));
+    }
+
+    /// Presses or releases a key of the chip, recording it for the next frame.
+    pub fn set_key(&mut self, chip: &mut Chip, key: u8, pressed: bool) {
+        if key > 0xF {
+            return;
+        }
+
+        if pressed {
+            chip.press_key(key);
+        } else {
+            chip.release_key(key);
+        }
+        self.movie.events.push(InputEvent { frame: self.movie.frames, key, pressed });
+    }
+
+    /// Counts a frame once the chip has finished a cycle, taking a checksum
+    /// every `CHECKSUM_INTERVAL` frames. Call it after each cycle.
+    pub fn end_frame(&mut self, chip: &mut Chip) {
+        if chip.cycle_position() != 0 || chip.paused() {
+            return;
+        }
+
+        self.movie.frames += 1;
+        if self.movie.frames.is_multiple_of(CHECKSUM_INTERVAL) {
+            self.movie.checksums.push((self.movie.frames, checksum(chip)));
+        }
+    }
+
+    /// Ends the recording, with a checksum of the last frame.
+    pub fn finish(mut self, chip: &mut Chip) -> Movie {
+        let frames = self.movie.frames;
+        if !frames.is_multiple_of(CHECKSUM_INTERVAL) {
+            self.movie.checksums.push((frames, checksum(chip)));
+        }
+        self.movie
+    }
+}
+
+impl Player {
+    /// Prepares a chip that has just loaded `rom` to play the movie back,
+    /// setting it up as it was recorded. Fails if the movie was recorded with
+    /// another ROM, or with a custom random source the chip doesn't have.
+    pub fn new(movie: Movie, chip: &mut Chip, rom: &[u8]) -> Result<Self, MovieError> {
+        let found = RomHash::of(rom);
+        if found != movie.rom_hash {
+            return Err(MovieError::RomMismatch { expected: movie.rom_hash, found });
+        }
+        if movie.random != chip.random_kind() {
+            chip.set_random_source(movie.random.source().ok_or(MovieError::CustomRandomSource)?);
+        }
+
+        chip.set_quirks(movie.quirks);
+        chip.set_timing(movie.timing);
+        chip.set_seed(movie.seed);
+        Ok(Self { movie, frame: 0, next_event: 0, next_checksum: 0 })
+    }
+
+    /// The number of frames played back so far.
+    pub fn frame(&self) -> u32 {
+        self.frame
+    }
+
+    pub fn finished(&self) -> bool {
+        self.frame >= self.movie.frames
+    }
+
+    /// Gives the chip the input recorded before the coming frame, and sets the
+    /// number of instructions it runs. Call it before each cycle.
+    pub fn start_frame(&mut self, chip: &mut Chip) {
+        chip.set_instructions_per_cycle(frame_instructions(self.movie.cpu_hz, self.frame as u64));
+        while let Some(event) = self.movie.events.get(self.next_event) {
+            if event.frame != self.frame {
+                break;
+            }
+
+            if event.pressed {
+                chip.press_key(event.key);
+            } else {
+                chip.release_key(event.key);
+            }
+            self.next_event += 1;
+        }
+    }
+
+    /// Counts a frame once the chip has finished a cycle, and checks it
+    /// against the recording's checksum if one was taken. Call it after each
+    /// cycle.
+    pub fn end_frame(&mut self, chip: &mut Chip) -> Result<(), MovieError> {
+        if chip.cycle_position() != 0 || chip.paused() || self.finished() {
+            return Ok(());
+        }
+
+        self.frame += 1;
+        match self.movie.checksums.get(self.next_checksum) {
+            Some(&(frame, expected)) if frame == self.frame => {
+                self.next_checksum += 1;
+                if checksum(chip) != expected {
+                    return Err(MovieError::Desync { frame });
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// A 32-bit FNV-1a hash of the chip's save state, as if it were running, since
+/// a recording can end while it is paused.
+fn checksum(chip: &mut Chip) -> u32 {
+    let paused = chip.paused();
+    chip.set_paused(false);
+    let state = chip.save_state();
+    chip.set_paused(paused);
+    state.iter().fold(0x811C_9DC5, |hash, byte| (hash ^ *byte as u32).wrapping_mul(0x0100_0193))
+}
+
+fn write_quirks(writer: &mut StateWriter, quirks: Quirks) {
+    writer.bool(quirks.shift_vx);
+    writer.u8(match quirks.load_store_increment_i {
+        IncrementI::None => 0,
+        IncrementI::X => 1,
+        IncrementI::XPlusOne => 2,
+    });
+    writer.bool(quirks.logic_resets_vf);
+    writer.bool(quirks.jump_vx);
+    writer.bool(quirks.display_wait);
+    writer.bool(quirks.wrap_sprites);
+    writer.bool(quirks.extended_memory);
+}
+
+fn read_quirks(reader: &mut StateReader) -> Result<Quirks, StateError> {
+    Ok(Quirks {
+        shift_vx: reader.bool()?,
+        load_store_increment_i: match reader.u8()? {
+            0 => IncrementI::None,
+            1 => IncrementI::X,
+            2 => IncrementI::XPlusOne,
+            _ => return Err(StateError::Invalid("unknown Fx55/Fx65 increment")),
+        },
+        logic_resets_vf: reader.bool()?,
+        jump_vx: reader.bool()?,
+        display_wait: reader.bool()?,
+        wrap_sprites: reader.bool()?,
+        extended_memory: reader.bool()?,
+    })
+}
@@ -35,7 +35,7 @@ impl Buffer {
         };
 
         buffer.bind();
-        gl.create_static_buffer_data(buffer.buffer_type, &data);
+        gl.create_static_buffer_data(buffer.buffer_type, data);
         buffer.unbind();
 
         buffer
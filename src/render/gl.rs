@@ -115,10 +115,10 @@ impl ShaderType {
 
 #[inline]
 fn convert_str_into_c_string(str: &str) -> Result<CString, GlError> {
-    CString::new(str.as_bytes()).map_err(|e| GlError::NulByteInStr(e))
+    CString::new(str.as_bytes()).map_err(GlError::NulByteInStr)
 }
 
-unsafe fn get_info_log<'a>(gl: &Gl, is_shader: bool, id: u32) -> Result<String, GlError> {
+unsafe fn get_info_log(gl: &Gl, is_shader: bool, id: u32) -> Result<String, GlError> {
     let mut len: i32 = 0;
     if is_shader {
         gl.gl.GetShaderiv(id, bindings::INFO_LOG_LENGTH, &mut len);
@@ -284,6 +284,12 @@ impl UploadableUniform for f32 {
     }
 }
 
+impl UploadableUniform for (i32, i32) {
+    fn upload(&self, gl: &Gl, uniform_location: &UniformLocationId) {
+        unsafe { gl.gl.Uniform2i(uniform_location.id, self.0, self.1) };
+    }
+}
+
 impl UploadableUniform for (f32, f32) {
     fn upload(&self, gl: &Gl, uniform_location: &UniformLocationId) {
         unsafe { gl.gl.Uniform2f(uniform_location.id, self.0, self.1) };
@@ -296,9 +302,16 @@ impl UploadableUniform for (f32, f32, f32) {
     }
 }
 
-impl UploadableUniform for [u32; 64] {
+impl UploadableUniform for [(f32, f32, f32)] {
+    fn upload(&self, gl: &Gl, uniform_location: &UniformLocationId) {
+        let flattened: Vec<f32> = self.iter().flat_map(|v| vec![v.0, v.1, v.2]).collect();
+        unsafe { gl.gl.Uniform3fv(uniform_location.id, self.len() as i32, flattened.as_ptr()) }
+    }
+}
+
+impl UploadableUniform for [u32] {
     fn upload(&self, gl: &Gl, uniform_location: &UniformLocationId) {
-        unsafe { gl.gl.Uniform1uiv(uniform_location.id, 64, self.as_ptr()) }
+        unsafe { gl.gl.Uniform1uiv(uniform_location.id, self.len() as i32, self.as_ptr()) }
     }
 }
 
@@ -413,7 +426,7 @@ impl Gl {
         unsafe {
             self.gl.BufferData(
                 buffer_type.value(),
-                (data.len() * std::mem::size_of::<T>()) as isize,
+                std::mem::size_of_val(data) as isize,
                 data.as_ptr() as *const bindings::types::GLvoid,
                 bindings::STATIC_DRAW,
             )
@@ -84,14 +84,14 @@ impl ShaderProgram {
     pub fn define_uniform<S: Into<String>>(&mut self, name: S) -> Result<(), ShaderError> {
         let name = name.into();
         let uniform_location = self.gl.get_uniform_location(&self.program_id, &name)?;
-        self.uniform_locations.insert(name.into(), uniform_location);
+        self.uniform_locations.insert(name, uniform_location);
         Ok(())
     }
 
     pub fn upload_uniform<S: Into<String>>(
         &mut self,
         name: S,
-        value: &impl gl::UploadableUniform,
+        value: &(impl gl::UploadableUniform + ?Sized),
     ) -> Result<(), ShaderError> {
         let name = name.into();
         let uniform_location = self
@@ -140,7 +140,7 @@ impl ProgramBuilder {
                 continue;
             }
 
-            let end_of_type_header = match shader.find(|s| s == ' ' || s == '\n') {
+            let end_of_type_header = match shader.find([' ', '\n']) {
                 Some(index) => index,
                 None => return Err(ShaderError::MissingTypeHeader),
             };
@@ -12,17 +12,27 @@ void main() {
 
 #type fragment
 #version 330 core
-uniform uint[64] uPixels;
+// Large enough for both planes of the 128x64 high resolution mode, one bit per
+// pixel. The second plane starts at PLANE_LEN.
+uniform uint[512] uPixels;
+uniform ivec2 uSize;
+// The colors of the background, first plane, second plane, and both planes.
+uniform vec3[4] uPalette;
 
 in vec2 vPixelPos;
 
 out vec4 fColor;
 
-int WIDTH = 64;
-int HEIGHT = 32;
+const int PLANE_LEN = 256;
 
 void main() {
-    float is_bright = (uPixels[int(vPixelPos.x * WIDTH)] << int((vPixelPos.y * HEIGHT))) >> HEIGHT - 1;
+    int x = min(int(vPixelPos.x * uSize.x), uSize.x - 1);
+    int y = min(int(vPixelPos.y * uSize.y), uSize.y - 1);
+    int index = y * uSize.x + x;
+    uint shift = uint(31 - index % 32);
 
-    fColor = vec4(is_bright, is_bright, is_bright, 1.0);
-}
\ No newline at end of file
+    uint first = (uPixels[index / 32] >> shift) & 1u;
+    uint second = (uPixels[PLANE_LEN + index / 32] >> shift) & 1u;
+
+    fColor = vec4(uPalette[int(first | (second << 1))], 1.0);
+}
@@ -1,3 +1,4 @@
+use chirp_8::emulator::{Display, MAX_HEIGHT, MAX_WIDTH, PLANES};
 use crate::render::gl;
 use crate::render::{Buffer, ProgramBuilder, ShaderError, ShaderProgram, VertexArray};
 use glutin::{
@@ -58,6 +59,8 @@ impl Surface {
         let mut shader = ProgramBuilder::new().with_combo(TEMP_SHADER)?.build(&gl)?;
         shader.bind();
         shader.define_uniform("uPixels")?;
+        shader.define_uniform("uSize")?;
+        shader.define_uniform("uPalette")?;
         shader.unbind();
 
         let vertices: [f32; 12] = [
@@ -114,10 +117,26 @@ impl Surface {
         Ok(())
     }
 
-    pub fn update_with_display(&mut self, display: &crate::emulator::Display) {
+    pub fn update_with_display(&mut self, display: &Display) {
         self.shader.bind();
 
-        self.shader.upload_uniform("uPixels", &display.pixels()).unwrap();
+        // The shader receives each plane packed into bits, most significant
+        // bit first, as uniform arrays are limited in size.
+        const PLANE_LEN: usize = MAX_WIDTH * MAX_HEIGHT / 32;
+        let mut packed = [0u32; PLANE_LEN * PLANES];
+        for (i, pixel) in display.pixels().iter().enumerate() {
+            for plane in 0..PLANES {
+                if pixel & (1 << plane) != 0 {
+                    packed[plane * PLANE_LEN + i / 32] |= 0x80000000 >> (i % 32);
+                }
+            }
+        }
+
+        let size = (display.width() as i32, display.height() as i32);
+
+        self.shader.upload_uniform("uPixels", &packed[..]).unwrap();
+        self.shader.upload_uniform("uSize", &size).unwrap();
+        self.shader.upload_uniform("uPalette", &display.palette()[..]).unwrap();
 
         self.shader.unbind();
     }
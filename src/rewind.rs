@@ -0,0 +1,249 @@
+use crate::emulator::Chip;
+use std::collections::VecDeque;
+
+/// The default amount of memory kept for history, which holds several minutes
+/// of most programs.
+pub const DEFAULT_BUDGET: usize = 32 * 1024 * 1024;
+
+/// Changed bytes closer together than this are stored as a single run, as a
+/// run's header is as big as this gap.
+const MERGE_GAP: usize = 6;
+
+/// A history of frames that execution can be rewound through. The state of
+/// the latest frame is kept in full, and every earlier frame as the bytes that
+/// differ from the frame after it, so a frame that only touched a few bytes of
+/// memory and the display costs a few bytes of history. The oldest frames are
+/// forgotten once the history outgrows its budget.
+pub struct Rewind {
+    latest: Option<Vec<u8>>,
+    /// Deltas that turn a frame's state into the previous frame's, oldest
+    /// first.
+    deltas: VecDeque<Vec<u8>>,
+    budget: usize,
+    used: usize,
+}
+
+impl Rewind {
+    /// Creates a history that uses at most about `budget` bytes.
+    pub fn new(budget: usize) -> Self {
+        Self {
+            latest: None,
+            deltas: VecDeque::new(),
+            budget,
+            used: 0,
+        }
+    }
+
+    /// The number of frames that can be rewound.
+    pub fn frames(&self) -> usize {
+        self.deltas.len()
+    }
+
+    /// The number of bytes the history takes up.
+    pub fn memory_used(&self) -> usize {
+        self.used
+    }
+
+    pub fn clear(&mut self) {
+        self.latest = None;
+        self.deltas.clear();
+        self.used = 0;
+    }
+
+    /// Records the chip's state, if it is at the end of a cycle and changed
+    /// since the last recorded frame. Call it after each cycle.
+    pub fn record(&mut self, chip: &Chip) {
+        if chip.cycle_position() != 0 {
+            return;
+        }
+
+        let state = chip.save_state();
+        match self.latest.take() {
+            Some(latest) if latest == state => self.latest = Some(latest),
+            Some(latest) => {
+                let delta = diff(&state, &latest);
+                self.used = self.used + delta.len() + state.len() - latest.len();
+                self.deltas.push_back(delta);
+                self.latest = Some(state);
+            }
+            None => {
+                self.used += state.len();
+                self.latest = Some(state);
+            }
+        }
+
+        while self.used > self.budget {
+            match self.deltas.pop_front() {
+                Some(delta) => self.used -= delta.len(),
+                None => break,
+            }
+        }
+    }
+
+    /// Restores the chip to the previous recorded frame, or to the latest one
+    /// if the chip has moved on from it. Returns false if there is no earlier
+    /// frame.
+    pub fn rewind_frame(&mut self, chip: &mut Chip) -> bool {
+        let latest = match &self.latest {
+            Some(latest) => latest,
+            None => return false,
+        };
+
+        if chip.cycle_position() != 0 || chip.save_state() != *latest {
+            return chip.load_state(latest).is_ok();
+        }
+        if !self.pop() {
+            return false;
+        }
+        self.restore_latest(chip)
+    }
+
+    /// Moves the chip back by one instruction, by restoring the start of the
+    /// frame and executing the instructions before the current one again. The
+    /// chip is left paused. Returns false if there is no history to go back
+    /// through.
+    pub fn step_back(&mut self, chip: &mut Chip) -> bool {
+        let at_latest = self.is_at_latest(chip);
+        let replay = match chip.cycle_position() {
+            _ if at_latest => {
+                if !self.pop() {
+                    return false;
+                }
+                None
+            }
+            0 => None,
+            position => Some(position),
+        };
+        if !self.restore_latest(chip) {
+            return false;
+        }
+
+        // The instructions were traced the first time they were executed.
+        let categories = chip.tracer().categories();
+        chip.tracer_mut().set_categories(Default::default());
+        chip.set_paused(false);
+        let replay = replay.unwrap_or_else(|| frame_length(chip));
+        for _ in 1..replay {
+            chip.step();
+        }
+        chip.set_paused(true);
+        chip.tracer_mut().set_categories(categories);
+        chip.memory_mut().take_watch_hit();
+        true
+    }
+
+    /// Whether the chip is at the start of the latest recorded frame. Stepping
+    /// back pauses the chip, which isn't a change the history records, so the
+    /// chip also counts as there if only its pause differs.
+    fn is_at_latest(&self, chip: &mut Chip) -> bool {
+        let latest = match &self.latest {
+            Some(latest) if chip.cycle_position() == 0 => latest,
+            _ => return false,
+        };
+        if chip.save_state() == *latest {
+            return true;
+        }
+
+        let paused = chip.paused();
+        chip.set_paused(!paused);
+        let at_latest = chip.save_state() == *latest;
+        chip.set_paused(paused);
+        at_latest
+    }
+
+    /// Drops the latest frame, making the one before it the latest.
+    fn pop(&mut self) -> bool {
+        let (latest, delta) = match (&self.latest, self.deltas.pop_back()) {
+            (Some(latest), Some(delta)) => (latest, delta),
+            _ => return false,
+        };
+
+        let previous = patch(latest, &delta);
+        self.used -= delta.len();
+        self.used = self.used + previous.len() - latest.len();
+        self.latest = Some(previous);
+        true
+    }
+
+    fn restore_latest(&self, chip: &mut Chip) -> bool {
+        match &self.latest {
+            Some(latest) => chip.load_state(latest).is_ok(),
+            None => false,
+        }
+    }
+}
+
+impl Default for Rewind {
+    fn default() -> Self {
+        Self::new(DEFAULT_BUDGET)
+    }
+}
+
+/// Encodes the bytes that differ between two states: the length of `to`, then
+/// runs of an offset, a length and the bytes of `to` to write there. Bytes past
+/// the end of `from` count as zero.
+pub fn diff(from: &[u8], to: &[u8]) -> Vec<u8> {
+    let mut delta = Vec::new();
+    delta.extend_from_slice(&(to.len() as u32).to_be_bytes());
+
+    let differs = |index: usize| from.get(index).copied().unwrap_or(0) != to[index];
+    let mut index = 0;
+    while index < to.len() {
+        if !differs(index) {
+            index += 1;
+            continue;
+        }
+
+        let start = index;
+        let mut end = index + 1;
+        let mut same = 0;
+        while end < to.len() && end - start < u16::MAX as usize && same < MERGE_GAP {
+            if differs(end) {
+                same = 0;
+            } else {
+                same += 1;
+            }
+            end += 1;
+        }
+        let end = end - same;
+
+        delta.extend_from_slice(&(start as u32).to_be_bytes());
+        delta.extend_from_slice(&((end - start) as u16).to_be_bytes());
+        delta.extend_from_slice(&to[start..end]);
+        index = end;
+    }
+    delta
+}
+
+/// Applies a delta made by `diff` to the state it was made from.
+pub fn patch(from: &[u8], delta: &[u8]) -> Vec<u8> {
+    let len = u32::from_be_bytes([delta[0], delta[1], delta[2], delta[3]]) as usize;
+    let mut to = from.to_vec();
+    to.resize(len, 0);
+
+    let mut index = 4;
+    while index < delta.len() {
+        let start = u32::from_be_bytes([delta[index], delta[index + 1], delta[index + 2], delta[index + 3]]) as usize;
+        let run = u16::from_be_bytes([delta[index + 4], delta[index + 5]]) as usize;
+        index += 6;
+        to[start..start + run].copy_from_slice(&delta[index..index + run]);
+        index += run;
+    }
+    to
+}
+
+/// The number of instructions in the frame starting at the chip's state, which
+/// varies with COSMAC VIP timing. The chip is left as it was.
+fn frame_length(chip: &mut Chip) -> usize {
+    let state = chip.save_state();
+    let mut length = 0;
+    loop {
+        chip.step();
+        length += 1;
+        if chip.cycle_position() == 0 || chip.paused() {
+            break;
+        }
+    }
+    chip.load_state(&state).unwrap();
+    length
+}
@@ -0,0 +1,46 @@
+use sha1_smol::Sha1;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("a ROM hash is 40 hexadecimal digits")]
+pub struct ParseRomHashError;
+
+/// The SHA-1 hash of a ROM's bytes, which identifies a program regardless of
+/// the name its file was given.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RomHash(pub [u8; 20]);
+
+impl RomHash {
+    pub fn of(rom: &[u8]) -> Self {
+        Self(Sha1::from(rom).digest().bytes())
+    }
+}
+
+/// Formats the hash as 40 lowercase hexadecimal digits.
+impl fmt::Display for RomHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in &self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for RomHash {
+    type Err = ParseRomHashError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != 40 || !s.is_ascii() {
+            return Err(ParseRomHashError);
+        }
+
+        let mut hash = [0; 20];
+        for (byte, digits) in hash.iter_mut().zip(s.as_bytes().chunks(2)) {
+            let digits = std::str::from_utf8(digits).map_err(|_| ParseRomHashError)?;
+            *byte = u8::from_str_radix(digits, 16).map_err(|_| ParseRomHashError)?;
+        }
+        Ok(Self(hash))
+    }
+}
@@ -0,0 +1,66 @@
+use chirp_8::emulator::{Chip, StateError};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SlotError {
+    #[error("slot {0} is empty")]
+    Empty(u8),
+    #[error("there is no data directory to keep save states in")]
+    NoDataDirectory,
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    State(#[from] StateError),
+}
+
+/// The name files about the ROM are given, which is its file name without the
+/// extension.
+pub fn rom_name(rom_path: &Path) -> String {
+    rom_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "rom".to_string())
+}
+
+/// Numbered save states of a ROM, kept on disk as
+/// `<data directory>/chirp-8/saves/<rom name>.<slot>.state`.
+pub struct SaveSlots {
+    rom_name: String,
+}
+
+impl SaveSlots {
+    pub fn new(rom_path: &Path) -> Self {
+        Self { rom_name: rom_name(rom_path) }
+    }
+
+    /// Where save states are kept, such as `~/.local/share/chirp-8/saves` on
+    /// Linux, if the platform has a data directory.
+    pub fn directory() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("chirp-8").join("saves"))
+    }
+
+    fn path(&self, slot: u8) -> Result<PathBuf, SlotError> {
+        let directory = Self::directory().ok_or(SlotError::NoDataDirectory)?;
+        Ok(directory.join(format!("{}.{}.state", self.rom_name, slot)))
+    }
+
+    pub fn save(&self, chip8: &Chip, slot: u8) -> Result<(), SlotError> {
+        let path = self.path(slot)?;
+        if let Some(directory) = path.parent() {
+            std::fs::create_dir_all(directory)?;
+        }
+        std::fs::write(path, chip8.save_state())?;
+        Ok(())
+    }
+
+    pub fn load(&self, chip8: &mut Chip, slot: u8) -> Result<(), SlotError> {
+        let state = match std::fs::read(self.path(slot)?) {
+            Ok(state) => state,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Err(SlotError::Empty(slot)),
+            Err(err) => return Err(err.into()),
+        };
+        chip8.load_state(&state)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,171 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// The rate the timers count down at, which is also the frame rate.
+pub const TIMER_HZ: u32 = 60;
+
+/// The most frames run to catch up at once. Time owed beyond that, such as
+/// while the window was minimised or the machine was suspended, is dropped
+/// rather than run in a burst.
+pub const MAX_CATCH_UP_FRAMES: u32 = 10;
+
+const NANOS_PER_SECOND: u128 = 1_000_000_000;
+
+/// A source of monotonic time.
+pub trait Clock {
+    /// The time elapsed since an arbitrary starting point. It never goes
+    /// backwards.
+    fn now(&self) -> Duration;
+}
+
+/// The system's monotonic clock.
+pub struct SystemClock {
+    start: Instant,
+}
+
+/// A clock that only moves when it is told to, for tests. Clones share the
+/// same time, so a test can keep one to advance the clock of a scheduler.
+#[derive(Clone, Debug, Default)]
+pub struct FakeClock {
+    now: Rc<Cell<Duration>>,
+}
+
+/// Decides when frames run, and how many instructions each frame runs. Time
+/// is accumulated as it passes, and a frame is due for every sixtieth of a
+/// second of it, so the timers count down at exactly 60 Hz however the host
+/// schedules the frontend. The CPU runs at its own rate, with the instructions
+/// of a second spread as evenly as possible over its 60 frames.
+pub struct Scheduler<C: Clock> {
+    clock: C,
+    cpu_hz: u32,
+    speed: f64,
+    last: Duration,
+    /// Emulated time owed, in billionths of a frame.
+    accumulator: u128,
+    /// The number of frames run, which places the next one within its second.
+    frames: u64,
+    dropped_frames: u64,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self { start: Instant::now() }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+impl FakeClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration);
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> Duration {
+        self.now.get()
+    }
+}
+
+impl<C: Clock> Scheduler<C> {
+    /// Creates a scheduler running the CPU at `cpu_hz` instructions per
+    /// second, which is at least `TIMER_HZ`. No frame is due until time
+    /// passes on the clock.
+    pub fn new(clock: C, cpu_hz: u32) -> Self {
+        let last = clock.now();
+        Self {
+            clock,
+            cpu_hz: cpu_hz.max(TIMER_HZ),
+            speed: 1.0,
+            last,
+            accumulator: 0,
+            frames: 0,
+            dropped_frames: 0,
+        }
+    }
+
+    pub fn cpu_hz(&self) -> u32 {
+        self.cpu_hz
+    }
+
+    /// Sets the number of instructions run per second, which is at least
+    /// `TIMER_HZ`, so every frame runs an instruction.
+    pub fn set_cpu_hz(&mut self, cpu_hz: u32) {
+        self.cpu_hz = cpu_hz.max(TIMER_HZ);
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    /// Sets how fast emulated time passes compared to the clock, 2.0 running
+    /// frames twice as often.
+    pub fn set_speed(&mut self, speed: f64) {
+        self.speed = speed.max(0.0);
+    }
+
+    /// Takes in the time that passed since the last call, and returns the
+    /// number of frames now due, which is at most `MAX_CATCH_UP_FRAMES`.
+    pub fn frames_due(&mut self) -> u32 {
+        let now = self.clock.now();
+        let elapsed = now.saturating_sub(self.last);
+        self.last = now;
+
+        let elapsed = (elapsed.as_nanos() as f64 * self.speed) as u128;
+        self.accumulator += elapsed * TIMER_HZ as u128;
+        let due = self.accumulator / NANOS_PER_SECOND;
+        self.accumulator %= NANOS_PER_SECOND;
+
+        if due > MAX_CATCH_UP_FRAMES as u128 {
+            self.dropped_frames += (due - MAX_CATCH_UP_FRAMES as u128) as u64;
+            return MAX_CATCH_UP_FRAMES;
+        }
+        due as u32
+    }
+
+    /// The number of instructions the next frame runs. Call it once before
+    /// each frame.
+    pub fn next_frame_instructions(&mut self) -> usize {
+        let instructions = frame_instructions(self.cpu_hz, self.frames);
+        self.frames += 1;
+        instructions
+    }
+
+    /// How long after the last call to `frames_due` the next frame is due, or
+    /// a second if emulated time is stopped.
+    pub fn until_next_frame(&self) -> Duration {
+        if self.speed <= 0.0 {
+            return Duration::from_secs(1);
+        }
+        let remaining = (NANOS_PER_SECOND - self.accumulator) as f64 / TIMER_HZ as f64 / self.speed;
+        Duration::from_nanos(remaining.ceil() as u64)
+    }
+
+    /// The number of frames skipped because they were too far behind.
+    pub fn dropped_frames(&self) -> u64 {
+        self.dropped_frames
+    }
+}
+
+/// The number of instructions frame number `frame` runs at `cpu_hz`, with the
+/// instructions of each second spread as evenly as possible over its frames.
+pub fn frame_instructions(cpu_hz: u32, frame: u64) -> usize {
+    let cpu_hz = cpu_hz.max(TIMER_HZ) as u64;
+    let frame = frame % TIMER_HZ as u64;
+    ((frame + 1) * cpu_hz / TIMER_HZ as u64 - frame * cpu_hz / TIMER_HZ as u64) as usize
+}
@@ -0,0 +1,83 @@
+use crate::save_slots::rom_name;
+use chirp_8::emulator::Display;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ScreenshotError {
+    #[error("there is no pictures or data directory to keep screenshots in")]
+    NoDirectory,
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Encoding(#[from] png::EncodingError),
+}
+
+/// Screenshots of a ROM, kept on disk as
+/// `<pictures directory>/chirp-8/<rom name>-<UTC date and time>.png`.
+pub struct Screenshots {
+    rom_name: String,
+}
+
+impl Screenshots {
+    pub fn new(rom_path: &Path) -> Self {
+        Self { rom_name: rom_name(rom_path) }
+    }
+
+    /// Where screenshots are kept, such as `~/Pictures/chirp-8` on Linux, or
+    /// in the data directory on platforms without a pictures directory.
+    pub fn directory() -> Option<PathBuf> {
+        dirs::picture_dir()
+            .map(|dir| dir.join("chirp-8"))
+            .or_else(|| dirs::data_dir().map(|dir| dir.join("chirp-8").join("screenshots")))
+    }
+
+    /// Saves the display as it looks in the window, returning the path of
+    /// the file.
+    pub fn save(&self, display: &Display) -> Result<PathBuf, ScreenshotError> {
+        // The window is sized for the low resolution, whatever the display's.
+        let scale = display.scale();
+        let image = display.to_image_sized(64 * scale, 32 * scale, display.palette());
+
+        let directory = Self::directory().ok_or(ScreenshotError::NoDirectory)?;
+        std::fs::create_dir_all(&directory)?;
+        let path = directory.join(format!("{}-{}.png", self.rom_name, timestamp()));
+        image.save_png(&path)?;
+        Ok(path)
+    }
+}
+
+/// The current UTC time, as `2024-01-31T23-59-59.999Z`, with dashes rather
+/// than colons so it can be part of a file name on every system.
+fn timestamp() -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = now.as_secs();
+    let (year, month, day) = civil_from_days((seconds / 86400) as i64);
+    let time = seconds % 86400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}-{:02}-{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60,
+        now.subsec_millis()
+    )
+}
+
+/// Converts days since 1970-01-01 into a year, month and day of the Gregorian
+/// calendar, with Howard Hinnant's algorithm.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
@@ -0,0 +1,76 @@
+use chirp_8::audio::AudioSink;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{Device, Sample, SampleFormat, Stream, StreamConfig};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// The most audio kept waiting for the device, in seconds. The emulator
+/// produces samples as it runs frames, so while it runs faster than the device
+/// plays, the oldest samples are dropped rather than falling further behind.
+const MAX_LATENCY: f32 = 0.1;
+
+/// Plays samples through the default output device with cpal.
+pub struct DeviceSink {
+    queue: Arc<Mutex<VecDeque<f32>>>,
+    sample_rate: u32,
+    _stream: Stream,
+}
+
+impl DeviceSink {
+    pub fn open() -> Result<Self, Box<dyn std::error::Error>> {
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or("there is no audio output device")?;
+        let supported = device.default_output_config()?;
+        let format = supported.sample_format();
+        let config: StreamConfig = supported.into();
+
+        let queue = Arc::new(Mutex::new(VecDeque::new()));
+        let stream = match format {
+            SampleFormat::F32 => build_stream::<f32>(&device, &config, queue.clone())?,
+            SampleFormat::I16 => build_stream::<i16>(&device, &config, queue.clone())?,
+            SampleFormat::U16 => build_stream::<u16>(&device, &config, queue.clone())?,
+        };
+        stream.play()?;
+
+        Ok(Self { queue, sample_rate: config.sample_rate.0, _stream: stream })
+    }
+}
+
+impl AudioSink for DeviceSink {
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn write(&mut self, samples: &[f32]) -> std::io::Result<()> {
+        let max = (self.sample_rate as f32 * MAX_LATENCY) as usize;
+        let mut queue = self.queue.lock().unwrap();
+        queue.extend(samples);
+        if queue.len() > max {
+            let excess = queue.len() - max;
+            queue.drain(..excess);
+        }
+        Ok(())
+    }
+}
+
+/// Opens a stream that plays the queued samples on every channel, and silence
+/// when the queue runs dry.
+fn build_stream<T: Sample>(
+    device: &Device,
+    config: &StreamConfig,
+    queue: Arc<Mutex<VecDeque<f32>>>,
+) -> Result<Stream, cpal::BuildStreamError> {
+    let channels = config.channels as usize;
+    device.build_output_stream(
+        config,
+        move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+            let mut queue = queue.lock().unwrap();
+            for frame in data.chunks_mut(channels) {
+                let sample = T::from(&queue.pop_front().unwrap_or(0.0));
+                frame.iter_mut().for_each(|out| *out = sample);
+            }
+        },
+        |err| println!("The audio device failed: {}", err),
+    )
+}
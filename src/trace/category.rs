@@ -0,0 +1,103 @@
+use crate::emulator::Instruction;
+use bitflags::bitflags;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+bitflags! {
+    /// The categories instructions are traced by.
+    #[derive(Default)]
+    pub struct Category: u8 {
+        /// Jumps, calls, returns, skips on registers and exiting.
+        const FLOW = 0b000001;
+        /// Clearing, drawing, scrolling and changing resolution or planes.
+        const DRAW = 0b000010;
+        /// Setting I, and loading from or storing to memory.
+        const MEMORY = 0b000100;
+        /// Skips on keys and waiting for a key.
+        const INPUT = 0b001000;
+        /// Reading and setting the timers and the pitch.
+        const TIMERS = 0b010000;
+        /// Loads, arithmetic and logic between registers.
+        const ALU = 0b100000;
+    }
+}
+
+const NAMES: [(Category, &str); 6] = [
+    (Category::FLOW, "flow"),
+    (Category::DRAW, "draw"),
+    (Category::MEMORY, "memory"),
+    (Category::INPUT, "input"),
+    (Category::TIMERS, "timers"),
+    (Category::ALU, "alu"),
+];
+
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("unknown trace category '{0}', expected flow, draw, memory, input, timers, alu or all")]
+pub struct UnknownCategory(pub String);
+
+impl Category {
+    /// The category of an instruction. Instructions that can't be decoded are
+    /// traced as flow.
+    pub fn of(instruction: Option<Instruction>) -> Category {
+        use Instruction::*;
+
+        let instruction = match instruction {
+            Some(instruction) => instruction,
+            None => return Category::FLOW,
+        };
+
+        match instruction {
+            RET | EXIT | JP_addr(_) | CALL_addr(_) | SE_Vx_byte(..) | SNE_Vx_byte(..) | SE_Vx_Vy(..)
+            | SNE_Vx_Vy(..) | JP_V0_addr(_) => Category::FLOW,
+            CLS | SCD_nibble(_) | SCU_nibble(_) | SCR | SCL | LOW | HIGH | DRW_Vx_Vy_n(..) | PLANE_n(_) => {
+                Category::DRAW
+            }
+            LD_I_addr(_) | LD_I_long | ADD_I_Vx(_) | LD_F_Vx(_) | LD_HF_Vx(_) | LD_B_Vx(_) | LD_I_Vx(_)
+            | LD_Vx_I(_) | LD_I_Vx_Vy(..) | LD_Vx_Vy_I(..) | AUDIO | LD_R_Vx(_) | LD_Vx_R(_) => Category::MEMORY,
+            SKP_Vx(_) | SKNP_Vx(_) | LD_Vx_K(_) => Category::INPUT,
+            LD_Vx_DT(_) | LD_DT_Vx(_) | LD_ST_Vx(_) | PITCH_Vx(_) => Category::TIMERS,
+            LD_Vx_byte(..) | ADD_Vx_byte(..) | LD_Vx_Vy(..) | OR_Vx_Vy(..) | AND_Vx_Vy(..) | XOR_Vx_Vy(..)
+            | ADD_Vx_Vy(..) | SUB_Vx_Vy(..) | SHR_Vx_Vy(..) | SUBN_Vx_Vy(..) | SHL_Vx_Vy(..)
+            | RND_Vx_byte(..) => Category::ALU,
+        }
+    }
+}
+
+/// Parses a comma separated list of category names, where `all` stands for
+/// every category and `off` for none.
+impl FromStr for Category {
+    type Err = UnknownCategory;
+
+    fn from_str(list: &str) -> Result<Self, Self::Err> {
+        let mut categories = Category::empty();
+        for name in list.split(',').map(str::trim) {
+            categories |= match name {
+                "all" => Category::all(),
+                "off" => Category::empty(),
+                _ => NAMES
+                    .iter()
+                    .find(|(_, known)| *known == name)
+                    .map(|(category, _)| *category)
+                    .ok_or_else(|| UnknownCategory(name.to_string()))?,
+            };
+        }
+        Ok(categories)
+    }
+}
+
+/// Writes the names of the categories, separated by commas.
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = NAMES
+            .iter()
+            .filter(|(category, _)| self.contains(*category))
+            .map(|(_, name)| *name)
+            .collect();
+        if names.is_empty() {
+            write!(f, "off")
+        } else {
+            write!(f, "{}", names.join(","))
+        }
+    }
+}
@@ -0,0 +1,15 @@
+//! Structured tracing of executed instructions. Tracing is off by default and
+//! is enabled per category of instruction; every traced instruction becomes a
+//! `TraceRecord`, which is given to each of the tracer's sinks.
+
+mod category;
+pub use category::*;
+
+mod record;
+pub use record::*;
+
+mod sink;
+pub use sink::*;
+
+mod tracer;
+pub use tracer::*;
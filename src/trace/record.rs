@@ -0,0 +1,140 @@
+use crate::emulator::{Instruction, Register, Registers};
+use crate::trace::Category;
+use std::convert::TryInto;
+
+/// The size of a record encoded by `TraceRecord::to_bytes`.
+pub const RECORD_SIZE: usize = 42;
+
+/// Something that happened while an instruction was executed, other than its
+/// usual effect.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Note {
+    /// The opcode isn't a valid instruction, and was skipped.
+    InvalidInstruction,
+    /// The instruction jumped to its own address, which pauses the chip.
+    SelfJump,
+    /// The sprite will be drawn at the start of the next cycle, because of the
+    /// display wait quirk.
+    DisplayWait,
+}
+
+/// The registers an instruction can change.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RegisterSnapshot {
+    pub v: [u8; 16],
+    pub i: u16,
+}
+
+/// A register changed by an instruction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RegisterDelta {
+    pub register: Register,
+    pub before: u16,
+    pub after: u16,
+}
+
+/// An executed instruction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TraceRecord {
+    /// The address the instruction was executed from.
+    pub pc: u16,
+    pub opcode: u16,
+    /// The decoded instruction, or None if the opcode isn't valid.
+    pub instruction: Option<Instruction>,
+    pub category: Category,
+    pub note: Option<Note>,
+    pub before: RegisterSnapshot,
+    pub after: RegisterSnapshot,
+}
+
+impl Note {
+    pub fn description(self) -> &'static str {
+        match self {
+            Note::InvalidInstruction => "not a valid instruction",
+            Note::SelfJump => "jumped to its own address, pausing",
+            Note::DisplayWait => "waiting for the start of the cycle to draw",
+        }
+    }
+
+    fn code(note: Option<Note>) -> u8 {
+        match note {
+            None => 0,
+            Some(Note::InvalidInstruction) => 1,
+            Some(Note::SelfJump) => 2,
+            Some(Note::DisplayWait) => 3,
+        }
+    }
+
+    fn from_code(code: u8) -> Option<Note> {
+        match code {
+            1 => Some(Note::InvalidInstruction),
+            2 => Some(Note::SelfJump),
+            3 => Some(Note::DisplayWait),
+            _ => None,
+        }
+    }
+}
+
+impl RegisterSnapshot {
+    pub fn of(registers: &Registers) -> Self {
+        let mut v = [0; 16];
+        for (x, value) in v.iter_mut().enumerate() {
+            *value = registers.v(x as u8);
+        }
+        Self { v, i: registers.i() }
+    }
+
+    fn write(&self, bytes: &mut [u8]) {
+        bytes[..16].copy_from_slice(&self.v);
+        bytes[16..18].copy_from_slice(&self.i.to_be_bytes());
+    }
+
+    fn read(bytes: &[u8]) -> Self {
+        Self {
+            v: bytes[..16].try_into().unwrap(),
+            i: u16::from_be_bytes([bytes[16], bytes[17]]),
+        }
+    }
+}
+
+impl TraceRecord {
+    /// The registers the instruction changed, V0 to VF and then I.
+    pub fn deltas(&self) -> impl Iterator<Item = RegisterDelta> + '_ {
+        let v = (0..16u8).map(move |x| RegisterDelta {
+            register: Register::V(x),
+            before: self.before.v[x as usize] as u16,
+            after: self.after.v[x as usize] as u16,
+        });
+        let i = std::iter::once(RegisterDelta { register: Register::I, before: self.before.i, after: self.after.i });
+
+        v.chain(i).filter(|delta| delta.before != delta.after)
+    }
+
+    /// Encodes the record into a fixed number of bytes: the PC and opcode, the
+    /// category and note, then the registers before and after, with every
+    /// 16-bit value big-endian.
+    pub fn to_bytes(&self) -> [u8; RECORD_SIZE] {
+        let mut bytes = [0; RECORD_SIZE];
+        bytes[0..2].copy_from_slice(&self.pc.to_be_bytes());
+        bytes[2..4].copy_from_slice(&self.opcode.to_be_bytes());
+        bytes[4] = self.category.bits();
+        bytes[5] = Note::code(self.note);
+        self.before.write(&mut bytes[6..24]);
+        self.after.write(&mut bytes[24..42]);
+        bytes
+    }
+
+    /// Decodes a record encoded by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8; RECORD_SIZE]) -> Self {
+        let opcode = u16::from_be_bytes([bytes[2], bytes[3]]);
+        Self {
+            pc: u16::from_be_bytes([bytes[0], bytes[1]]),
+            opcode,
+            instruction: Instruction::decode(opcode).ok(),
+            category: Category::from_bits_truncate(bytes[4]),
+            note: Note::from_code(bytes[5]),
+            before: RegisterSnapshot::read(&bytes[6..24]),
+            after: RegisterSnapshot::read(&bytes[24..42]),
+        }
+    }
+}
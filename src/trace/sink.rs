@@ -0,0 +1,177 @@
+use crate::emulator::Register;
+use crate::trace::{Note, TraceRecord, RECORD_SIZE};
+use serde::Serialize;
+use std::convert::TryInto;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Where trace records go.
+pub trait TraceSink {
+    fn record(&mut self, record: &TraceRecord);
+
+    /// Writes out anything buffered.
+    fn flush(&mut self) {}
+}
+
+/// Prints a line per record, such as `0x204: ADD  V0, 0x01      V0 02->03`.
+pub struct StdoutSink;
+
+/// Keeps the last records in a fixed amount of memory, encoded with
+/// `TraceRecord::to_bytes`.
+pub struct RingBuffer {
+    bytes: Vec<u8>,
+    capacity: usize,
+    /// The index of the slot the next record is written to.
+    next: usize,
+    len: usize,
+}
+
+/// Writes a JSON object per record and line, such as
+/// `{"pc":516,"opcode":28673,"instruction":"ADD  V0, 0x01","category":"alu","deltas":[{"register":"V0","before":2,"after":3}]}`.
+pub struct JsonLinesSink<W: Write> {
+    writer: W,
+}
+
+impl TraceSink for StdoutSink {
+    fn record(&mut self, record: &TraceRecord) {
+        let instruction = match record.instruction {
+            Some(instruction) => instruction.to_string(),
+            None => format!("{:#06x}", record.opcode),
+        };
+
+        let mut line = format!("{:<#05x}: {:<20}", record.pc, instruction);
+        for delta in record.deltas() {
+            match delta.register {
+                Register::V(_) => line += &format!(" {} {:02X}->{:02X}", delta.register, delta.before, delta.after),
+                Register::I => line += &format!(" {} {:03X}->{:03X}", delta.register, delta.before, delta.after),
+            }
+        }
+        if let Some(note) = record.note {
+            line += &format!(" # {}", note.description());
+        }
+        println!("{}", line.trim_end());
+    }
+}
+
+impl RingBuffer {
+    /// Creates a buffer that keeps the last `capacity` records.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            bytes: vec![0; capacity * RECORD_SIZE],
+            capacity,
+            next: 0,
+            len: 0,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.next = 0;
+        self.len = 0;
+    }
+
+    /// The encoded records, oldest first.
+    fn slots(&self) -> impl Iterator<Item = &[u8]> + '_ {
+        let first = (self.next + self.capacity - self.len) % self.capacity.max(1);
+        (0..self.len).map(move |offset| {
+            let start = (first + offset) % self.capacity * RECORD_SIZE;
+            &self.bytes[start..start + RECORD_SIZE]
+        })
+    }
+
+    /// The records, oldest first.
+    pub fn records(&self) -> impl Iterator<Item = TraceRecord> + '_ {
+        self.slots().map(|slot| TraceRecord::from_bytes(slot.try_into().unwrap()))
+    }
+
+    /// Writes the encoded records, oldest first, so they can be read back with
+    /// `TraceRecord::from_bytes` for offline analysis.
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        for slot in self.slots() {
+            writer.write_all(slot)?;
+        }
+        Ok(())
+    }
+}
+
+impl TraceSink for RingBuffer {
+    fn record(&mut self, record: &TraceRecord) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let start = self.next * RECORD_SIZE;
+        self.bytes[start..start + RECORD_SIZE].copy_from_slice(&record.to_bytes());
+        self.next = (self.next + 1) % self.capacity;
+        self.len = (self.len + 1).min(self.capacity);
+    }
+}
+
+impl JsonLinesSink<BufWriter<File>> {
+    /// Creates the file, replacing it if it exists.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W: Write> JsonLinesSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+}
+
+/// A record as `JsonLinesSink` writes it.
+#[derive(Serialize)]
+struct JsonRecord {
+    pc: u16,
+    opcode: u16,
+    instruction: Option<String>,
+    category: String,
+    deltas: Vec<JsonDelta>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    note: Option<&'static str>,
+}
+
+#[derive(Serialize)]
+struct JsonDelta {
+    register: String,
+    before: u16,
+    after: u16,
+}
+
+impl<W: Write> TraceSink for JsonLinesSink<W> {
+    fn record(&mut self, record: &TraceRecord) {
+        let json = JsonRecord {
+            pc: record.pc,
+            opcode: record.opcode,
+            instruction: record.instruction.map(|instruction| instruction.to_string()),
+            category: record.category.to_string(),
+            deltas: record
+                .deltas()
+                .map(|delta| JsonDelta { register: delta.register.to_string(), before: delta.before, after: delta.after })
+                .collect(),
+            note: record.note.map(Note::description),
+        };
+
+        // A trace that can't be written isn't worth stopping emulation for.
+        if serde_json::to_writer(&mut self.writer, &json).is_ok() {
+            let _ = self.writer.write_all(b"\n");
+        }
+    }
+
+    fn flush(&mut self) {
+        let _ = self.writer.flush();
+    }
+}
@@ -0,0 +1,75 @@
+use crate::trace::{Category, RingBuffer, TraceRecord, TraceSink};
+
+/// Sends the records of instructions in the enabled categories to its sinks.
+/// Nothing is enabled by default, which costs the chip a single check per
+/// instruction.
+#[derive(Default)]
+pub struct Tracer {
+    categories: Category,
+    sinks: Vec<Box<dyn TraceSink>>,
+    /// Kept apart from the other sinks, so its records can be read back.
+    ring_buffer: Option<RingBuffer>,
+}
+
+impl Tracer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn categories(&self) -> Category {
+        self.categories
+    }
+
+    pub fn set_categories(&mut self, categories: Category) {
+        self.categories = categories;
+    }
+
+    /// Whether instructions in any of the categories are traced, and there is
+    /// somewhere for them to go.
+    pub fn is_enabled(&self, category: Category) -> bool {
+        self.categories.intersects(category) && (!self.sinks.is_empty() || self.ring_buffer.is_some())
+    }
+
+    pub fn add_sink(&mut self, sink: Box<dyn TraceSink>) {
+        self.sinks.push(sink);
+    }
+
+    /// Flushes and removes every sink, other than the ring buffer.
+    pub fn clear_sinks(&mut self) {
+        self.flush();
+        self.sinks.clear();
+    }
+
+    pub fn has_sinks(&self) -> bool {
+        !self.sinks.is_empty()
+    }
+
+    /// Keeps the last `capacity` records in a ring buffer, replacing any
+    /// existing one.
+    pub fn set_ring_buffer(&mut self, capacity: usize) {
+        self.ring_buffer = Some(RingBuffer::new(capacity));
+    }
+
+    pub fn ring_buffer(&self) -> Option<&RingBuffer> {
+        self.ring_buffer.as_ref()
+    }
+
+    pub fn record(&mut self, record: &TraceRecord) {
+        if !self.categories.intersects(record.category) {
+            return;
+        }
+
+        if let Some(ring_buffer) = &mut self.ring_buffer {
+            ring_buffer.record(record);
+        }
+        for sink in &mut self.sinks {
+            sink.record(record);
+        }
+    }
+
+    pub fn flush(&mut self) {
+        for sink in &mut self.sinks {
+            sink.flush();
+        }
+    }
+}
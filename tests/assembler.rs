@@ -0,0 +1,122 @@
+//! Assembles Octo programs and runs them.
+
+use chirp_8::assembler::{assemble, AssembleError};
+use chirp_8::emulator::{Chip, Quirks};
+
+const COUNTER: &str = "\
+# Counts to 5 and stores the count.
+: main
+  v0 := 0
+  loop
+    v0 += 1
+    if v0 == 5 then jump done
+  again
+: done
+  i := result
+  save v0
+  loop again
+: result 0
+";
+
+fn error(source: &str) -> AssembleError {
+    assemble(source).expect_err("the program assembled")
+}
+
+#[test]
+fn assembles_a_program_that_runs() {
+    let program = assemble(COUNTER).unwrap();
+    let result = program.labels["result"];
+
+    let mut chip = Chip::new(Quirks::default());
+    chip.load_rom(&program.rom).unwrap();
+    for _ in 0..10 {
+        chip.cpu_cycle();
+    }
+    assert!(chip.paused(), "the program didn't reach its final loop");
+    assert_eq!(chip.registers().v(0), 5);
+    assert_eq!(chip.memory().peek(result), 5);
+}
+
+#[test]
+fn jumps_to_main_unless_the_program_starts_with_it() {
+    let program = assemble("v0 := 1\n: main\nv1 := 2").unwrap();
+    assert_eq!(program.rom, [0x12, 0x04, 0x60, 0x01, 0x61, 0x02]);
+    assert_eq!(program.labels["main"], 0x204);
+}
+
+#[test]
+fn maps_addresses_to_lines() {
+    let program = assemble(COUNTER).unwrap();
+    let map = &program.source_map;
+
+    assert_eq!(map.line(0x200), Some(3));
+    assert_eq!(map.line(0x201), Some(3));
+    assert_eq!(map.line(0x202), Some(5));
+    assert_eq!(map.address(6), Some(0x204));
+    assert_eq!(map.line(program.labels["result"]), Some(12));
+    assert_eq!(map.address(1), None);
+}
+
+#[test]
+fn reports_lines_and_columns() {
+    assert_eq!(
+        error(": main\n  jump nowhere"),
+        AssembleError { line: 2, column: 8, message: "undefined label 'nowhere'".to_string() }
+    );
+
+    let err = error(": main\nv0 := 300");
+    assert_eq!((err.line, err.column), (2, 7));
+    assert_eq!(err.message, "300 doesn't fit in -128..=255");
+
+    let err = error(": main\n  loop\n    v0 += 1\n");
+    assert_eq!((err.line, err.column, err.message.as_str()), (2, 3, "loop is missing its again"));
+
+    let err = error("v0 := 1");
+    assert_eq!(err.message, "this program is missing a main label");
+}
+
+#[test]
+fn rejects_jumps_past_0xfff() {
+    let err = error(": main\n:org 0x1000\n  loop\n  again");
+    assert_eq!((err.line, err.column), (3, 3));
+    assert_eq!(err.message, "0x1000 is past 0xFFF, where jumps can't reach");
+
+    let err = error(": main\n:org 0xffe\nif v0 == 0 begin\n  v0 := 1\nend");
+    assert_eq!((err.line, err.column), (5, 1));
+    assert_eq!(err.message, "0x1004 is past 0xFFF, where jumps can't reach");
+
+    let err = error(": main\n  jump far\n:org 0x1000\n: far");
+    assert_eq!((err.line, err.column), (2, 8));
+}
+
+/// Asserts that assembling the source fails on the directive at the line and
+/// column.
+fn assert_unsupported(source: &str, directive: &str, line: usize, column: usize) {
+    let message = format!("the Octo directive '{}' is not supported", directive);
+    assert_eq!(error(source), AssembleError { line, column, message });
+}
+
+#[test]
+fn rejects_call() {
+    assert_unsupported(": main\n  :call 0x300", ":call", 2, 3);
+}
+
+#[test]
+fn rejects_calls() {
+    assert_unsupported(": main\n: table\n:calls main", ":calls", 3, 1);
+}
+
+#[test]
+fn rejects_unpack() {
+    assert_unsupported(": main\n  v0 := 1 :unpack 0xA sprites", ":unpack", 2, 11);
+}
+
+#[test]
+fn rejects_next() {
+    assert_unsupported(": main\n  :next target v0 := 1", ":next", 2, 3);
+}
+
+#[test]
+fn rejects_stringmode() {
+    assert_unsupported(": main\n\n:stringmode text \"abc\" { :byte VALUE }", ":stringmode", 3, 1);
+}
@@ -0,0 +1,181 @@
+//! Generates the beeper's tone into headless sinks, and checks the sound of
+//! programs by capturing it to WAV data. The captures of the beep test and of
+//! an XO-CHIP pattern are compared with `tests/golden/7-beep.wav` and
+//! `tests/golden/xo-pattern.wav`; run with `UPDATE_GOLDEN=1` to write them
+//! again, after listening to them.
+
+use chirp_8::audio::{AudioSink, Beeper, NullSink, Pattern, Synth, Tone, WavSink, Waveform};
+use chirp_8::emulator::{Chip, Quirks};
+use std::io::Cursor;
+use std::path::Path;
+
+/// Runs a program for a number of frames, holding a key down between two of
+/// them, and returns the WAV data of its beeper.
+fn capture(rom: &[u8], tone: Tone, sample_rate: u32, frames: u32, key: Option<(u8, u32, u32)>) -> Vec<u8> {
+    let mut chip = Chip::new(Quirks::COSMAC_VIP);
+    chip.load_rom(rom).unwrap();
+    chip.set_seed(1);
+    let mut beeper = Beeper::new(tone, WavSink::new(Cursor::new(Vec::new()), sample_rate).unwrap());
+
+    for frame in 0..frames {
+        if let Some((key, pressed, released)) = key {
+            if frame == pressed {
+                chip.press_key(key);
+            } else if frame == released {
+                chip.release_key(key);
+            }
+        }
+        chip.cpu_cycle();
+        beeper.set_pattern(Pattern::of(&chip));
+        beeper.frame(chip.sound_active()).unwrap();
+    }
+    beeper.into_sink().finish().unwrap().into_inner()
+}
+
+fn samples(wav: &[u8]) -> Vec<i16> {
+    wav[44..].chunks(2).map(|sample| i16::from_le_bytes([sample[0], sample[1]])).collect()
+}
+
+/// Shows the frames of samples, at 6000 Hz, with sound as `#` and the silent
+/// ones as `.`.
+fn frames(samples: &[i16]) -> String {
+    samples
+        .chunks(100)
+        .map(|frame| if frame.iter().any(|&sample| sample != 0) { '#' } else { '.' })
+        .collect()
+}
+
+#[test]
+fn a_second_of_frames_is_a_second_of_samples() {
+    let mut beeper = Beeper::new(Tone::default(), NullSink::new(44100));
+    beeper.frame(true).unwrap();
+    assert_eq!(beeper.sink().samples(), 735);
+
+    for _ in 1..60 {
+        beeper.frame(true).unwrap();
+    }
+    assert_eq!(beeper.sink().samples(), 44100);
+}
+
+#[test]
+fn carries_fractions_of_a_sample_over() {
+    let mut beeper = Beeper::new(Tone::default(), NullSink::new(1000));
+    let mut sizes = Vec::new();
+    for _ in 0..3 {
+        let before = beeper.sink().samples();
+        beeper.frame(false).unwrap();
+        sizes.push(beeper.sink().samples() - before);
+    }
+    assert_eq!(sizes, [16, 17, 17]);
+}
+
+#[test]
+fn fades_in_and_out() {
+    let tone = Tone { waveform: Waveform::Square, volume: 1.0, attack: 0.01, release: 0.01, ..Tone::default() };
+    let mut synth = Synth::new(tone, 1000);
+
+    let mut samples = [0.0; 20];
+    synth.fill(&mut samples, true);
+    assert!(samples[0].abs() <= 0.1);
+    assert!(samples.windows(2).take(9).all(|pair| pair[1].abs() > pair[0].abs()));
+    assert_eq!(samples[19].abs(), 1.0);
+
+    synth.fill(&mut samples, false);
+    assert!(samples[0].abs() < 1.0);
+    assert!(samples[10..].iter().all(|&sample| sample == 0.0));
+}
+
+#[test]
+fn waveforms_peak_at_a_quarter_cycle() {
+    for waveform in [Waveform::Square, Waveform::Sine, Waveform::Triangle] {
+        assert!((waveform.sample(0.25) - 1.0).abs() < 1e-6, "{:?}", waveform);
+        assert!((waveform.sample(0.75) + 1.0).abs() < 1e-6, "{:?}", waveform);
+    }
+    assert_eq!("Sine".parse(), Ok(Waveform::Sine));
+    assert!("sawtooth".parse::<Waveform>().is_err());
+}
+
+#[test]
+fn writes_a_wav_file() {
+    let mut sink = WavSink::new(Cursor::new(Vec::new()), 8000).unwrap();
+    sink.write(&[0.0, 1.0, -1.0]).unwrap();
+    let wav = sink.finish().unwrap().into_inner();
+
+    assert_eq!(wav.len(), 44 + 6);
+    assert_eq!(&wav[0..4], b"RIFF");
+    assert_eq!(u32_at(&wav, 4), 36 + 6);
+    assert_eq!(&wav[8..16], b"WAVEfmt ");
+    assert_eq!(u32_at(&wav, 24), 8000);
+    assert_eq!(&wav[36..40], b"data");
+    assert_eq!(u32_at(&wav, 40), 6);
+    assert_eq!(wav[44..], [0, 0, 0xff, 0x7f, 0x01, 0x80]);
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]])
+}
+
+/// The sound timer is counted down at the end of every frame, including the
+/// one that set it, so the tone lasts a frame less than the timer's value.
+#[test]
+fn ld_st_vx_sounds_for_the_timer() {
+    // LD V0, 30; LD ST, V0; loop: LD V1, 0; JP loop
+    let rom = [0x60, 30, 0xf0, 0x18, 0x61, 0x00, 0x12, 0x04];
+    let tone = Tone { attack: 0.0, release: 0.0, ..Tone::default() };
+    let samples = samples(&capture(&rom, tone, 6000, 60, None));
+
+    assert_eq!(samples.len(), 60 * 100);
+    let sounding = samples.iter().take_while(|&&sample| sample != 0).count();
+    assert_eq!(sounding, 29 * 100);
+    assert!(samples[sounding..].iter().all(|&sample| sample == 0));
+}
+
+/// The beep test sounds the buzzer while B is held.
+#[test]
+fn beep() {
+    let rom = std::fs::read("roms/tests/7-beep.ch8").expect("the test ROM is missing");
+    let actual = capture(&rom, Tone::default(), 6000, 180, Some((0xB, 60, 120)));
+
+    let path = Path::new("tests/golden/7-beep.wav");
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(path, &actual).unwrap();
+        return;
+    }
+
+    let expected = std::fs::read(path)
+        .unwrap_or_else(|_| panic!("{} is missing, run with UPDATE_GOLDEN=1 to create it", path.display()));
+    let (expected, actual) = (samples(&expected), samples(&actual));
+    assert!(
+        expected == actual,
+        "the beeper doesn't match {}, frames with sound:\nexpected {}\nactual   {}",
+        path.display(),
+        frames(&expected),
+        frames(&actual)
+    );
+}
+
+/// An XO-CHIP pattern of eight set bits and eight clear ones, played at 8000
+/// bits per second, is a 500 Hz square wave in place of the tone.
+#[test]
+fn xo_pattern() {
+    // LD I, 210; AUDIO; LD V0, 112; PITCH V0; LD V0, 30; LD ST, V0; loop: LD V1, 0; JP loop
+    let mut rom = vec![0xa2, 0x10, 0xf0, 0x02, 0x60, 112, 0xf0, 0x3a, 0x60, 30, 0xf0, 0x18, 0x61, 0x00, 0x12, 0x0c];
+    rom.extend([0xff, 0x00].repeat(8));
+    let tone = Tone { frequency: 1000.0, attack: 0.0, release: 0.0, ..Tone::default() };
+    let actual = capture(&rom, tone, 16000, 40, None);
+
+    let sounding: Vec<i16> = samples(&actual).into_iter().take_while(|&sample| sample != 0).collect();
+    assert_eq!(sounding.len(), 29 * 16000 / 60);
+    assert!(sounding.chunks_exact(32).all(|period| period[..16].iter().all(|&sample| sample > 0)
+        && period[16..].iter().all(|&sample| sample < 0)));
+
+    let path = Path::new("tests/golden/xo-pattern.wav");
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(path, &actual).unwrap();
+        return;
+    }
+
+    let expected = std::fs::read(path)
+        .unwrap_or_else(|_| panic!("{} is missing, run with UPDATE_GOLDEN=1 to create it", path.display()));
+    assert!(samples(&expected) == samples(&actual), "the pattern doesn't match {}", path.display());
+}
@@ -0,0 +1,48 @@
+//! Checks the embedded game database against the ROMs shipped in `roms/`.
+
+use chirp_8::database::Database;
+use chirp_8::emulator::{Chip, Quirks};
+use chirp_8::rom::RomHash;
+use std::path::Path;
+
+fn roms(directory: &Path) -> Vec<std::path::PathBuf> {
+    let mut roms = Vec::new();
+    for entry in std::fs::read_dir(directory).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            roms.extend(self::roms(&path));
+        } else if path.extension() == Some("ch8".as_ref()) {
+            roms.push(path);
+        }
+    }
+    roms
+}
+
+#[test]
+fn every_shipped_rom_is_known() {
+    for path in roms(Path::new("roms")) {
+        let hash = RomHash::of(&std::fs::read(&path).unwrap());
+        assert!(Database::builtin().get(hash).is_some(), "{} ({}) is not in the database", path.display(), hash);
+    }
+}
+
+#[test]
+fn loading_a_known_rom_applies_its_quirks() {
+    let mut chip = Chip::new(Quirks::COSMAC_VIP);
+    let game = chip.load_rom_from_path(Path::new("roms/Tetris [Fran Dachille, 1991].ch8")).unwrap();
+    assert_eq!(game.map(|game| game.title.as_str()), Some("Tetris"));
+    assert_eq!(chip.quirks(), Quirks::CHIP_48);
+}
+
+#[test]
+fn loading_an_unknown_rom_keeps_the_settings() {
+    let path = std::env::temp_dir().join(format!("chirp-8-unknown-{}.ch8", std::process::id()));
+    std::fs::write(&path, [0x12, 0x00, 0xFF]).unwrap();
+    let mut chip = Chip::new(Quirks::SUPER_CHIP);
+    let game = chip.load_rom_from_path(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(game.unwrap(), None);
+    assert_eq!(chip.quirks(), Quirks::SUPER_CHIP);
+    assert_eq!(chip.memory().peek(0x202), 0xFF);
+}
@@ -0,0 +1,139 @@
+//! Parses debugger commands and runs a program under the debugger.
+
+use chirp_8::assembler::assemble;
+use chirp_8::debugger::{Breakpoint, Command, CommandError, Comparison, Condition, Debugger, Source, Stop};
+use chirp_8::emulator::{Access, Chip, Quirks, Register, WatchHit};
+
+/// LD V0, 0; loop: ADD V0, 1; CALL sub; JP loop; sub: LD I, 0x300;
+/// LD [I], V0; RET
+const PROGRAM: [u8; 16] = [
+    0x60, 0x00, 0x70, 0x01, 0x22, 0x0A, 0x12, 0x02, 0x00, 0x00, 0xA3, 0x00, 0xF0, 0x55, 0x00, 0xEE,
+];
+
+fn chip() -> Chip {
+    let mut chip = Chip::new(Quirks::default());
+    chip.load_rom(&PROGRAM).unwrap();
+    chip
+}
+
+/// Runs cycles until the debugger stops the chip.
+fn run(debugger: &mut Debugger, chip: &mut Chip) -> Stop {
+    for _ in 0..100 {
+        if let Some(stop) = debugger.run_cycle(chip) {
+            return stop;
+        }
+    }
+    panic!("the debugger didn't stop");
+}
+
+#[test]
+fn parses_commands() {
+    let condition = Condition { register: Register::V(3), comparison: Comparison::LessOrEqual, value: 5 };
+    assert_eq!(
+        Command::parse("break 0x2a0 if v3 <= 5"),
+        Ok(Command::Break(Breakpoint { address: 0x2A0, condition: Some(condition) }))
+    );
+    assert_eq!(Command::parse("b 512"), Ok(Command::Break(Breakpoint { address: 0x200, condition: None })));
+    assert_eq!(Command::parse("watch 0x300 rw"), Ok(Command::Watch(0x300, Access::READ | Access::WRITE)));
+    assert_eq!(Command::parse("watch 0x300"), Ok(Command::Watch(0x300, Access::WRITE)));
+    assert_eq!(Command::parse("  step  "), Ok(Command::Step(1)));
+    assert_eq!(Command::parse("s 10"), Ok(Command::Step(10)));
+    assert_eq!(Command::parse("mem 0x200"), Ok(Command::Memory(0x200, 16)));
+    assert_eq!(Command::parse("x 0x200 4"), Ok(Command::Memory(0x200, 4)));
+    assert_eq!(Command::parse("rstep"), Ok(Command::ReverseStep));
+    assert_eq!(Command::parse("history"), Ok(Command::History(None)));
+    assert_eq!(Command::parse("history 8"), Ok(Command::History(Some(8))));
+}
+
+#[test]
+fn reports_command_errors() {
+    assert_eq!(Command::parse(""), Err(CommandError::Missing("a command")));
+    assert_eq!(Command::parse("jump"), Err(CommandError::Unknown("jump".to_string())));
+    assert_eq!(Command::parse("break"), Err(CommandError::Missing("an address")));
+    assert_eq!(Command::parse("break 0xZZ"), Err(CommandError::InvalidNumber("0xZZ".to_string())));
+    assert_eq!(Command::parse("break 0x200 if vg == 1"), Err(CommandError::InvalidRegister("vg".to_string())));
+    assert_eq!(Command::parse("break 0x200 if v0 = 1"), Err(CommandError::InvalidComparison("=".to_string())));
+    assert_eq!(Command::parse("break 0x200 when"), Err(CommandError::Unexpected("when".to_string())));
+    assert_eq!(Command::parse("continue now"), Err(CommandError::Unexpected("now".to_string())));
+    assert!(matches!(Command::parse("trace sound"), Err(CommandError::UnknownCategory(_))));
+}
+
+#[test]
+fn stops_at_a_conditional_breakpoint() {
+    let mut chip = chip();
+    let mut debugger = Debugger::new();
+    let condition = Condition { register: Register::V(0), comparison: Comparison::Equal, value: 3 };
+    let breakpoint = Breakpoint { address: 0x202, condition: Some(condition) };
+    debugger.add_breakpoint(breakpoint);
+
+    assert_eq!(run(&mut debugger, &mut chip), Stop::Breakpoint(breakpoint));
+    assert!(chip.paused());
+    assert_eq!(chip.registers().pc(), 0x202);
+    assert_eq!(chip.registers().v(0), 3);
+
+    // Resuming doesn't stop at the same breakpoint again straight away.
+    debugger.remove_breakpoint(0x202);
+    debugger.add_breakpoint(Breakpoint { address: 0x202, condition: None });
+    debugger.resume(&mut chip);
+    run(&mut debugger, &mut chip);
+    assert_eq!(chip.registers().v(0), 4);
+}
+
+#[test]
+fn steps_over_and_out_of_calls() {
+    let mut chip = chip();
+    let mut debugger = Debugger::new();
+
+    debugger.step(&mut chip, 2);
+    assert_eq!(run(&mut debugger, &mut chip), Stop::Step);
+    assert_eq!(chip.registers().pc(), 0x204);
+
+    debugger.step_over(&mut chip);
+    assert_eq!(run(&mut debugger, &mut chip), Stop::Step);
+    assert_eq!(chip.registers().pc(), 0x206);
+    assert!(chip.registers().stack().is_empty());
+
+    debugger.step(&mut chip, 3);
+    assert_eq!(run(&mut debugger, &mut chip), Stop::Step);
+    assert_eq!(chip.registers().pc(), 0x20A);
+    assert!(debugger.step_out(&mut chip));
+    assert_eq!(run(&mut debugger, &mut chip), Stop::Step);
+    assert_eq!(chip.registers().pc(), 0x206);
+    assert!(!debugger.step_out(&mut chip));
+}
+
+#[test]
+fn stops_at_a_watchpoint() {
+    let mut chip = chip();
+    let mut debugger = Debugger::new();
+    chip.memory_mut().add_watchpoint(0x300, Access::WRITE);
+
+    let hit = WatchHit { address: 0x300, access: Access::WRITE };
+    assert_eq!(run(&mut debugger, &mut chip), Stop::Watchpoint(hit));
+    assert_eq!(chip.registers().pc(), 0x20E);
+    assert_eq!(chip.memory().peek(0x300), 1);
+}
+
+#[test]
+fn stops_at_breakpoint_directives_and_shows_their_source() {
+    let text = ": main\n  v0 := 1\n:breakpoint added\n  v0 += 2\n  loop again\n";
+    let program = assemble(text).unwrap();
+    let source = Source::new(text, &program);
+
+    let breakpoints: Vec<_> = source.breakpoints().collect();
+    assert_eq!(breakpoints, [(Breakpoint { address: 0x202, condition: None }, "added")]);
+    assert_eq!(source.breakpoint_name(0x202), Some("added"));
+    assert_eq!(source.breakpoint_name(0x200), None);
+    assert_eq!(source.line(0x200), Some((2, "v0 := 1")));
+    assert_eq!(source.line(0x203), Some((4, "v0 += 2")));
+
+    let mut chip = Chip::new(Quirks::default());
+    chip.load_rom(&program.rom).unwrap();
+    let mut debugger = Debugger::new();
+    for (breakpoint, _) in source.breakpoints() {
+        debugger.add_breakpoint(breakpoint);
+    }
+    assert_eq!(run(&mut debugger, &mut chip), Stop::Breakpoint(breakpoints[0].0));
+    assert_eq!(chip.registers().pc(), 0x202);
+    assert_eq!(chip.registers().v(0), 1);
+}
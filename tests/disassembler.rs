@@ -0,0 +1,57 @@
+//! Disassembles small programs, checking which bytes are found to be code.
+
+use chirp_8::disassembler::{ByteKind, Disassembly};
+
+/// LD I, sprite; DRW V0, V1, 5; CALL sub; loop: JP loop; sub: RET; sprite: the
+/// digit 0; a byte nothing uses.
+const PROGRAM: [u8; 16] = [
+    0xA2, 0x0A, 0xD0, 0x15, 0x22, 0x08, 0x12, 0x06, 0x00, 0xEE, 0xF0, 0x90, 0x90, 0x90, 0xF0, 0xFF,
+];
+
+#[test]
+fn follows_control_flow() {
+    let disassembly = Disassembly::new(&PROGRAM);
+
+    for address in [0x200, 0x202, 0x204, 0x206, 0x208] {
+        assert_eq!(disassembly.kind(address), Some(ByteKind::Code), "{:#05x}", address);
+        assert_eq!(disassembly.kind(address + 1), Some(ByteKind::Operand), "{:#05x}", address + 1);
+    }
+    for address in 0x20A..0x20F {
+        assert_eq!(disassembly.kind(address), Some(ByteKind::Sprite), "{:#05x}", address);
+    }
+    assert_eq!(disassembly.kind(0x20F), Some(ByteKind::Unknown));
+    assert_eq!(disassembly.kind(0x210), None);
+}
+
+#[test]
+fn labels_targets() {
+    let disassembly = Disassembly::new(&PROGRAM);
+
+    assert_eq!(disassembly.label(0x200).as_deref(), Some("label_200"));
+    assert_eq!(disassembly.label(0x206).as_deref(), Some("label_206"));
+    assert_eq!(disassembly.label(0x208).as_deref(), Some("sub_208"));
+    assert_eq!(disassembly.label(0x20A).as_deref(), Some("data_20A"));
+    assert_eq!(disassembly.label(0x202), None);
+
+    let listing = disassembly.to_string();
+    assert!(listing.contains("CALL sub_208"), "{}", listing);
+    assert!(listing.contains("JP   label_206"), "{}", listing);
+    assert!(listing.contains("LD   I, data_20A"), "{}", listing);
+    assert!(listing.contains("DB   0x90  ; #..#...."), "{}", listing);
+    assert!(listing.contains("0x20f: FF           DB   0xff\n"), "{}", listing);
+}
+
+/// A skip over `F000 nnnn` skips all four of its bytes.
+#[test]
+fn skips_over_long_loads() {
+    // SE V0, 0; LD I, long 0x208; EXIT; EXIT
+    let disassembly = Disassembly::new(&[0x30, 0x00, 0xF0, 0x00, 0x02, 0x08, 0x00, 0xFD, 0x00, 0xFD]);
+
+    assert_eq!(disassembly.kind(0x202), Some(ByteKind::Code));
+    for address in 0x203..0x206 {
+        assert_eq!(disassembly.kind(address), Some(ByteKind::Operand), "{:#05x}", address);
+    }
+    assert_eq!(disassembly.kind(0x206), Some(ByteKind::Code));
+    assert_eq!(disassembly.kind(0x208), Some(ByteKind::Unknown));
+    assert!(disassembly.to_string().contains("LD   I, long data_208"));
+}
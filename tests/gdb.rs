@@ -0,0 +1,207 @@
+//! Drives the GDB stub through a socket, as a client would.
+
+use chirp_8::debugger::{GdbEvent, GdbStub};
+use chirp_8::emulator::{Chip, Quirks};
+use std::io::{ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+/// LD V0, 5; LD V1, 7; loop: ADD V0, 1; JP loop
+const PROGRAM: [u8; 8] = [0x60, 0x05, 0x61, 0x07, 0x70, 0x01, 0x12, 0x04];
+
+/// A client connected to a stub, running the stub's side in the same thread.
+struct Session {
+    chip: Chip,
+    stub: GdbStub,
+    client: TcpStream,
+    input: Vec<u8>,
+}
+
+impl Session {
+    fn connect() -> Self {
+        let mut chip = Chip::new(Quirks::default());
+        chip.load_rom(&PROGRAM).unwrap();
+        let stub = GdbStub::listen("127.0.0.1:0", &mut chip).unwrap();
+        let client = TcpStream::connect(stub.local_addr().unwrap()).unwrap();
+        client.set_nonblocking(true).unwrap();
+        Self { chip, stub, client, input: Vec::new() }
+    }
+
+    fn send_raw(&mut self, bytes: &[u8]) {
+        self.client.write_all(bytes).unwrap();
+    }
+
+    fn send(&mut self, data: &str) {
+        let checksum = data.bytes().fold(0u8, |sum, byte| sum.wrapping_add(byte));
+        self.send_raw(format!("${}#{:02x}", data, checksum).as_bytes());
+    }
+
+    /// Runs the stub until it has written `len` bytes, and takes them.
+    fn receive(&mut self, len: usize) -> Vec<u8> {
+        for _ in 0..1000 {
+            if self.input.len() >= len {
+                return self.input.drain(..len).collect();
+            }
+            self.stub.run_cycle(&mut self.chip);
+            let mut buffer = [0; 1024];
+            match self.client.read(&mut buffer) {
+                Ok(read) => self.input.extend_from_slice(&buffer[..read]),
+                Err(err) if err.kind() == ErrorKind::WouldBlock => std::thread::sleep(Duration::from_millis(1)),
+                Err(err) => panic!("{}", err),
+            }
+        }
+        panic!("the stub didn't reply, received {:?}", String::from_utf8_lossy(&self.input));
+    }
+
+    /// Runs the stub until it sends a packet, checks its checksum, and
+    /// returns its data.
+    fn reply(&mut self) -> String {
+        assert_eq!(self.receive(1), b"$");
+        let mut data = Vec::new();
+        loop {
+            match self.receive(1)[0] {
+                b'#' => break,
+                byte => data.push(byte),
+            }
+        }
+        let checksum = String::from_utf8(self.receive(2)).unwrap();
+        let expected = data.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+        assert_eq!(u8::from_str_radix(&checksum, 16), Ok(expected), "bad checksum on {:?}", data);
+        String::from_utf8(data).unwrap()
+    }
+
+    /// Sends a packet, expects it to be acknowledged, and returns the reply.
+    fn exchange(&mut self, data: &str) -> String {
+        self.send(data);
+        assert_eq!(self.receive(1), b"+", "{} wasn't acknowledged", data);
+        self.reply()
+    }
+}
+
+#[test]
+fn acknowledges_packets_by_checksum() {
+    let mut session = Session::connect();
+
+    session.send_raw(b"$?#00");
+    assert_eq!(session.receive(1), b"-");
+    assert_eq!(session.exchange("?"), "S05");
+    assert_eq!(session.exchange("vMustReplyEmpty"), "");
+}
+
+#[test]
+fn reads_and_writes_registers() {
+    let mut session = Session::connect();
+
+    let registers = session.exchange("g");
+    // V0-VF, I, PC, the stack depth and the timers.
+    assert_eq!(registers, format!("{}{}{}{}", "00".repeat(16), "0000", "0200", "000000"));
+    assert_eq!(session.exchange("p11"), "0200");
+
+    assert_eq!(session.exchange("P3=2a"), "OK");
+    assert_eq!(session.exchange("P10=0345"), "OK");
+    assert_eq!(session.chip.registers().v(3), 0x2A);
+    assert_eq!(session.chip.registers().i(), 0x345);
+    assert_eq!(session.exchange("p10"), "0345");
+    assert_eq!(session.exchange("p15"), "E01");
+}
+
+#[test]
+fn reads_and_writes_memory() {
+    let mut session = Session::connect();
+
+    assert_eq!(session.exchange("m200,4"), "60056107");
+    assert_eq!(session.exchange("M300,3:abcdef"), "OK");
+    assert_eq!(session.exchange("m300,3"), "abcdef");
+    assert_eq!(session.chip.memory().peek(0x301), 0xCD);
+    assert_eq!(session.exchange("M300,2:ab"), "E01");
+}
+
+#[test]
+fn stops_at_breakpoints_and_steps() {
+    let mut session = Session::connect();
+
+    assert_eq!(session.exchange("Z0,204,2"), "OK");
+    session.send("c");
+    assert_eq!(session.receive(1), b"+");
+    assert_eq!(session.reply(), "S05");
+    assert_eq!(session.exchange("p11"), "0204");
+    assert_eq!(session.exchange("p0"), "05");
+    assert_eq!(session.exchange("p1"), "07");
+
+    session.send("s");
+    assert_eq!(session.receive(1), b"+");
+    assert_eq!(session.reply(), "S05");
+    assert_eq!(session.exchange("p11"), "0206");
+    assert_eq!(session.exchange("p0"), "06");
+}
+
+#[test]
+fn interrupts_a_running_program() {
+    let mut session = Session::connect();
+
+    session.send("c");
+    assert_eq!(session.receive(1), b"+");
+    for _ in 0..10 {
+        session.stub.run_cycle(&mut session.chip);
+    }
+    assert!(!session.chip.paused());
+
+    session.send_raw(b"\x03");
+    assert_eq!(session.reply(), "S02");
+    assert!(session.chip.paused());
+    assert!(session.chip.registers().v(0) > 5);
+}
+
+#[test]
+fn survives_malformed_packets() {
+    let mut session = Session::connect();
+
+    // Addresses wrap around the address space rather than overflowing.
+    assert_eq!(session.exchange("Mffffffffffffffff,2:abcd"), "OK");
+    assert_eq!(session.exchange("mffffffffffffffff,2"), "abcd");
+    assert_eq!(session.chip.memory().peek(0xFFFF), 0xAB);
+    assert_eq!(session.chip.memory().peek(0x0000), 0xCD);
+    assert_eq!(session.exchange("m1ffffffffffffffff,2"), "E01");
+    assert_eq!(session.exchange("mzz,2"), "E01");
+    assert_eq!(session.exchange("M300,2"), "E01");
+
+    // A watchpoint can't cover more than the address space.
+    assert_eq!(session.exchange("Z2,200,ffffffffffffffff"), "E01");
+    assert_eq!(session.exchange("Z2,200,x"), "E01");
+    assert_eq!(session.exchange("Z2,zz,1"), "E01");
+    assert_eq!(session.chip.memory().watchpoints().count(), 0);
+
+    assert_eq!(session.exchange("qXfer:features:read:target.xml:0,ffffffffffffffff").chars().next(), Some('l'));
+    assert_eq!(session.exchange("qXfer:features:read:target.xml:ffffffffffffffff,10"), "l");
+    assert_eq!(session.exchange("qXfer:features:read:target.xml:0"), "E01");
+}
+
+#[test]
+fn reports_connections() {
+    let mut chip = Chip::new(Quirks::default());
+    let mut stub = GdbStub::listen("127.0.0.1:0", &mut chip).unwrap();
+    assert!(stub.run_cycle(&mut chip).is_empty());
+
+    let client = TcpStream::connect(stub.local_addr().unwrap()).unwrap();
+    let mut events = Vec::new();
+    for _ in 0..1000 {
+        events.extend(stub.run_cycle(&mut chip));
+        if !events.is_empty() {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    assert!(matches!(events[..], [GdbEvent::Connected(address)] if address == client.local_addr().unwrap()));
+
+    drop(client);
+    let mut events = Vec::new();
+    for _ in 0..1000 {
+        events.extend(stub.run_cycle(&mut chip));
+        if !events.is_empty() {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    assert!(matches!(events[..], [GdbEvent::Disconnected(_)]));
+    assert!(!chip.paused());
+}
@@ -0,0 +1,80 @@
+//! Drives the emulator core the way a frontend would, with nothing but the
+//! library: keys go in as indices, and the display and sound are read back.
+
+use chirp_8::emulator::{Chip, Quirks};
+
+/// Waits for a key, sounds the buzzer for as many frames as the key's value,
+/// and draws the key's digit at (0, 0). It then keeps running, as a jump to
+/// itself would pause the chip.
+const PROGRAM: [u8; 12] = [
+    0xf0, 0x0a, // LD V0, K
+    0xf0, 0x18, // LD ST, V0
+    0xf0, 0x29, // LD F, V0
+    0xd1, 0x15, // DRW V1, V1, 5
+    0x62, 0x00, // LD V2, 0
+    0x12, 0x08, // JP 208
+];
+
+fn chip() -> Chip {
+    let mut chip = Chip::new(Quirks::MODERN);
+    chip.load_rom(&PROGRAM).unwrap();
+    chip
+}
+
+/// The rows of the 4x5 digit drawn at (0, 0), one string per row.
+fn digit(chip: &Chip) -> Vec<String> {
+    let width = chip.display().width();
+    (0..5)
+        .map(|y| {
+            (0..4)
+                .map(|x| if chip.display().pixels()[y * width + x] != 0 { '#' } else { '.' })
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn waits_for_a_key_given_by_its_index() {
+    let mut chip = chip();
+    for _ in 0..3 {
+        chip.cpu_cycle();
+    }
+    assert_eq!(chip.registers().pc(), 0x200);
+
+    chip.press_key(5);
+    chip.cpu_cycle();
+    assert!(chip.keyboard().is_pressed(5));
+    assert_eq!(chip.registers().pc(), 0x200, "the key has to be released");
+
+    chip.release_key(5);
+    chip.cpu_cycle();
+    assert_eq!(chip.registers().v(0), 5);
+    assert_eq!(digit(&chip), ["####", "#...", "####", "...#", "####"]);
+}
+
+#[test]
+fn ignores_keys_outside_the_keypad() {
+    let mut chip = chip();
+    chip.press_key(0x10);
+    chip.release_key(0x10);
+    chip.cpu_cycle();
+    assert_eq!(chip.registers().pc(), 0x200);
+    assert!(!chip.keyboard().is_pressed(0x10));
+}
+
+#[test]
+fn sounds_while_the_sound_timer_runs() {
+    let mut chip = chip();
+    assert!(!chip.sound_active());
+
+    chip.press_key(2);
+    chip.release_key(2);
+    // The sound timer is set to 2 and counts down at the end of each cycle,
+    // starting with the one that set it.
+    chip.cpu_cycle();
+    assert_eq!(chip.timers().sound(), 1);
+    assert!(chip.sound_active());
+
+    chip.cpu_cycle();
+    assert!(!chip.sound_active());
+}
@@ -0,0 +1,33 @@
+//! Checks decoding and encoding of every opcode.
+
+use chirp_8::emulator::{DecodeError, Instruction};
+
+#[test]
+fn decode_and_encode_round_trip() {
+    let mut valid = 0;
+    for opcode in 0..=u16::MAX {
+        if let Ok(instruction) = Instruction::decode(opcode) {
+            assert_eq!(instruction.encode(), opcode, "{:#06x} decodes to {:?}", opcode, instruction);
+            assert_eq!(Instruction::decode(instruction.encode()), Ok(instruction));
+            valid += 1;
+        }
+    }
+    // 39 in 00xx, 10 prefixes with 12 bits of operands, 13 with x and y, 16
+    // with x, and F000 and F002.
+    assert_eq!(valid, 39 + 10 * 4096 + 13 * 256 + 16 * 16 + 2);
+}
+
+#[test]
+fn rejects_invalid_opcodes() {
+    for opcode in [0x0000, 0x00E1, 0x5001, 0x8008, 0x9001, 0xE000, 0xF100, 0xF102, 0xF0FF] {
+        assert_eq!(Instruction::decode(opcode), Err(DecodeError(opcode)));
+    }
+}
+
+#[test]
+fn long_load_spans_four_bytes() {
+    assert_eq!(Instruction::decode(0xF000).map(|instruction| instruction.size()), Ok(4));
+    for opcode in [0x00E0, 0x1234, 0xD015, 0xF002, 0xF101, 0xF265] {
+        assert_eq!(Instruction::decode(opcode).map(|instruction| instruction.size()), Ok(2), "{:#06x}", opcode);
+    }
+}
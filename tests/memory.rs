@@ -0,0 +1,84 @@
+//! Checks the size of memory on each platform, and what happens at its end.
+
+use chirp_8::emulator::{Chip, Quirks};
+use std::io::ErrorKind;
+
+#[test]
+fn memory_is_sized_by_platform() {
+    assert_eq!(Chip::new(Quirks::COSMAC_VIP).memory().size(), 0x1000);
+    assert_eq!(Chip::new(Quirks::SUPER_CHIP).memory().size(), 0x1000);
+    assert_eq!(Chip::new(Quirks::XO_CHIP).memory().size(), 0x10000);
+
+    // Changing platform keeps the program.
+    let mut chip = Chip::new(Quirks::COSMAC_VIP);
+    chip.load_rom(&[0x12, 0x34]).unwrap();
+    chip.set_quirks(Quirks::XO_CHIP);
+    assert_eq!(chip.memory().size(), 0x10000);
+    assert_eq!(chip.memory().peek(0x201), 0x34);
+}
+
+/// `LD I, FFF; LD V1, AB; LD [I], V1` stores V1 past the end of 4KB.
+#[test]
+fn addresses_wrap_at_4k_unless_memory_is_extended() {
+    let rom = [0xaf, 0xff, 0x61, 0xab, 0xf1, 0x55];
+
+    let mut chip = Chip::new(Quirks::COSMAC_VIP);
+    chip.load_rom(&rom).unwrap();
+    for _ in 0..3 {
+        chip.step();
+    }
+    assert_eq!(chip.memory().peek(0x000), 0xAB);
+
+    let mut chip = Chip::new(Quirks::XO_CHIP);
+    chip.load_rom(&rom).unwrap();
+    for _ in 0..3 {
+        chip.step();
+    }
+    assert_eq!(chip.memory().peek(0x1000), 0xAB);
+    assert_ne!(chip.memory().peek(0x000), 0xAB);
+}
+
+#[test]
+fn rejects_roms_that_dont_fit() {
+    let mut chip = Chip::new(Quirks::COSMAC_VIP);
+    assert!(chip.load_rom(&vec![0; 0x1000 - 0x200]).is_ok());
+    let err = chip.load_rom(&vec![0; 0x1000 - 0x200 + 1]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+    let mut chip = Chip::new(Quirks::XO_CHIP);
+    assert!(chip.load_rom(&vec![0xAA; 0x10000 - 0x200]).is_ok());
+    assert_eq!(chip.memory().peek(0xFFFF), 0xAA);
+    assert_eq!(chip.load_rom(&vec![0; 0x10000 - 0x200 + 1]).unwrap_err().kind(), ErrorKind::InvalidData);
+    assert_eq!(chip.reset(&vec![0; 0x10000]).unwrap_err().kind(), ErrorKind::InvalidData);
+    assert_eq!(chip.memory().peek(0xFFFF), 0xAA);
+}
+
+/// Instructions that go back to themselves do so across the end of the
+/// address space.
+#[test]
+fn the_program_counter_wraps_around() {
+    let mut chip = Chip::new(Quirks::XO_CHIP);
+    chip.load_rom(&[]).unwrap();
+    chip.memory_mut().poke(0xFFFE, 0xF0);
+    chip.memory_mut().poke(0xFFFF, 0x0A);
+    chip.registers_mut().set_pc(0xFFFE);
+    chip.step();
+    assert_eq!(chip.registers().pc(), 0xFFFE);
+
+    // JP 000 from the last address isn't a jump to itself.
+    chip.memory_mut().poke(0xFFFE, 0x10);
+    chip.memory_mut().poke(0xFFFF, 0x00);
+    chip.step();
+    assert_eq!(chip.registers().pc(), 0x000);
+    assert!(!chip.paused());
+
+    // With the display wait, a sprite drawn mid-frame waits in place.
+    let mut chip = Chip::new(Quirks::COSMAC_VIP);
+    chip.load_rom(&[0x60, 0x00]).unwrap();
+    chip.step();
+    chip.memory_mut().poke(0xFFFE, 0xD0);
+    chip.memory_mut().poke(0xFFFF, 0x01);
+    chip.registers_mut().set_pc(0xFFFE);
+    chip.step();
+    assert_eq!(chip.registers().pc(), 0xFFFE);
+}
@@ -0,0 +1,132 @@
+//! Records movies of a program and plays them back, in and out of sync.
+
+use chirp_8::emulator::{Chip, CosmacVipRandom, Quirks, RandomKind, Timing};
+use chirp_8::movie::{InputEvent, Movie, MovieError, Player, Recorder, MOVIE_MAGIC, MOVIE_VERSION};
+
+/// loop: RND V0, 0xFF; ADD V1, V0; SKNP V2; ADD V3, 1; JP loop
+const PROGRAM: [u8; 10] = [0xC0, 0xFF, 0x81, 0x04, 0xE2, 0xA1, 0x73, 0x01, 0x12, 0x00];
+
+/// A CPU rate that doesn't divide into frames evenly.
+const CPU_HZ: u32 = 500;
+
+fn chip() -> Chip {
+    let mut chip = Chip::new(Quirks::default());
+    chip.load_rom(&PROGRAM).unwrap();
+    chip
+}
+
+/// Records `frames` frames with the VIP random source, pausing from the
+/// tenth frame for 30 frames' time, and holding key 0 down from the middle of
+/// the pause until frame 70. Returns the movie and the state the chip ended in.
+fn record(frames: u32) -> (Movie, Vec<u8>) {
+    let mut chip = chip();
+    chip.set_random_source(Box::new(CosmacVipRandom::new()));
+    let mut recorder = Recorder::new(&mut chip, &PROGRAM, 1234, CPU_HZ);
+
+    let mut recorded = 0;
+    for tick in 0.. {
+        if recorded == frames {
+            break;
+        }
+        chip.set_paused((10..40).contains(&tick));
+        match tick {
+            25 => recorder.set_key(&mut chip, 0, true),
+            100 => recorder.set_key(&mut chip, 0, false),
+            _ => (),
+        }
+        recorder.start_frame(&mut chip);
+        chip.cpu_cycle();
+        recorder.end_frame(&mut chip);
+        if !chip.paused() {
+            recorded += 1;
+        }
+    }
+
+    // The recording ends paused.
+    chip.set_paused(true);
+    let movie = recorder.finish(&mut chip);
+    chip.set_paused(false);
+    (movie, chip.save_state())
+}
+
+/// Plays a movie back on a chip set up unlike the recording's, until it ends.
+fn play(movie: Movie) -> Result<Chip, MovieError> {
+    let mut chip = chip();
+    chip.set_timing(Timing::CosmacVip);
+    chip.set_instructions_per_cycle(1);
+    let mut player = Player::new(movie, &mut chip, &PROGRAM)?;
+    while !player.finished() {
+        player.start_frame(&mut chip);
+        chip.cpu_cycle();
+        player.end_frame(&mut chip)?;
+    }
+    Ok(chip)
+}
+
+#[test]
+fn plays_back_what_was_recorded() {
+    let (movie, state) = record(150);
+    assert_eq!(movie.frames, 150);
+    assert_eq!(
+        (movie.random, movie.seed, movie.cpu_hz, movie.timing),
+        (RandomKind::CosmacVip, 1234, CPU_HZ, Timing::Instructions)
+    );
+    assert_eq!(movie.checksums.iter().map(|(frame, _)| *frame).collect::<Vec<_>>(), [60, 120, 150]);
+    assert_eq!(
+        movie.events,
+        [InputEvent { frame: 10, key: 0, pressed: true }, InputEvent { frame: 70, key: 0, pressed: false }]
+    );
+
+    let chip = play(movie).unwrap();
+    assert_eq!(chip.random_kind(), RandomKind::CosmacVip);
+    assert_eq!(chip.timing(), Timing::Instructions);
+    assert_eq!(chip.save_state(), state);
+    assert!(chip.registers().v(3) > 0, "the key was never seen");
+}
+
+#[test]
+fn survives_saving() {
+    let (movie, _) = record(70);
+    let bytes = movie.to_bytes();
+    assert_eq!(bytes[..4], MOVIE_MAGIC);
+    assert_eq!(Movie::from_bytes(&bytes).unwrap(), movie);
+
+    assert!(matches!(Movie::from_bytes(b"C8ST"), Err(MovieError::InvalidMagic)));
+    let mut other_version = bytes.clone();
+    other_version[4..6].copy_from_slice(&(MOVIE_VERSION - 1).to_be_bytes());
+    assert!(matches!(
+        Movie::from_bytes(&other_version),
+        Err(MovieError::UnsupportedVersion { found, expected: MOVIE_VERSION }) if found == MOVIE_VERSION - 1
+    ));
+    assert!(matches!(Movie::from_bytes(&bytes[..bytes.len() - 1]), Err(MovieError::Corrupt(_))));
+}
+
+#[test]
+fn refuses_another_rom() {
+    let (movie, _) = record(10);
+    let expected = movie.rom_hash;
+    let mut chip = chip();
+    let other = [0x12, 0x00];
+    match Player::new(movie, &mut chip, &other) {
+        Err(MovieError::RomMismatch { expected: hash, found }) => {
+            assert_eq!(hash, expected);
+            assert_ne!(found, expected);
+        }
+        _ => panic!("the movie played on another ROM"),
+    }
+}
+
+#[test]
+fn reports_going_out_of_sync() {
+    let (mut movie, _) = record(150);
+    movie.events[1].frame = 100;
+    assert!(matches!(play(movie), Err(MovieError::Desync { frame: 120 })));
+
+    let (mut movie, _) = record(150);
+    movie.seed += 1;
+    assert!(matches!(play(movie), Err(MovieError::Desync { frame: 60 })));
+
+    let (mut movie, _) = record(150);
+    movie.cpu_hz += 1;
+    assert!(matches!(play(movie), Err(MovieError::Desync { frame: 60 })));
+}
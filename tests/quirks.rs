@@ -0,0 +1,21 @@
+//! Checks the quirks that the presets differ in.
+
+use chirp_8::emulator::{Chip, Quirks};
+
+/// Runs `LD I, 0x300; LD [I], V2; LD V2, [I]` and returns where I is left.
+fn i_after_load_store(quirks: Quirks) -> u16 {
+    let mut chip = Chip::new(quirks);
+    chip.load_rom(&[0xA3, 0x00, 0xF2, 0x55, 0xF2, 0x65]).unwrap();
+    for _ in 0..3 {
+        chip.step();
+    }
+    chip.registers().i()
+}
+
+#[test]
+fn load_store_increments_i() {
+    assert_eq!(i_after_load_store(Quirks::COSMAC_VIP), 0x306);
+    assert_eq!(i_after_load_store(Quirks::CHIP_48), 0x304);
+    assert_eq!(i_after_load_store(Quirks::SUPER_CHIP), 0x300);
+    assert_eq!(i_after_load_store(Quirks::XO_CHIP), 0x306);
+}
@@ -0,0 +1,128 @@
+//! Generates random numbers with the COSMAC VIP's algorithm, and restores the
+//! random source from save states.
+
+use chirp_8::emulator::{Chip, CosmacVipRandom, Quirks, RandomKind, RandomSource, StateError, Xorshift, VIP_RANDOM_PAGE};
+
+/// loop: RND V0, 0xFF; LD I, 0x300; ADD I, V1; LD [I], V0; ADD V1, 1; JP loop
+const PROGRAM: [u8; 12] = [0xC0, 0xFF, 0xA3, 0x00, 0xF1, 0x1E, 0xF0, 0x55, 0x71, 0x01, 0x12, 0x00];
+
+fn chip(random: Box<dyn RandomSource>) -> Chip {
+    let mut chip = Chip::new(Quirks::default());
+    chip.set_random_source(random);
+    chip.load_rom(&PROGRAM).unwrap();
+    chip
+}
+
+/// Executes the RND instructions of `count` iterations of the loop, returning
+/// the numbers.
+fn numbers(chip: &mut Chip, count: usize) -> Vec<u8> {
+    let mut numbers = Vec::new();
+    for _ in 0..count {
+        chip.step();
+        numbers.push(chip.registers().v(0));
+        for _ in 0..5 {
+            chip.step();
+        }
+    }
+    numbers
+}
+
+#[test]
+fn vip_adds_the_page_to_a_counter() {
+    let mut page = [0; 256];
+    for (index, byte) in page.iter_mut().enumerate() {
+        *byte = index as u8;
+    }
+    let mut random = CosmacVipRandom::with_page(page);
+    let numbers: Vec<u8> = (0..6).map(|_| random.next_byte()).collect();
+    // R9 goes 0x0001, 0x0102, 0x0303, 0x0604..., its high byte adding up the
+    // low bytes.
+    assert_eq!(numbers, [1, 3, 6, 10, 15, 21]);
+    assert_eq!(random.state(), 0x1506);
+
+    random.set_state(0x20FF);
+    assert_eq!(random.next_byte(), 0x21);
+    random.set_state(0xFFFF);
+    assert_eq!(random.next_byte(), 0);
+}
+
+#[test]
+fn vip_page_holds_every_byte() {
+    let mut page = VIP_RANDOM_PAGE;
+    page.sort_unstable();
+    assert!(page.iter().enumerate().all(|(index, &byte)| byte as usize == index));
+    assert_ne!(VIP_RANDOM_PAGE, page);
+}
+
+#[test]
+fn vip_numbers_vary() {
+    let mut chip = chip(Box::new(CosmacVipRandom::new()));
+    let numbers = numbers(&mut chip, 256);
+    assert_eq!(chip.random_kind(), RandomKind::CosmacVip);
+
+    let mut expected = CosmacVipRandom::new();
+    assert_eq!(numbers, (0..256).map(|_| expected.next_byte()).collect::<Vec<_>>());
+    let mut distinct = numbers.clone();
+    distinct.sort_unstable();
+    distinct.dedup();
+    assert!(distinct.len() > 100, "only {} distinct numbers", distinct.len());
+    assert_eq!(chip.memory().peek(0x300 + 17), numbers[17]);
+}
+
+#[test]
+fn save_states_restore_the_source() {
+    for source in [RandomKind::CosmacVip, RandomKind::Xorshift] {
+        let mut chip = chip(source.source().unwrap());
+        chip.set_seed(99);
+        numbers(&mut chip, 10);
+        let state = chip.save_state();
+        let expected = numbers(&mut chip, 20);
+
+        let other = match source {
+            RandomKind::Xorshift => RandomKind::CosmacVip,
+            _ => RandomKind::Xorshift,
+        };
+        let mut restored = Chip::new(Quirks::default());
+        restored.set_random_source(other.source().unwrap());
+        restored.load_state(&state).unwrap();
+        assert_eq!(restored.random_kind(), source);
+        assert_eq!(numbers(&mut restored, 20), expected, "{:?}", source);
+    }
+}
+
+/// A source chirp-8 doesn't know, counting up.
+struct Counter(u64);
+
+impl RandomSource for Counter {
+    fn next_byte(&mut self) -> u8 {
+        self.0 += 1;
+        self.0 as u8
+    }
+
+    fn state(&self) -> u64 {
+        self.0
+    }
+
+    fn set_state(&mut self, state: u64) {
+        self.0 = state;
+    }
+}
+
+#[test]
+fn custom_sources_need_a_custom_source_to_load() {
+    let mut counting = chip(Box::new(Counter(0)));
+    numbers(&mut counting, 3);
+    let state = counting.save_state();
+    assert_eq!(counting.random_kind(), RandomKind::Custom);
+
+    let mut restored = chip(Box::new(Counter(40)));
+    restored.load_state(&state).unwrap();
+    assert_eq!(numbers(&mut restored, 2), [4, 5]);
+
+    let mut restored = chip(Box::new(Xorshift::from_seed(1)));
+    assert_eq!(
+        restored.load_state(&state),
+        Err(StateError::Invalid("the state was saved with a custom random source"))
+    );
+    assert_eq!(restored.random_kind(), RandomKind::Xorshift);
+}
@@ -0,0 +1,28 @@
+//! Checks that a reset chip runs a program the same as a new one.
+
+use chirp_8::emulator::{Chip, Quirks};
+
+fn run(chip: &mut Chip, frames: u32) {
+    for _ in 0..frames {
+        chip.cpu_cycle();
+    }
+}
+
+#[test]
+fn reset_matches_a_new_chip() {
+    let rom = std::fs::read("roms/tests/3-corax+.ch8").unwrap();
+
+    let mut reset = Chip::new(Quirks::default());
+    reset.load_rom(&rom).unwrap();
+    run(&mut reset, 120);
+    reset.reset(&rom).unwrap();
+    reset.set_seed(1);
+    run(&mut reset, 60);
+
+    let mut new = Chip::new(Quirks::default());
+    new.load_rom(&rom).unwrap();
+    new.set_seed(1);
+    run(&mut new, 60);
+
+    assert_eq!(reset.save_state(), new.save_state());
+}
@@ -0,0 +1,132 @@
+//! Records frames of a program, and goes back through them by frame and by
+//! instruction.
+
+use chirp_8::emulator::{Chip, Quirks, Registers, Timing};
+use chirp_8::rewind::{diff, patch, Rewind};
+
+/// LD V0, 0; loop: ADD V0, 1; ADD V1, 2; LD I, 0x300; JP loop
+const PROGRAM: [u8; 10] = [0x60, 0x00, 0x70, 0x01, 0x71, 0x02, 0xA3, 0x00, 0x12, 0x02];
+
+fn chip(timing: Timing) -> Chip {
+    let mut chip = Chip::new(Quirks::default());
+    chip.set_timing(timing);
+    chip.load_rom(&PROGRAM).unwrap();
+    chip
+}
+
+/// The registers that tell the instructions of the program apart.
+fn location(registers: &Registers) -> (u16, u8, u8) {
+    (registers.pc(), registers.v(0), registers.v(1))
+}
+
+/// Steps through the rest of the frame, returning the location after each
+/// instruction.
+fn run_frame(chip: &mut Chip) -> Vec<(u16, u8, u8)> {
+    let mut locations = Vec::new();
+    loop {
+        chip.step();
+        locations.push(location(chip.registers()));
+        if chip.cycle_position() == 0 {
+            return locations;
+        }
+    }
+}
+
+#[test]
+fn patching_a_diff_gives_the_state_back() {
+    let mut chip = chip(Timing::Instructions);
+    let mut states = vec![chip.save_state()];
+    for _ in 0..3 {
+        chip.cpu_cycle();
+        states.push(chip.save_state());
+    }
+    for pair in states.windows(2) {
+        assert_eq!(patch(&pair[1], &diff(&pair[1], &pair[0])), pair[0]);
+        assert_eq!(patch(&pair[0], &diff(&pair[0], &pair[1])), pair[1]);
+    }
+
+    let from: Vec<u8> = (0..70_000u32).map(|index| (index % 251) as u8).collect();
+    let mut to: Vec<u8> = from.iter().map(|byte| byte.wrapping_add(1)).collect();
+    to[10] = from[10];
+    to[20..30].copy_from_slice(&from[20..30]);
+    assert_eq!(patch(&from, &diff(&from, &to)), to);
+    assert_eq!(patch(&from, &diff(&from, &to[..100])), to[..100]);
+    assert_eq!(patch(&from[..100], &diff(&from[..100], &to)), to);
+    assert_eq!(diff(&from, &from), 70_000u32.to_be_bytes());
+}
+
+#[test]
+fn forgets_the_oldest_frames_past_the_budget() {
+    let mut chip = chip(Timing::Instructions);
+    let mut rewind = Rewind::new(chip.save_state().len() + 1000);
+    let mut states = Vec::new();
+    for _ in 0..200 {
+        chip.cpu_cycle();
+        rewind.record(&chip);
+        states.push(chip.save_state());
+    }
+    assert!(rewind.memory_used() <= chip.save_state().len() + 1000);
+    let frames = rewind.frames();
+    assert!(frames > 0 && frames < 199, "kept {} frames", frames);
+
+    for _ in 0..frames {
+        assert!(rewind.rewind_frame(&mut chip));
+    }
+    assert!(!rewind.rewind_frame(&mut chip));
+    assert_eq!(chip.save_state(), states[states.len() - 1 - frames]);
+}
+
+#[test]
+fn rewinds_frame_by_frame() {
+    let mut chip = chip(Timing::Instructions);
+    let mut rewind = Rewind::default();
+    let mut states = Vec::new();
+    for _ in 0..5 {
+        chip.cpu_cycle();
+        rewind.record(&chip);
+        states.push(chip.save_state());
+    }
+    assert_eq!(rewind.frames(), 4);
+
+    // Part way through a frame, the first rewind goes back to its start.
+    chip.step();
+    assert!(rewind.rewind_frame(&mut chip));
+    assert_eq!(chip.save_state(), states[4]);
+    for state in states[..4].iter().rev() {
+        assert!(rewind.rewind_frame(&mut chip));
+        assert_eq!(&chip.save_state(), state);
+    }
+    assert!(!rewind.rewind_frame(&mut chip));
+}
+
+#[test]
+fn steps_back_an_instruction() {
+    for timing in [Timing::Instructions, Timing::CosmacVip] {
+        let mut chip = chip(timing);
+        let mut rewind = Rewind::default();
+        chip.cpu_cycle();
+        rewind.record(&chip);
+        let previous = run_frame(&mut chip);
+        assert!(previous.len() > 3, "{:?}", timing);
+        rewind.record(&chip);
+
+        // Mid-frame, back through the instructions executed so far.
+        let mut current = Vec::new();
+        for _ in 0..3 {
+            chip.step();
+            current.push(location(chip.registers()));
+            rewind.record(&chip);
+        }
+        for expected in [current[1], current[0], previous[previous.len() - 1]] {
+            assert!(rewind.step_back(&mut chip));
+            assert_eq!(location(chip.registers()), expected, "{:?}", timing);
+            assert!(chip.paused());
+        }
+
+        // From the start of a frame, back to the last instruction but one of
+        // the previous frame, however many instructions it had.
+        assert!(rewind.step_back(&mut chip));
+        assert_eq!(location(chip.registers()), previous[previous.len() - 2], "{:?}", timing);
+        assert_eq!(chip.cycle_position(), previous.len() - 1, "{:?}", timing);
+    }
+}
@@ -0,0 +1,105 @@
+//! Drives the frame scheduler with a fake clock.
+
+use chirp_8::scheduler::{frame_instructions, FakeClock, Scheduler, MAX_CATCH_UP_FRAMES, TIMER_HZ};
+use std::time::Duration;
+
+const FRAME: Duration = Duration::from_nanos(16_666_667);
+
+#[test]
+fn runs_sixty_frames_a_second() {
+    let clock = FakeClock::new();
+    let mut scheduler = Scheduler::new(clock.clone(), 600);
+
+    let mut frames = 0;
+    for _ in 0..1000 {
+        clock.advance(Duration::from_millis(1));
+        frames += scheduler.frames_due();
+    }
+    assert_eq!(frames, TIMER_HZ);
+}
+
+#[test]
+fn catches_up_on_late_frames() {
+    let clock = FakeClock::new();
+    let mut scheduler = Scheduler::new(clock.clone(), 600);
+
+    clock.advance(FRAME / 2);
+    assert_eq!(scheduler.frames_due(), 0);
+    clock.advance(FRAME * 3);
+    assert_eq!(scheduler.frames_due(), 3);
+    assert!(scheduler.until_next_frame() <= FRAME / 2 + Duration::from_nanos(1));
+}
+
+#[test]
+fn drops_frames_after_a_stall() {
+    let clock = FakeClock::new();
+    let mut scheduler = Scheduler::new(clock.clone(), 600);
+
+    clock.advance(Duration::from_secs(5));
+    assert_eq!(scheduler.frames_due(), MAX_CATCH_UP_FRAMES);
+    assert_eq!(scheduler.dropped_frames(), 5 * TIMER_HZ as u64 - MAX_CATCH_UP_FRAMES as u64);
+    clock.advance(FRAME);
+    assert_eq!(scheduler.frames_due(), 1);
+}
+
+#[test]
+fn spreads_instructions_over_a_second() {
+    let mut scheduler = Scheduler::new(FakeClock::new(), 1000);
+
+    let frames: Vec<usize> = (0..TIMER_HZ).map(|_| scheduler.next_frame_instructions()).collect();
+    assert_eq!(frames.iter().sum::<usize>(), 1000);
+    assert!(frames.iter().all(|instructions| (16..=17).contains(instructions)));
+}
+
+#[test]
+fn speed_scales_emulated_time() {
+    let clock = FakeClock::new();
+    let mut scheduler = Scheduler::new(clock.clone(), 600);
+
+    scheduler.set_speed(2.0);
+    clock.advance(FRAME * 4);
+    assert_eq!(scheduler.frames_due(), 8);
+    scheduler.set_speed(0.5);
+    clock.advance(FRAME * 4);
+    assert_eq!(scheduler.frames_due(), 2);
+}
+
+#[test]
+fn runs_at_least_an_instruction_a_frame() {
+    let mut scheduler = Scheduler::new(FakeClock::new(), 10);
+    assert_eq!(scheduler.cpu_hz(), TIMER_HZ);
+    assert_eq!(scheduler.next_frame_instructions(), 1);
+
+    scheduler.set_cpu_hz(0);
+    assert_eq!(scheduler.cpu_hz(), TIMER_HZ);
+    scheduler.set_cpu_hz(1200);
+    assert_eq!(scheduler.next_frame_instructions(), 20);
+}
+
+#[test]
+fn repeats_the_spread_every_second() {
+    assert!((0..TIMER_HZ as u64).all(|frame| frame_instructions(600, frame) == 10));
+
+    let second: Vec<usize> = (0..TIMER_HZ as u64).map(|frame| frame_instructions(61, frame)).collect();
+    assert_eq!(second.iter().sum::<usize>(), 61);
+    assert_eq!(second.iter().filter(|instructions| **instructions == 2).count(), 1);
+    let next: Vec<usize> = (TIMER_HZ as u64..2 * TIMER_HZ as u64).map(|frame| frame_instructions(61, frame)).collect();
+    assert_eq!(second, next);
+}
+
+#[test]
+fn stops_time_at_zero_speed() {
+    let clock = FakeClock::new();
+    let mut scheduler = Scheduler::new(clock.clone(), 600);
+
+    scheduler.set_speed(-1.0);
+    assert_eq!(scheduler.speed(), 0.0);
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(scheduler.frames_due(), 0);
+    assert_eq!(scheduler.until_next_frame(), Duration::from_secs(1));
+
+    // Time that passed while stopped isn't run once time starts again.
+    scheduler.set_speed(1.0);
+    assert_eq!(scheduler.frames_due(), 0);
+    assert_eq!(scheduler.dropped_frames(), 0);
+}
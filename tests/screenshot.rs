@@ -0,0 +1,87 @@
+//! Renders the display to an image headlessly and encodes it as PNG.
+
+use chirp_8::emulator::{Chip, Quirks};
+
+const PALETTE: [(f32, f32, f32); 4] = [(0.0, 0.0, 0.0), (1.0, 0.8, 0.0), (0.6, 0.6, 0.6), (0.3, 0.3, 0.3)];
+
+fn ibm_logo() -> Chip {
+    let rom = std::fs::read("roms/tests/2-ibm-logo.ch8").expect("the test ROM is missing");
+    let mut chip = Chip::new(Quirks::COSMAC_VIP);
+    chip.load_rom(&rom).unwrap();
+    for _ in 0..60 {
+        chip.cpu_cycle();
+    }
+    chip
+}
+
+#[test]
+fn scales_the_pixels_into_squares() {
+    let chip = ibm_logo();
+    let display = chip.display();
+    let image = display.to_image(3, PALETTE);
+
+    assert_eq!((image.width(), image.height()), (64 * 3, 32 * 3));
+    assert_eq!(image.rgb().len(), 64 * 3 * 32 * 3 * 3);
+    for (index, &pixel) in display.pixels().iter().enumerate() {
+        let (x, y) = ((index % 64) as u32, (index / 64) as u32);
+        let color = if pixel == 0 { [0, 0, 0] } else { [255, 204, 0] };
+        for (dx, dy) in [(0, 0), (2, 0), (0, 2), (2, 2)] {
+            assert_eq!(image.pixel(x * 3 + dx, y * 3 + dy), color, "at ({}, {})", x, y);
+        }
+    }
+    assert!(display.pixels().iter().any(|&pixel| pixel != 0));
+}
+
+#[test]
+fn encodes_a_png() {
+    let image = ibm_logo().display().to_image(2, PALETTE);
+    let mut png = Vec::new();
+    image.write_png(&mut png).unwrap();
+
+    let mut reader = png::Decoder::new(png.as_slice()).read_info().unwrap();
+    let mut decoded = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut decoded).unwrap();
+    assert_eq!((info.width, info.height), (128, 64));
+    assert_eq!(info.color_type, png::ColorType::Rgb);
+    assert_eq!(&decoded[..info.buffer_size()], image.rgb());
+}
+
+#[test]
+fn stretches_high_resolution_pixels_over_the_window() {
+    // HIGH; LD I, sprite; DRW V0, V0, 1; loop: LD V1, 0; JP loop; sprite: 10100000
+    let rom = [0x00, 0xff, 0xa2, 0x0c, 0xd0, 0x01, 0x61, 0x00, 0x12, 0x06, 0x00, 0x00, 0xa0];
+    let mut chip = Chip::new(Quirks::XO_CHIP);
+    chip.load_rom(&rom).unwrap();
+    chip.cpu_cycle();
+    let display = chip.display();
+    assert_eq!(display.width(), 128);
+
+    // At the window's scale of 3, every high resolution pixel is 1.5 image
+    // pixels wide.
+    let image = display.to_image_sized(64 * 3, 32 * 3, PALETTE);
+    assert_eq!((image.width(), image.height()), (192, 96));
+    let row: Vec<bool> = (0..6).map(|x| image.pixel(x, 0) != [0, 0, 0]).collect();
+    assert_eq!(row, [true, true, false, true, true, false]);
+    assert_eq!(image.pixel(0, 1), [255, 204, 0]);
+    assert_eq!(image.pixel(0, 2), [0, 0, 0]);
+}
+
+/// The screenshot hotkey renders at the window's size, which is what
+/// `to_image` gives whenever the display's pixels fit it exactly.
+#[test]
+fn the_window_sized_image_matches_to_image() {
+    let chip = ibm_logo();
+    let display = chip.display();
+    for scale in 1..=4 {
+        assert_eq!(display.to_image_sized(64 * scale, 32 * scale, PALETTE), display.to_image(scale, PALETTE));
+    }
+
+    // HIGH; LD I, 0; DRW V0, V0, 5; loop: LD V1, 0; JP loop
+    let mut chip = Chip::new(Quirks::XO_CHIP);
+    chip.load_rom(&[0x00, 0xff, 0xa0, 0x00, 0xd0, 0x05, 0x61, 0x00, 0x12, 0x06]).unwrap();
+    chip.cpu_cycle();
+    let display = chip.display();
+    for scale in [2, 4, 6] {
+        assert_eq!(display.to_image_sized(64 * scale, 32 * scale, PALETTE), display.to_image(scale / 2, PALETTE));
+    }
+}
@@ -0,0 +1,74 @@
+//! Saves the state of a running program and loads it back, and rejects states
+//! that can't be loaded.
+
+use chirp_8::emulator::{Chip, Quirks, StateError, STATE_VERSION};
+
+fn ibm_logo(frames: usize) -> Chip {
+    let rom = std::fs::read("roms/tests/2-ibm-logo.ch8").expect("the test ROM is missing");
+    let mut chip = Chip::new(Quirks::COSMAC_VIP);
+    chip.load_rom(&rom).unwrap();
+    chip.set_seed(7);
+    run(&mut chip, frames);
+    chip
+}
+
+fn run(chip: &mut Chip, frames: usize) {
+    for _ in 0..frames {
+        chip.cpu_cycle();
+    }
+}
+
+#[test]
+fn resumes_from_a_saved_state() {
+    let mut chip = ibm_logo(2);
+    let state = chip.save_state();
+    run(&mut chip, 30);
+
+    let mut restored = Chip::new(Quirks::COSMAC_VIP);
+    restored.load_state(&state).unwrap();
+    assert_eq!(restored.save_state(), state);
+    run(&mut restored, 30);
+
+    assert_eq!(restored.save_state(), chip.save_state());
+    assert_eq!(restored.display().pixels(), chip.display().pixels());
+    assert!(chip.display().pixels().iter().any(|&pixel| pixel != 0));
+}
+
+#[test]
+fn rejects_other_versions() {
+    let mut state = ibm_logo(1).save_state();
+    state[4..6].copy_from_slice(&(STATE_VERSION + 1).to_be_bytes());
+
+    let mut chip = ibm_logo(2);
+    let before = chip.save_state();
+    assert_eq!(
+        chip.load_state(&state),
+        Err(StateError::UnsupportedVersion { found: STATE_VERSION + 1, expected: STATE_VERSION })
+    );
+    assert_eq!(chip.save_state(), before);
+}
+
+#[test]
+fn rejects_other_files() {
+    let mut chip = ibm_logo(1);
+    let before = chip.save_state();
+
+    assert_eq!(chip.load_state(b"C8"), Err(StateError::InvalidMagic));
+    let rom = std::fs::read("roms/tests/2-ibm-logo.ch8").unwrap();
+    assert_eq!(chip.load_state(&rom), Err(StateError::InvalidMagic));
+    assert_eq!(chip.save_state(), before);
+}
+
+#[test]
+fn rejects_truncated_and_padded_states() {
+    let mut chip = ibm_logo(1);
+    let state = chip.save_state();
+
+    for len in [6, 100, state.len() - 1] {
+        assert_eq!(chip.load_state(&state[..len]), Err(StateError::Truncated), "at {} bytes", len);
+    }
+    let mut padded = state.clone();
+    padded.extend_from_slice(&[0, 0]);
+    assert_eq!(chip.load_state(&padded), Err(StateError::TrailingBytes(2)));
+    assert_eq!(chip.save_state(), state);
+}
@@ -0,0 +1,155 @@
+//! Checks the instructions SUPER-CHIP added and the 128x64 hires display.
+
+use chirp_8::emulator::{Chip, Quirks};
+
+fn super_chip(rom: &[u8]) -> Chip {
+    let mut chip = Chip::new(Quirks::SUPER_CHIP);
+    chip.load_rom(rom).unwrap();
+    chip
+}
+
+fn run(chip: &mut Chip, steps: usize) {
+    for _ in 0..steps {
+        chip.step();
+    }
+}
+
+fn lit(chip: &Chip, x: usize, y: usize) -> bool {
+    chip.display().pixels()[y * chip.display().width() + x] != 0
+}
+
+/// The pixels lit on the display, as (x, y) pairs.
+fn lit_pixels(chip: &Chip) -> Vec<(usize, usize)> {
+    let width = chip.display().width();
+    (0..chip.display().pixels().len())
+        .filter(|index| chip.display().pixels()[*index] != 0)
+        .map(|index| (index % width, index / width))
+        .collect()
+}
+
+#[test]
+fn switches_between_lores_and_hires() {
+    let mut chip = super_chip(&[
+        0x00, 0xff, // HIGH
+        0x00, 0xfe, // LOW
+    ]);
+    assert!(!chip.display().hires());
+    assert_eq!((chip.display().width(), chip.display().height()), (64, 32));
+
+    run(&mut chip, 1);
+    assert!(chip.display().hires());
+    assert_eq!((chip.display().width(), chip.display().height()), (128, 64));
+    assert_eq!(chip.display().pixels().len(), 128 * 64);
+
+    run(&mut chip, 1);
+    assert!(!chip.display().hires());
+    assert_eq!(chip.display().pixels().len(), 64 * 32);
+}
+
+#[test]
+fn draws_16x16_sprites() {
+    let mut rom = vec![
+        0x00, 0xff, // HIGH
+        0xa2, 0x0a, // LD I, 20A
+        0xd0, 0x00, // DRW V0, V0, 0
+        0xd0, 0x00, // DRW V0, V0, 0
+        0x12, 0x08, // JP 208
+    ];
+    for _ in 0..16 {
+        rom.extend([0x80, 0x01]);
+    }
+    let mut chip = super_chip(&rom);
+
+    run(&mut chip, 3);
+    let mut expected: Vec<(usize, usize)> = Vec::new();
+    for y in 0..16 {
+        expected.extend([(0, y), (15, y)]);
+    }
+    assert_eq!(lit_pixels(&chip), expected);
+    assert_eq!(chip.registers().v(0xF), 0);
+
+    run(&mut chip, 1);
+    assert!(lit_pixels(&chip).is_empty());
+    assert_eq!(chip.registers().v(0xF), 1);
+}
+
+#[test]
+fn clips_sprites_at_the_edge_of_the_hires_display() {
+    let mut chip = super_chip(&[
+        0x00, 0xff, // HIGH
+        0x60, 0x7c, // LD V0, 124
+        0x61, 0x3f, // LD V1, 63
+        0xa2, 0x0a, // LD I, 20A
+        0xd0, 0x12, // DRW V0, V1, 2
+        0xff, 0xff,
+    ]);
+    run(&mut chip, 5);
+    assert_eq!(lit_pixels(&chip), [(124, 63), (125, 63), (126, 63), (127, 63)]);
+}
+
+#[test]
+fn scrolls_the_display() {
+    let mut chip = super_chip(&[
+        0x00, 0xff, // HIGH
+        0xa2, 0x0c, // LD I, 20C
+        0xd0, 0x01, // DRW V0, V0, 1
+        0x00, 0xfb, // SCR
+        0x00, 0xc3, // SCD 3
+        0x00, 0xfc, // SCL
+        0x80,
+    ]);
+    run(&mut chip, 3);
+    assert_eq!(lit_pixels(&chip), [(0, 0)]);
+
+    run(&mut chip, 1);
+    assert_eq!(lit_pixels(&chip), [(4, 0)]);
+
+    run(&mut chip, 1);
+    assert_eq!(lit_pixels(&chip), [(4, 3)]);
+
+    run(&mut chip, 1);
+    assert_eq!(lit_pixels(&chip), [(0, 3)]);
+}
+
+#[test]
+fn points_i_at_the_large_font() {
+    let mut chip = super_chip(&[
+        0x60, 0x08, // LD V0, 8
+        0xf0, 0x30, // LD HF, V0
+        0xd1, 0x1a, // DRW V1, V1, 10
+    ]);
+    run(&mut chip, 3);
+
+    assert_eq!(chip.registers().i(), 0x50 + 8 * 10);
+    // The large 8 is two stacked boxes with two pixel thick sides.
+    assert!((0..8).all(|x| lit(&chip, x, 0)));
+    let row: Vec<bool> = (0..8).map(|x| lit(&chip, x, 2)).collect();
+    assert_eq!(row, [true, true, false, false, false, false, true, true]);
+}
+
+#[test]
+fn keeps_rpl_flags_across_programs() {
+    let mut chip = super_chip(&[
+        0x60, 0x11, // LD V0, 11
+        0x61, 0x22, // LD V1, 22
+        0x62, 0x33, // LD V2, 33
+        0xf2, 0x75, // LD R, V2
+    ]);
+    run(&mut chip, 4);
+
+    chip.reset(&[
+        0xf1, 0x85, // LD V1, R
+    ])
+    .unwrap();
+    run(&mut chip, 1);
+    assert_eq!((chip.registers().v(0), chip.registers().v(1), chip.registers().v(2)), (0x11, 0x22, 0));
+}
+
+#[test]
+fn exit_pauses_the_chip() {
+    let mut chip = super_chip(&[
+        0x00, 0xfd, // EXIT
+    ]);
+    run(&mut chip, 1);
+    assert!(chip.paused());
+}
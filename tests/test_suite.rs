@@ -0,0 +1,163 @@
+//! Runs Timendus' CHIP-8 test suite (https://github.com/Timendus/chip8-test-suite)
+//! headlessly and compares the screen after a number of frames with golden
+//! images in `tests/golden/`. A golden image has a character per pixel: `.`
+//! for the background, `#` for the first plane, `+` for the second plane and
+//! `%` for both.
+//!
+//! Run with `UPDATE_GOLDEN=1` to write the current screens as the golden
+//! images, after checking that they show the tests passing.
+
+use chirp_8::emulator::{Chip, Display, Quirks};
+use chirp_8::movie::InputEvent;
+use std::path::Path;
+
+/// Holds a key down from one frame until another, as the test menus need
+/// keys to be released before acting on them.
+fn tap(key: u8, frame: u32, release_frame: u32) -> [InputEvent; 2] {
+    [
+        InputEvent { frame, key, pressed: true },
+        InputEvent { frame: release_frame, key, pressed: false },
+    ]
+}
+
+fn run(rom: &str, quirks: Quirks, frames: u32, input: &[InputEvent]) -> Chip {
+    let rom = std::fs::read(Path::new("roms/tests").join(rom)).expect("the test ROM is missing");
+    let mut chip = Chip::new(quirks);
+    chip.load_rom(&rom).unwrap();
+    chip.set_seed(1);
+
+    for frame in 0..frames {
+        for event in input.iter().filter(|event| event.frame == frame) {
+            if event.pressed {
+                chip.press_key(event.key);
+            } else {
+                chip.release_key(event.key);
+            }
+        }
+        chip.cpu_cycle();
+    }
+    chip
+}
+
+fn screen(display: &Display) -> String {
+    let mut screen = String::new();
+    for row in display.pixels().chunks(display.width()) {
+        screen.extend(row.iter().map(|pixel| match pixel {
+            0 => '.',
+            1 => '#',
+            2 => '+',
+            _ => '%',
+        }));
+        screen.push('\n');
+    }
+    screen
+}
+
+/// Shows the expected and actual screens side by side, with the differing
+/// pixels of each row marked with `^` underneath.
+fn diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+    let width = expected.iter().chain(&actual).map(|row| row.len()).max().unwrap_or(0);
+
+    let mut diff = format!("{:width$} | actual\n", "expected", width = width);
+    for index in 0..expected.len().max(actual.len()) {
+        let expected = expected.get(index).copied().unwrap_or("");
+        let actual = actual.get(index).copied().unwrap_or("");
+        diff += &format!("{:width$} | {}\n", expected, actual, width = width);
+        if expected != actual {
+            let marks: String = (0..width)
+                .map(|column| match (expected.as_bytes().get(column), actual.as_bytes().get(column)) {
+                    (a, b) if a == b => ' ',
+                    _ => '^',
+                })
+                .collect();
+            diff += &format!("{}   {}\n", marks, marks);
+        }
+    }
+    diff
+}
+
+fn check(name: &str, chip: &Chip) {
+    let path = Path::new("tests/golden").join(format!("{}.txt", name));
+    let actual = screen(chip.display());
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&path, &actual).unwrap();
+        return;
+    }
+
+    let expected = std::fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("{} is missing, run with UPDATE_GOLDEN=1 to create it", path.display()));
+    if expected != actual {
+        panic!("the screen of {} doesn't match {}:\n{}", name, path.display(), diff(&expected, &actual));
+    }
+}
+
+#[test]
+fn chip8_logo() {
+    check("1-chip8-logo", &run("1-chip8-logo.ch8", Quirks::COSMAC_VIP, 60, &[]));
+}
+
+#[test]
+fn ibm_logo() {
+    check("2-ibm-logo", &run("2-ibm-logo.ch8", Quirks::COSMAC_VIP, 60, &[]));
+}
+
+#[test]
+fn corax_plus() {
+    check("3-corax+", &run("3-corax+.ch8", Quirks::COSMAC_VIP, 300, &[]));
+}
+
+#[test]
+fn flags() {
+    check("4-flags", &run("4-flags.ch8", Quirks::COSMAC_VIP, 300, &[]));
+}
+
+#[test]
+fn quirks_chip8() {
+    let input = tap(0x1, 60, 63);
+    check("5-quirks-chip8", &run("5-quirks.ch8", Quirks::COSMAC_VIP, 600, &input));
+}
+
+#[test]
+fn quirks_super_chip() {
+    let input = [tap(0x2, 60, 63), tap(0x1, 120, 123)].concat();
+    check("5-quirks-super-chip", &run("5-quirks.ch8", Quirks::SUPER_CHIP, 700, &input));
+}
+
+#[test]
+fn quirks_xo_chip() {
+    let input = tap(0x3, 60, 63);
+    check("5-quirks-xo-chip", &run("5-quirks.ch8", Quirks::XO_CHIP, 600, &input));
+}
+
+/// The Ex9E test lights up the keys that are held, here 5 and A.
+#[test]
+fn keypad_ex9e() {
+    let input = [tap(0x1, 100, 103), tap(0x5, 200, 300), tap(0xA, 200, 300)].concat();
+    check("6-keypad-ex9e", &run("6-keypad.ch8", Quirks::COSMAC_VIP, 260, &input));
+}
+
+/// The ExA1 test lights up the keys that are not held, here all but 5.
+#[test]
+fn keypad_exa1() {
+    let input = [tap(0x2, 100, 103), tap(0x5, 200, 300)].concat();
+    check("6-keypad-exa1", &run("6-keypad.ch8", Quirks::COSMAC_VIP, 260, &input));
+}
+
+/// The Fx0A test only passes if the key is registered when it is released.
+#[test]
+fn keypad_fx0a() {
+    let input = [tap(0x3, 100, 103), tap(0x5, 200, 203)].concat();
+    check("6-keypad-fx0a", &run("6-keypad.ch8", Quirks::COSMAC_VIP, 300, &input));
+}
+
+/// The beep test sounds the buzzer while B is held.
+#[test]
+fn beep() {
+    let input = tap(0xB, 60, 120);
+    let chip = run("7-beep.ch8", Quirks::COSMAC_VIP, 90, &input);
+    assert!(chip.sound_active());
+    check("7-beep", &chip);
+}
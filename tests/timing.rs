@@ -0,0 +1,92 @@
+//! Checks the COSMAC VIP timing model.
+
+use chirp_8::emulator::{vip_cycles, Chip, Quirks, Registers, Timing, VIP_INTERPRETER_CYCLES};
+
+fn vip_chip(rom: &[u8]) -> Chip {
+    let mut chip = Chip::new(Quirks::COSMAC_VIP);
+    chip.load_rom(rom).unwrap();
+    chip.set_timing(Timing::CosmacVip);
+    chip
+}
+
+#[test]
+fn sprites_cost_more_when_taller_or_unaligned() {
+    let mut registers = Registers::new();
+    let aligned = vip_cycles(0xD015, &registers);
+    let taller = vip_cycles(0xD01A, &registers);
+    registers.set_v(0, 3);
+    let unaligned = vip_cycles(0xD015, &registers);
+    registers.set_v(0, 7);
+    let more_unaligned = vip_cycles(0xD015, &registers);
+
+    assert!(aligned < taller);
+    assert!(aligned < unaligned);
+    assert!(unaligned < more_unaligned);
+}
+
+#[test]
+fn a_frame_runs_as_many_instructions_as_fit() {
+    // 7001 ADD V0, 1; 1200 JP 200
+    let mut chip = vip_chip(&[0x70, 0x01, 0x12, 0x00]);
+    chip.cpu_cycle();
+
+    let add = vip_cycles(0x7001, chip.registers());
+    let jump = vip_cycles(0x1200, chip.registers());
+    // Every loop that starts before the frame's cycles run out adds one.
+    assert_eq!(chip.registers().v(0) as u32, VIP_INTERPRETER_CYCLES.div_ceil(add + jump));
+}
+
+#[test]
+fn drawing_waits_for_the_display_interrupt() {
+    // 7001 ADD V0, 1; D115 DRW V1, V1, 5; 1200 JP 200
+    let mut chip = vip_chip(&[0x70, 0x01, 0xD1, 0x15, 0x12, 0x00]);
+    for _ in 0..10 {
+        chip.cpu_cycle();
+    }
+
+    // A frame draws, jumps, adds and then waits, so a sprite is drawn a frame.
+    assert_eq!(chip.registers().v(0), 10);
+}
+
+#[test]
+fn a_deferred_draw_is_charged_once_it_is_drawn() {
+    // 7001 ADD V0, 1; D115 DRW V1, V1, 5; 7201 ADD V2, 1; 1204 JP 204
+    let mut chip = vip_chip(&[0x70, 0x01, 0xD1, 0x15, 0x72, 0x01, 0x12, 0x04]);
+    chip.cpu_cycle();
+    assert_eq!((chip.registers().pc(), chip.registers().v(2)), (0x202, 0));
+
+    // The next frame starts with all of its cycles, and spends the draw's
+    // only once.
+    chip.cpu_cycle();
+    let draw = vip_cycles(0xD115, chip.registers());
+    let loop_cycles = vip_cycles(0x7201, chip.registers()) + vip_cycles(0x1204, chip.registers());
+    assert_eq!(chip.registers().v(2) as u32, (VIP_INTERPRETER_CYCLES - draw).div_ceil(loop_cycles));
+}
+
+#[test]
+fn costs_follow_the_published_instruction_times() {
+    let registers = Registers::new();
+    // 105 µs and 27 µs, at 8 periods of a 1.76064 MHz clock per machine cycle.
+    assert_eq!(vip_cycles(0x1200, &registers), 23);
+    assert_eq!(vip_cycles(0x6001, &registers), 6);
+}
+
+#[test]
+fn timing_survives_save_states() {
+    let rom = std::fs::read("roms/Trip8_Demo_(2008)_[Revival_Studios].ch8").unwrap();
+    let mut chip = vip_chip(&rom);
+    chip.set_seed(1);
+    for _ in 0..100 {
+        chip.cpu_cycle();
+    }
+    chip.step();
+    let state = chip.save_state();
+
+    let mut restored = vip_chip(&rom);
+    restored.load_state(&state).unwrap();
+    for _ in 0..100 {
+        chip.cpu_cycle();
+        restored.cpu_cycle();
+    }
+    assert_eq!(chip.save_state(), restored.save_state());
+}
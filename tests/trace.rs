@@ -0,0 +1,126 @@
+//! Traces a program's instructions by category into a ring buffer and a
+//! JSON-lines sink.
+
+use chirp_8::emulator::{Chip, Quirks};
+use chirp_8::trace::{Category, JsonLinesSink, RingBuffer, TraceRecord, TraceSink, RECORD_SIZE};
+use std::convert::TryInto;
+
+/// LD V0, 5; loop: ADD V0, 1; LD I, 0x300; JP loop
+const PROGRAM: [u8; 8] = [0x60, 0x05, 0x70, 0x01, 0xA3, 0x00, 0x12, 0x02];
+
+/// Steps through `count` instructions, tracing the categories into a ring
+/// buffer of `capacity` records.
+fn trace(categories: Category, capacity: usize, count: usize) -> Vec<TraceRecord> {
+    let mut chip = Chip::new(Quirks::default());
+    chip.load_rom(&PROGRAM).unwrap();
+    chip.tracer_mut().set_categories(categories);
+    chip.tracer_mut().set_ring_buffer(capacity);
+    for _ in 0..count {
+        chip.step();
+    }
+    chip.tracer().ring_buffer().unwrap().records().collect()
+}
+
+#[test]
+fn parses_category_lists() {
+    assert_eq!("flow, alu".parse(), Ok(Category::FLOW | Category::ALU));
+    assert_eq!("all".parse(), Ok(Category::all()));
+    assert_eq!("off".parse(), Ok(Category::empty()));
+    assert!("flow,sound".parse::<Category>().is_err());
+    assert_eq!((Category::DRAW | Category::TIMERS).to_string(), "draw,timers");
+    assert_eq!(Category::empty().to_string(), "off");
+}
+
+#[test]
+fn keeps_only_the_enabled_categories() {
+    let pcs = |records: Vec<TraceRecord>| records.iter().map(|record| record.pc).collect::<Vec<_>>();
+
+    assert_eq!(pcs(trace(Category::all(), 16, 7)), [0x200, 0x202, 0x204, 0x206, 0x202, 0x204, 0x206]);
+    assert_eq!(pcs(trace(Category::ALU, 16, 7)), [0x200, 0x202, 0x202]);
+    assert_eq!(pcs(trace(Category::FLOW | Category::MEMORY, 16, 7)), [0x204, 0x206, 0x204, 0x206]);
+    assert!(trace(Category::DRAW, 16, 7).is_empty());
+    assert!(!Chip::new(Quirks::default()).tracer().is_enabled(Category::all()));
+}
+
+#[test]
+fn records_register_changes() {
+    let records = trace(Category::all(), 16, 3);
+    let deltas: Vec<Vec<_>> = records
+        .iter()
+        .map(|record| record.deltas().map(|delta| (delta.register.to_string(), delta.before, delta.after)).collect())
+        .collect();
+    assert_eq!(
+        deltas,
+        [
+            vec![("V0".to_string(), 0, 5)],
+            vec![("V0".to_string(), 5, 6)],
+            vec![("I".to_string(), 0, 0x300)]
+        ]
+    );
+    assert_eq!(records[2].category, Category::MEMORY);
+}
+
+#[test]
+fn ring_buffer_keeps_the_newest_records() {
+    let records = trace(Category::all(), 3, 7);
+    assert_eq!(records.iter().map(|record| record.pc).collect::<Vec<_>>(), [0x202, 0x204, 0x206]);
+
+    let mut buffer = RingBuffer::new(3);
+    for record in &trace(Category::all(), 16, 5) {
+        buffer.record(record);
+    }
+    assert_eq!(buffer.len(), 3);
+    let mut bytes = Vec::new();
+    buffer.write_to(&mut bytes).unwrap();
+    assert_eq!(bytes.len(), 3 * RECORD_SIZE);
+    let first = TraceRecord::from_bytes(bytes[..RECORD_SIZE].try_into().unwrap());
+    assert_eq!(first, buffer.records().next().unwrap());
+    assert_eq!(first.pc, 0x204);
+
+    buffer.clear();
+    assert!(buffer.is_empty());
+    RingBuffer::new(0).record(&first);
+}
+
+#[test]
+fn writes_json_lines() {
+    let mut output = Vec::new();
+    let mut sink = JsonLinesSink::new(&mut output);
+    for record in &trace(Category::all(), 16, 4) {
+        sink.record(record);
+    }
+    sink.flush();
+
+    let output = String::from_utf8(output).unwrap();
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(
+        lines,
+        [
+            r#"{"pc":512,"opcode":24581,"instruction":"LD   V0, 0x05","category":"alu","deltas":[{"register":"V0","before":0,"after":5}]}"#,
+            r#"{"pc":514,"opcode":28673,"instruction":"ADD  V0, 0x01","category":"alu","deltas":[{"register":"V0","before":5,"after":6}]}"#,
+            r#"{"pc":516,"opcode":41728,"instruction":"LD   I, 0x300","category":"memory","deltas":[{"register":"I","before":0,"after":768}]}"#,
+            r#"{"pc":518,"opcode":4610,"instruction":"JP   0x202","category":"flow","deltas":[]}"#,
+        ]
+    );
+}
+
+#[test]
+fn writes_invalid_instructions_and_notes_as_json() {
+    let mut chip = Chip::new(Quirks::default());
+    chip.load_rom(&[0xFF, 0xFF]).unwrap();
+    chip.tracer_mut().set_categories(Category::all());
+    chip.tracer_mut().set_ring_buffer(1);
+    chip.step();
+
+    let mut output = Vec::new();
+    let mut sink = JsonLinesSink::new(&mut output);
+    sink.record(&chip.tracer().ring_buffer().unwrap().records().next().unwrap());
+    sink.flush();
+
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(json["pc"], 512);
+    assert_eq!(json["opcode"], 0xFFFF);
+    assert!(json["instruction"].is_null());
+    assert_eq!(json["note"], "not a valid instruction");
+    assert_eq!(output.last(), Some(&b'\n'));
+}
@@ -0,0 +1,127 @@
+//! Checks the instructions XO-CHIP added, one at a time.
+
+use chirp_8::emulator::{Chip, Quirks};
+
+fn xo_chip(rom: &[u8]) -> Chip {
+    let mut chip = Chip::new(Quirks::XO_CHIP);
+    chip.load_rom(rom).unwrap();
+    chip
+}
+
+fn run(chip: &mut Chip, steps: usize) {
+    for _ in 0..steps {
+        chip.step();
+    }
+}
+
+/// The planes set at a pixel, one bit each.
+fn planes(chip: &Chip, x: usize, y: usize) -> u8 {
+    chip.display().pixels()[y * chip.display().width() + x]
+}
+
+/// Draws a row to the second plane at (0, 0), then a row to both planes at
+/// (2, 2), which reads the first plane's row and then the second's.
+const PLANE_PROGRAM: [u8; 20] = [
+    0xf2, 0x01, // PLANE 2
+    0xa2, 0x20, // LD I, 220
+    0xd0, 0x01, // DRW V0, V0, 1
+    0xf3, 0x01, // PLANE 3
+    0x61, 0x02, // LD V1, 2
+    0xd1, 0x11, // DRW V1, V1, 1
+    0xf1, 0x01, // PLANE 1
+    0x00, 0xe0, // CLS
+    0xf2, 0x01, // PLANE 2
+    0x00, 0xc1, // SCD 1
+];
+
+fn plane_program() -> Chip {
+    let mut rom = PLANE_PROGRAM.to_vec();
+    rom.extend([0; 12]);
+    rom.extend([0xff, 0x0f]);
+    xo_chip(&rom)
+}
+
+#[test]
+fn draws_to_the_selected_planes() {
+    let mut chip = plane_program();
+    run(&mut chip, 6);
+
+    assert!((0..8).all(|x| planes(&chip, x, 0) == 0b10));
+    assert_eq!(planes(&chip, 8, 0), 0);
+    let row: Vec<u8> = (2..10).map(|x| planes(&chip, x, 2)).collect();
+    assert_eq!(row, [0b01, 0b01, 0b01, 0b01, 0b11, 0b11, 0b11, 0b11]);
+    assert_eq!(chip.registers().v(0xF), 0);
+}
+
+#[test]
+fn clears_and_scrolls_only_the_selected_planes() {
+    let mut chip = plane_program();
+    run(&mut chip, 8);
+    assert!((0..8).all(|x| planes(&chip, x, 0) == 0b10));
+    let row: Vec<u8> = (2..10).map(|x| planes(&chip, x, 2)).collect();
+    assert_eq!(row, [0, 0, 0, 0, 0b10, 0b10, 0b10, 0b10]);
+
+    run(&mut chip, 2);
+    assert!((0..8).all(|x| planes(&chip, x, 0) == 0 && planes(&chip, x, 1) == 0b10));
+    assert!((6..10).all(|x| planes(&chip, x, 2) == 0 && planes(&chip, x, 3) == 0b10));
+}
+
+#[test]
+fn saves_and_loads_register_ranges_in_both_directions() {
+    let mut chip = xo_chip(&[
+        0xa3, 0x00, // LD I, 300
+        0x61, 0x01, // LD V1, 1
+        0x62, 0x02, // LD V2, 2
+        0x63, 0x03, // LD V3, 3
+        0x51, 0x32, // LD [I], V1-V3
+        0x53, 0x12, // LD [I], V3-V1
+        0x54, 0x63, // LD V4-V6, [I]
+        0x56, 0x43, // LD V6-V4, [I]
+    ]);
+
+    run(&mut chip, 5);
+    let memory = |chip: &Chip| [0x300, 0x301, 0x302].map(|address| chip.memory().peek(address));
+    assert_eq!(memory(&chip), [1, 2, 3]);
+    run(&mut chip, 1);
+    assert_eq!(memory(&chip), [3, 2, 1]);
+
+    let registers = |chip: &Chip| [4, 5, 6].map(|x| chip.registers().v(x));
+    run(&mut chip, 1);
+    assert_eq!(registers(&chip), [3, 2, 1]);
+    run(&mut chip, 1);
+    assert_eq!(registers(&chip), [1, 2, 3]);
+    assert_eq!(chip.registers().i(), 0x300);
+}
+
+#[test]
+fn loads_a_long_address_into_i() {
+    let mut chip = xo_chip(&[0xf0, 0x00, 0x12, 0x34]);
+    chip.step();
+    assert_eq!(chip.registers().i(), 0x1234);
+    assert_eq!(chip.registers().pc(), 0x204);
+}
+
+#[test]
+fn skips_the_whole_long_load() {
+    // SE V0, 0; LD I, long 1234; LD V1, 1
+    let mut chip = xo_chip(&[0x30, 0x00, 0xf0, 0x00, 0x12, 0x34, 0x61, 0x01]);
+    chip.step();
+    assert_eq!(chip.registers().pc(), 0x206);
+    chip.step();
+    assert_eq!((chip.registers().v(1), chip.registers().i()), (1, 0));
+}
+
+#[test]
+fn loads_the_audio_pattern_and_pitch() {
+    // LD I, 208; AUDIO; LD V0, 70; PITCH V0; pattern
+    let mut rom = vec![0xa2, 0x08, 0xf0, 0x02, 0x60, 0x70, 0xf0, 0x3a];
+    rom.extend(1..=16);
+    let mut chip = xo_chip(&rom);
+    assert_eq!(chip.audio_pattern(), None);
+
+    run(&mut chip, 4);
+    let expected: Vec<u8> = (1..=16).collect();
+    assert_eq!(chip.audio_pattern().map(|pattern| pattern.to_vec()), Some(expected));
+    assert_eq!(chip.pitch(), 0x70);
+    assert_eq!(chip.playback_rate(), 8000.0);
+}